@@ -0,0 +1,63 @@
+//! What `forget` saves by looking up the one known network it needs rather
+//! than every object, against a mock with many known networks:
+//! `cargo bench --bench lookup`.
+
+#[path = "../tests/mock/mod.rs"]
+mod mock;
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use iwd_playground::client::find_known_network;
+use iwd_playground::types::Ssid;
+use iwd_playground::IwdClient;
+
+use mock::{block_on, MockIwd, MockNetwork};
+
+const KNOWN_NETWORKS: usize = 1000;
+const ROUNDS: u32 = 20;
+
+/// The mean time `f` takes over [`ROUNDS`].
+async fn time<F, Fut>(mut f: F) -> Duration
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = ()>,
+{
+	f().await;
+	let start = Instant::now();
+	for _ in 0..ROUNDS {
+		f().await;
+	}
+	start.elapsed() / ROUNDS
+}
+
+fn main() {
+	let iwd = MockIwd {
+		networks: (0..KNOWN_NETWORKS)
+			.map(|i| MockNetwork::new(&format!("net-{i:04}"), "psk", -6000))
+			.map(MockNetwork::known)
+			.collect(),
+		connected: None,
+		..MockIwd::default()
+	};
+
+	block_on(async {
+		let (conn, _server) = iwd.connect().await.unwrap();
+		let client = IwdClient::new(conn).await.unwrap();
+
+		let all = time(|| async {
+			client.objects().await.unwrap();
+		})
+		.await;
+
+		let ssid = Ssid::Utf8(format!("net-{:04}", KNOWN_NETWORKS / 2));
+		let known = time(|| async {
+			find_known_network(&client, &ssid).await.unwrap().unwrap();
+		})
+		.await;
+
+		println!("{KNOWN_NETWORKS} known networks, mean of {ROUNDS} rounds:");
+		println!("GetManagedObjects                  {all:?}");
+		println!("GetAll at the known network's path {known:?}");
+	});
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use iwd_playground::objects::All;
+use libfuzzer_sys::fuzz_target;
+use zbus::zvariant::serialized::{Context, Data, Format};
+use zbus::zvariant::LE;
+
+fuzz_target!(|bytes: &[u8]| {
+	let ctxt = Context::new(Format::DBus, LE, 0);
+	let _ = Data::new(bytes, ctxt).deserialize::<All>();
+});
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use zbus::zvariant::{self, OwnedObjectPath};
+
+use crate::client::{traced, IwdClient};
+use crate::error::IwdError;
+use crate::proxy::StationProxy;
+
+pub type SignalLevelCallback = Box<dyn Fn(OwnedObjectPath, i16) + Send + Sync>;
+
+/// Server side of `net.connman.iwd.SignalLevelAgent`, calling back whenever
+/// the RSSI of the connected network crosses one of `thresholds`.
+pub struct SignalLevelAgent {
+	/// In dBm, strongest first.
+	thresholds: Vec<i16>,
+	callback: SignalLevelCallback,
+}
+
+impl SignalLevelAgent {
+	/// `cb` gets the station's path and the threshold the RSSI is now at or
+	/// above, or `i16::MIN` when it's below all of them.
+	pub fn new(
+		mut thresholds: Vec<i16>,
+		cb: impl Fn(OwnedObjectPath, i16) + Send + Sync + 'static,
+	) -> Self {
+		// iwd wants them in strictly descending order
+		thresholds.sort_unstable_by(|a, b| b.cmp(a));
+		thresholds.dedup();
+		SignalLevelAgent {
+			thresholds,
+			callback: Box::new(cb),
+		}
+	}
+
+	pub fn thresholds(&self) -> &[i16] {
+		&self.thresholds
+	}
+
+	/// Serves the agent at `path` and registers it with `station`.
+	///
+	/// Fails with [`IwdError::PathInUse`] if an agent is served there
+	/// already, e.g. one never [unregistered](Self::unregister). If iwd
+	/// refuses it, the agent isn't served either.
+	pub async fn register(
+		self,
+		client: &IwdClient,
+		station: &StationProxy<'_>,
+		path: zvariant::ObjectPath<'_>,
+	) -> Result<(), IwdError> {
+		let thresholds = self.thresholds.clone();
+		let server = client.connection().object_server();
+		if !server.at(&path, self).await? {
+			return Err(IwdError::PathInUse(path.into()));
+		}
+		let res = traced(
+			client.timeouts(),
+			"RegisterSignalLevelAgent",
+			station.inner().path(),
+			station.register_signal_level_agent(&path, &thresholds),
+		)
+		.await;
+		if res.is_err() {
+			server.remove::<SignalLevelAgent, _>(&path).await?;
+		}
+		res
+	}
+
+	/// Unregisters the agent at `path` from `station` and stops serving it,
+	/// which it does even if iwd is gone and the call fails.
+	pub async fn unregister(
+		client: &IwdClient,
+		station: &StationProxy<'_>,
+		path: zvariant::ObjectPath<'_>,
+	) -> Result<(), IwdError> {
+		let res = traced(
+			client.timeouts(),
+			"UnregisterSignalLevelAgent",
+			station.inner().path(),
+			station.unregister_signal_level_agent(&path),
+		)
+		.await;
+		let server = client.connection().object_server();
+		server.remove::<SignalLevelAgent, _>(&path).await?;
+		res
+	}
+}
+
+impl fmt::Debug for SignalLevelAgent {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SignalLevelAgent")
+			.field("thresholds", &self.thresholds)
+			.finish_non_exhaustive()
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.SignalLevelAgent")]
+impl SignalLevelAgent {
+	fn release(&self, path: OwnedObjectPath) {
+		tracing::debug!(?path, "signal level agent released");
+	}
+
+	/// `level` is the index of the range the RSSI is now in: 0 is above the
+	/// first threshold, N is below the last of the N thresholds.
+	fn changed(&self, path: OwnedObjectPath, level: u8) {
+		tracing::debug!(?path, level, "signal level changed");
+		let threshold = self
+			.thresholds
+			.get(usize::from(level))
+			.copied()
+			.unwrap_or(i16::MIN);
+		(self.callback)(path, threshold);
+	}
+}
+
+/// IPv4 settings exchanged with a [`NetworkConfigurationAgent`].
+#[derive(
+	Clone,
+	Debug,
+	zvariant::DeserializeDict,
+	zvariant::SerializeDict,
+	zvariant::Type,
+)]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct Ipv4Config {
+	/// `"static"` or `"dhcp"`.
+	pub method: String,
+	pub address: Option<String>,
+	pub netmask: Option<String>,
+	pub gateway: Option<String>,
+	pub broadcast: Option<String>,
+	pub domain_name_servers: Option<Vec<String>>,
+}
+
+/// Server side of `net.connman.iwd.NetworkConfigurationAgent`.
+///
+/// iwd only consults this agent when it was built with
+/// `--enable-network-config` and `EnableNetworkConfiguration` is turned off
+/// in `main.conf`, so that IP configuration is delegated to the client.
+/// `ConfigureIPv4`/`ConfigureIPv6` get iwd's proposed settings and answer
+/// with the ones to apply: the configured overrides if there are any, the
+/// proposal otherwise.
+#[derive(Default, Debug)]
+pub struct NetworkConfigurationAgent {
+	/// Answer to `ConfigureIPv4` in place of iwd's proposal.
+	pub ipv4: Option<Ipv4Config>,
+	/// Answer to `ConfigureIPv6` in place of iwd's proposal.
+	pub ipv6: Option<HashMap<String, zvariant::OwnedValue>>,
+}
+
+#[zbus::interface(name = "net.connman.iwd.NetworkConfigurationAgent")]
+impl NetworkConfigurationAgent {
+	fn release(&self) {}
+
+	#[zbus(name = "ConfigureIPv4")]
+	fn configure_ipv4(
+		&self,
+		_device: OwnedObjectPath,
+		config: Ipv4Config,
+	) -> Ipv4Config {
+		self.ipv4.clone().unwrap_or(config)
+	}
+
+	#[zbus(name = "ConfigureIPv6")]
+	fn configure_ipv6(
+		&self,
+		_device: OwnedObjectPath,
+		config: HashMap<String, zvariant::OwnedValue>,
+	) -> zbus::fdo::Result<HashMap<String, zvariant::OwnedValue>> {
+		match &self.ipv6 {
+			Some(ipv6) => ipv6
+				.iter()
+				.map(|(k, v)| Ok((k.clone(), v.try_clone()?)))
+				.collect::<zvariant::Result<_>>()
+				.map_err(|e| zbus::fdo::Error::Failed(e.to_string())),
+			None => Ok(config),
+		}
+	}
+
+	fn cancel_configuration(&self, _device: OwnedObjectPath, _reason: String) {}
+}
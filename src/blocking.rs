@@ -0,0 +1,137 @@
+//! A blocking [`IwdClient`], for synchronous code such as a status bar
+//! script; built with the `blocking` feature.
+//!
+//! Calls here don't go through [`traced`](crate::client::traced), so the
+//! [`CallTimeouts`] don't apply, but for [`IwdClient::scan`].
+
+use zbus::blocking::Connection;
+use zbus::names::BusName;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::bus::{Bus, DEFAULT_SERVICE};
+use crate::client::{
+	add_bands, ordered, wait_for_scan, CallTimeouts, NetworkRow,
+};
+use crate::error::IwdError;
+use crate::objects::IwdObjectMap;
+use crate::proxy::{
+	NetworkProxyBlocking, ObjectManagerProxyBlocking, StationProxy,
+	StationProxyBlocking,
+};
+use crate::types::{Network, Station};
+
+/// The blocking counterpart of [`crate::IwdClient`].
+#[derive(Clone, Debug)]
+pub struct IwdClient {
+	conn: Connection,
+	manager: ObjectManagerProxyBlocking<'static>,
+	service: BusName<'static>,
+}
+
+impl IwdClient {
+	/// Talks to iwd on the system bus.
+	pub fn system() -> Result<Self, IwdError> {
+		Self::new(Connection::system()?)
+	}
+
+	/// Talks to iwd on `bus`, under [`DEFAULT_SERVICE`].
+	pub fn on(bus: &Bus) -> Result<Self, IwdError> {
+		Self::new(bus.connect_blocking()?)
+	}
+
+	pub fn new(conn: Connection) -> Result<Self, IwdError> {
+		Self::with_service(conn, DEFAULT_SERVICE)
+	}
+
+	/// See [`crate::IwdClient::with_service`].
+	pub fn with_service(
+		conn: Connection,
+		service: &str,
+	) -> Result<Self, IwdError> {
+		let service =
+			BusName::try_from(service.to_owned()).map_err(zbus::Error::from)?;
+		let manager =
+			ObjectManagerProxyBlocking::new(&conn, service.clone(), "/")?;
+		Ok(IwdClient {
+			conn,
+			manager,
+			service,
+		})
+	}
+
+	pub fn connection(&self) -> &Connection {
+		&self.conn
+	}
+
+	/// Every object iwd manages, with its properties.
+	pub fn objects(&self) -> Result<IwdObjectMap, IwdError> {
+		self.manager
+			.get_managed_objects()
+			.map_err(|err| IwdError::from(err).at_object_manager(&self.service))
+	}
+
+	pub fn station(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Result<StationProxyBlocking<'static>, IwdError> {
+		Ok(StationProxyBlocking::builder(&self.conn)
+			.destination(self.service.clone())?
+			.path(path.clone())?
+			.build()?)
+	}
+
+	/// The station of the device at `path`, and the network it's connected
+	/// to, if any.
+	pub fn status(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Result<(Station, Option<Network>), IwdError> {
+		let objects = self.objects()?;
+		let (_, station) =
+			objects.find_station_for_device(path).ok_or_else(|| {
+				IwdError::Failed(format!(
+					"{} is not in station mode",
+					path.as_str()
+				))
+			})?;
+		let network = station
+			.connected_network_info(&objects)
+			.map(|(_, network)| network.clone());
+		Ok((station.clone(), network))
+	}
+
+	/// Requests a scan with the station at `path` and waits for it to
+	/// finish, joining one that is already running; this one is
+	/// [`scan_and_wait`](crate::client::scan_and_wait), so it does time out,
+	/// after the [`DEFAULT`](CallTimeouts::DEFAULT) timeout for `Scan`.
+	pub fn scan(&self, path: &OwnedObjectPath) -> Result<(), IwdError> {
+		let station = self.station(path)?;
+		let station = StationProxy::from(station.inner().inner().clone());
+		zbus::block_on(wait_for_scan(&CallTimeouts::DEFAULT, &station))
+	}
+
+	/// Networks the station at `path` sees, best first, with their
+	/// properties from `objects`; those missing there are kept, see
+	/// [`NetworkRow::network`].
+	pub fn ordered_networks(
+		&self,
+		objects: &IwdObjectMap,
+		path: &OwnedObjectPath,
+	) -> Result<Vec<NetworkRow>, IwdError> {
+		let entries = self.station(path)?.get_ordered_networks()?;
+		let mut networks: Vec<_> =
+			ordered(entries.into_vec(), objects, path).collect();
+		add_bands(&mut networks, objects);
+		Ok(networks)
+	}
+
+	/// Connects to the network at `path`, asking the registered agent for
+	/// secrets if needed.
+	pub fn connect(&self, path: &OwnedObjectPath) -> Result<(), IwdError> {
+		let proxy = NetworkProxyBlocking::builder(&self.conn)
+			.destination(self.service.clone())?
+			.path(path.clone())?
+			.build()?;
+		Ok(proxy.connect()?)
+	}
+}
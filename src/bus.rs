@@ -0,0 +1,70 @@
+//! Where to find iwd: on which bus, and under which name, for an iwd in a
+//! network namespace with its own bus, or a mock one in tests.
+
+use std::str::FromStr;
+
+use zbus::Connection;
+
+use crate::error::IwdError;
+
+/// The name iwd owns on the bus, which [`IwdClient`] talks to unless told
+/// otherwise.
+///
+/// [`IwdClient`]: crate::IwdClient
+pub const DEFAULT_SERVICE: &str = "net.connman.iwd";
+
+/// The bus to connect to.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub enum Bus {
+	/// Where iwd normally is; `DBUS_SYSTEM_BUS_ADDRESS` still applies.
+	#[default]
+	System,
+	Session,
+	/// A D-Bus address, e.g. `unix:path=/run/netns-a/bus`.
+	Address(String),
+}
+
+impl Bus {
+	pub async fn connect(&self) -> Result<Connection, IwdError> {
+		Ok(match self {
+			Bus::System => Connection::system().await?,
+			Bus::Session => Connection::session().await?,
+			Bus::Address(addr) => {
+				zbus::connection::Builder::address(addr.as_str())?
+					.build()
+					.await?
+			}
+		})
+	}
+
+	#[cfg(feature = "blocking")]
+	pub fn connect_blocking(
+		&self,
+	) -> Result<zbus::blocking::Connection, IwdError> {
+		use zbus::blocking::{connection, Connection};
+
+		Ok(match self {
+			Bus::System => Connection::system()?,
+			Bus::Session => Connection::session()?,
+			Bus::Address(addr) => {
+				connection::Builder::address(addr.as_str())?.build()?
+			}
+		})
+	}
+}
+
+/// `system`, `session`, or else an address.
+impl FromStr for Bus {
+	type Err = IwdError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"system" => Ok(Bus::System),
+			"session" => Ok(Bus::Session),
+			addr if addr.contains(':') => Ok(Bus::Address(addr.to_owned())),
+			_ => Err(IwdError::InvalidArguments(format!(
+				"{s:?} is neither system, session nor a bus address"
+			))),
+		}
+	}
+}
@@ -0,0 +1,174 @@
+//! Escaping of what iwd reports, which is up to whoever runs the access
+//! point, for the places it is printed to.
+
+use std::borrow::Cow;
+
+/// Makes an SSID safe to print to a terminal.
+///
+/// SSIDs come from whoever runs the access point, so they may carry escape
+/// sequences or bidi overrides. C0 and C1 control characters are replaced by
+/// `\x1b`-style escapes and bidi controls by `\u{202e}`-style ones, leaving
+/// them visible but inert. Anything else is left alone.
+pub(crate) fn sanitize_ssid(ssid: &str) -> Cow<'_, str> {
+	fn is_bidi_control(c: char) -> bool {
+		matches!(
+			c,
+			'\u{061c}'
+				| '\u{200e}'
+				| '\u{200f}'
+				| '\u{202a}'..='\u{202e}'
+				| '\u{2066}'..='\u{2069}'
+		)
+	}
+
+	if !ssid.chars().any(|c| c.is_control() || is_bidi_control(c)) {
+		return Cow::Borrowed(ssid);
+	}
+
+	let mut out = String::with_capacity(ssid.len() + 8);
+	for c in ssid.chars() {
+		if c.is_ascii_control() {
+			out.push_str(&format!("\\x{:02x}", c as u32));
+		} else if c.is_control() || is_bidi_control(c) {
+			out.push_str(&format!("\\u{{{:04x}}}", c as u32));
+		} else {
+			out.push(c);
+		}
+	}
+	Cow::Owned(out)
+}
+
+/// Escapes the characters Pango markup gives meaning to.
+pub(crate) fn escape_markup(s: &str) -> Cow<'_, str> {
+	if !s.contains(['&', '<', '>']) {
+		return Cow::Borrowed(s);
+	}
+	let mut out = String::with_capacity(s.len() + 8);
+	for c in s.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			c => out.push(c),
+		}
+	}
+	Cow::Owned(out)
+}
+
+/// Backslash-escapes `\`, tab, newline and carriage return so a porcelain
+/// field never contains a separator. Reversed by [`unescape_field`].
+pub(crate) fn escape_field(s: &str) -> Cow<'_, str> {
+	if !s.contains(['\\', '\t', '\n', '\r']) {
+		return Cow::Borrowed(s);
+	}
+
+	let mut out = String::with_capacity(s.len() + 2);
+	for c in s.chars() {
+		match c {
+			'\\' => out.push_str("\\\\"),
+			'\t' => out.push_str("\\t"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			c => out.push(c),
+		}
+	}
+	Cow::Owned(out)
+}
+
+/// Inverse of [`escape_field`]. Returns `None` on a dangling or unknown
+/// escape.
+pub(crate) fn unescape_field(s: &str) -> Option<String> {
+	let mut out = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+		out.push(match chars.next()? {
+			'\\' => '\\',
+			't' => '\t',
+			'n' => '\n',
+			'r' => '\r',
+			_ => return None,
+		});
+	}
+	Some(out)
+}
+
+/// Escapes a Prometheus label value.
+pub(crate) fn escape_label(s: &str) -> Cow<'_, str> {
+	if !s.contains(['\\', '"', '\n']) {
+		return Cow::Borrowed(s);
+	}
+	Cow::Owned(
+		s.replace('\\', "\\\\")
+			.replace('"', "\\\"")
+			.replace('\n', "\\n"),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn printable_ssids_stay_borrowed() {
+		for ssid in ["home", "café", "日本語", "😀 e\u{308}x", ""] {
+			assert!(
+				matches!(sanitize_ssid(ssid), Cow::Borrowed(s) if s == ssid)
+			);
+		}
+	}
+
+	#[test]
+	fn control_characters_are_escaped() {
+		let cases = [
+			// an OSC sequence setting the terminal title
+			("\x1b]0;owned\x07", "\\x1b]0;owned\\x07"),
+			("tab\there\n", "tab\\x09here\\x0a"),
+			("\0", "\\x00"),
+			("del\x7f", "del\\x7f"),
+			// C1: CSI and NEL
+			("\u{9b}31m", "\\u{009b}31m"),
+			("a\u{85}b", "a\\u{0085}b"),
+		];
+		for (ssid, sanitized) in cases {
+			assert_eq!(sanitize_ssid(ssid), sanitized, "{ssid:?}");
+		}
+	}
+
+	#[test]
+	fn bidi_controls_are_escaped() {
+		let cases = [
+			("evil\u{202e}gpj.exe", "evil\\u{202e}gpj.exe"),
+			("\u{2066}a\u{2069}", "\\u{2066}a\\u{2069}"),
+			("\u{200f}\u{61c}", "\\u{200f}\\u{061c}"),
+		];
+		for (ssid, sanitized) in cases {
+			assert_eq!(sanitize_ssid(ssid), sanitized, "{ssid:?}");
+		}
+	}
+
+	#[test]
+	fn fields_round_trip() {
+		for field in ["plain", "a\tb", "two\nlines\r", "back\\slash", "\\t"] {
+			let escaped = escape_field(field);
+			assert!(!escaped.contains(['\t', '\n', '\r']));
+			assert_eq!(unescape_field(&escaped).as_deref(), Some(field));
+		}
+	}
+
+	#[test]
+	fn bad_escapes_dont_unescape() {
+		assert_eq!(unescape_field("dangling\\"), None);
+		assert_eq!(unescape_field("\\x1b"), None);
+	}
+
+	#[test]
+	fn markup_and_labels() {
+		assert!(matches!(escape_markup("café"), Cow::Borrowed(_)));
+		assert_eq!(escape_markup("<b>&</b>"), "&lt;b&gt;&amp;&lt;/b&gt;");
+		assert_eq!(escape_label("say \"hi\"\\\n"), "say \\\"hi\\\"\\\\\\n");
+	}
+}
@@ -0,0 +1,334 @@
+//! The `metrics` command's Prometheus exporter, and the HTTP server it
+//! answers scrapes with.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_io::Async;
+use futures_lite::StreamExt;
+use iwd_playground::client::{join_all, station_diagnostics};
+use iwd_playground::opath::OPath;
+use iwd_playground::proxy::StationProxy;
+use iwd_playground::types::StationState;
+use iwd_playground::IwdClient;
+use zbus::zvariant::{self, OwnedObjectPath};
+
+use super::escape::escape_label;
+use crate::rt;
+
+/// Prometheus metrics for the `metrics` command, collected from iwd at most
+/// once per [`Exporter::MIN_INTERVAL`] however often they're scraped.
+pub(crate) struct Exporter {
+	client: IwdClient,
+	/// How long the last scan of each station took.
+	scan_durations: Mutex<HashMap<OwnedObjectPath, Duration>>,
+	/// The last rendered metrics, and when they were collected.
+	cache: async_lock::Mutex<Option<(Instant, String)>>,
+}
+
+impl Exporter {
+	const MIN_INTERVAL: Duration = Duration::from_secs(5);
+	pub(crate) const DEFAULT_LISTEN: &str = "127.0.0.1:9477";
+
+	pub(crate) fn new(client: IwdClient) -> Self {
+		Exporter {
+			client,
+			scan_durations: Mutex::default(),
+			cache: async_lock::Mutex::default(),
+		}
+	}
+
+	async fn render(&self) -> String {
+		let mut cache = self.cache.lock().await;
+		if let Some((at, text)) = &*cache {
+			if at.elapsed() < Self::MIN_INTERVAL {
+				return text.clone();
+			}
+		}
+
+		let text = self.collect().await;
+		*cache = Some((Instant::now(), text.clone()));
+		text
+	}
+
+	async fn collect(&self) -> String {
+		let mut out = String::new();
+		let objects = self.client.objects().await;
+		metric(&mut out, "iwd_up", "gauge", "Whether iwd answers.");
+		sample(&mut out, "iwd_up", &[], u8::from(objects.is_ok()));
+		let objects = match objects {
+			Ok(objects) => objects,
+			Err(err) => {
+				tracing::warn!(%err, "can't collect metrics");
+				return out;
+			}
+		};
+
+		let mut stations: Vec<_> = objects
+			.devices()
+			.filter_map(|(path, d)| {
+				let (_, s) = objects.find_station_for_device(path)?;
+				Some((path, d.name(), s))
+			})
+			.collect();
+		stations.sort_by_key(|(_, name, _)| *name);
+
+		metric(
+			&mut out,
+			"iwd_station_state",
+			"gauge",
+			"State of the station, 1 for the current one.",
+		);
+		for (_, device, s) in &stations {
+			for state in [
+				StationState::Connected,
+				StationState::Disconnected,
+				StationState::Connecting,
+				StationState::Disconnecting,
+				StationState::Roaming,
+			] {
+				sample(
+					&mut out,
+					"iwd_station_state",
+					&[("device", device), ("state", state.as_str())],
+					u8::from(s.state() == state),
+				);
+			}
+		}
+
+		metric(
+			&mut out,
+			"iwd_station_scanning",
+			"gauge",
+			"Whether the station is scanning.",
+		);
+		for (_, device, s) in &stations {
+			let labels = [("device", *device)];
+			let scanning = u8::from(s.is_scanning());
+			sample(&mut out, "iwd_station_scanning", &labels, scanning);
+		}
+
+		metric(
+			&mut out,
+			"iwd_connected_network_info",
+			"gauge",
+			"The network the station is connected to.",
+		);
+		for (_, device, s) in &stations {
+			let Some(network) = s
+				.connected_network()
+				.and_then(|connected| objects.get_network(connected))
+			else {
+				continue;
+			};
+			let ssid = network.name().to_str_lossy();
+			let labels = [
+				("device", *device),
+				("ssid", &ssid),
+				("type", network.network_type().as_str()),
+			];
+			sample(&mut out, "iwd_connected_network_info", &labels, 1);
+		}
+
+		metric(
+			&mut out,
+			"iwd_visible_networks",
+			"gauge",
+			"Networks the station saw in its last scan.",
+		);
+		for (path, device, _) in &stations {
+			let labels = [("device", *device)];
+			let count = objects.networks_for_device(path).count();
+			sample(&mut out, "iwd_visible_networks", &labels, count);
+		}
+
+		// iwd reports bitrates in units of 100 kbit/s
+		let gauges = [
+			(
+				"iwd_rssi_dbm",
+				"RSSI",
+				1.0,
+				"Signal strength of the connection.",
+			),
+			(
+				"iwd_rx_bitrate_bits_per_second",
+				"RxBitrate",
+				100e3,
+				"Bitrate of the last received frame.",
+			),
+			(
+				"iwd_tx_bitrate_bits_per_second",
+				"TxBitrate",
+				100e3,
+				"Bitrate of the last sent frame.",
+			),
+		];
+		let connected = stations
+			.iter()
+			.filter(|(_, _, s)| s.connected_network().is_some());
+		let diagnostics: Vec<_> =
+			join_all(connected.map(|(path, device, _)| {
+				let client = &self.client;
+				async move {
+					Some((*device, station_diagnostics(client, path).await?))
+				}
+			}))
+			.await
+			.into_iter()
+			.flatten()
+			.collect();
+		for (name, key, scale, help) in gauges {
+			metric(&mut out, name, "gauge", help);
+			for (device, d) in &diagnostics {
+				let value = match d.get(key).map(|v| &**v) {
+					Some(zvariant::Value::I16(n)) => f64::from(*n),
+					Some(zvariant::Value::U32(n)) => f64::from(*n),
+					_ => continue,
+				};
+				sample(&mut out, name, &[("device", device)], value * scale);
+			}
+		}
+
+		metric(
+			&mut out,
+			"iwd_last_scan_duration_seconds",
+			"gauge",
+			"How long the last scan seen took.",
+		);
+		let durations = self
+			.scan_durations
+			.lock()
+			.unwrap_or_else(|e| e.into_inner());
+		for (path, device, _) in &stations {
+			if let Some(duration) = durations.get(*path) {
+				sample(
+					&mut out,
+					"iwd_last_scan_duration_seconds",
+					&[("device", device)],
+					duration.as_secs_f64(),
+				);
+			}
+		}
+
+		out
+	}
+
+	/// Times the scans of the station at `path`, for as long as it exists.
+	pub(crate) async fn watch_scans(self: Arc<Self>, path: OwnedObjectPath) {
+		let station = match OPath::<StationProxy>::from(path.clone())
+			.proxy(&self.client)
+			.await
+		{
+			Ok(station) => station,
+			Err(err) => {
+				tracing::warn!(%err, %path, "can't time scans");
+				return;
+			}
+		};
+		let mut changes = station.receive_scanning_changed().await;
+		let mut started = None;
+		while let Some(change) = changes.next().await {
+			match change.get().await {
+				Ok(true) => started = Some(Instant::now()),
+				Ok(false) => {
+					if let Some(started) = started.take() {
+						self.scan_durations
+							.lock()
+							.unwrap_or_else(|e| e.into_inner())
+							.insert(path.clone(), started.elapsed());
+					}
+				}
+				Err(_) => {}
+			}
+		}
+	}
+}
+
+/// Writes the `HELP` and `TYPE` lines introducing a metric.
+fn metric(out: &mut String, name: &str, ty: &str, help: &str) {
+	use std::fmt::Write;
+
+	let _ = writeln!(out, "# HELP {name} {help}");
+	let _ = writeln!(out, "# TYPE {name} {ty}");
+}
+
+/// Writes a sample line of a metric.
+fn sample(
+	out: &mut String,
+	name: &str,
+	labels: &[(&str, &str)],
+	value: impl fmt::Display,
+) {
+	use std::fmt::Write;
+
+	out.push_str(name);
+	if !labels.is_empty() {
+		let labels: Vec<_> = labels
+			.iter()
+			.map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+			.collect();
+		let _ = write!(out, "{{{}}}", labels.join(","));
+	}
+	let _ = writeln!(out, " {value}");
+}
+
+/// Accepts connections for [`Exporter`] until cancelled.
+pub(crate) async fn serve_metrics(
+	listener: Async<std::net::TcpListener>,
+	exporter: Arc<Exporter>,
+) {
+	loop {
+		let stream = match listener.accept().await {
+			Ok((stream, _)) => stream,
+			Err(err) => {
+				tracing::warn!(%err, "failed to accept");
+				// e.g. out of file descriptors, which won't clear right away
+				async_io::Timer::after(Duration::from_millis(100)).await;
+				continue;
+			}
+		};
+		let exporter = exporter.clone();
+		rt::spawn(async move {
+			if let Err(err) = respond(stream, &exporter).await {
+				tracing::debug!(%err, "metrics request failed");
+			}
+		});
+	}
+}
+
+/// Answers a single HTTP request, closing the connection afterwards.
+async fn respond(
+	stream: Async<std::net::TcpStream>,
+	exporter: &Exporter,
+) -> std::io::Result<()> {
+	use futures_lite::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+	let mut reader = futures_lite::io::BufReader::new((&stream).take(8192));
+	let mut request = String::new();
+	reader.read_line(&mut request).await?;
+	// the headers don't matter
+	loop {
+		let mut line = String::new();
+		if reader.read_line(&mut line).await? == 0 || line.trim_end().is_empty()
+		{
+			break;
+		}
+	}
+
+	let target: Vec<_> = request.split_whitespace().take(2).collect();
+	let (status, body) = match target[..] {
+		["GET", "/metrics"] => ("200 OK", exporter.render().await),
+		["GET", _] => ("404 Not Found", "Not found\n".to_owned()),
+		_ => ("405 Method Not Allowed", "Method not allowed\n".to_owned()),
+	};
+	let response = format!(
+		"HTTP/1.1 {status}\r\n\
+		Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+		Content-Length: {}\r\n\
+		Connection: close\r\n\r\n{body}",
+		body.len(),
+	);
+	(&stream).write_all(response.as_bytes()).await
+}
@@ -0,0 +1,8 @@
+//! The commands and their output, kept apart from the command line.
+
+pub(crate) mod escape;
+pub(crate) mod metrics;
+pub(crate) mod render;
+pub(crate) mod session;
+#[cfg(feature = "tui")]
+pub(crate) mod tui;
@@ -0,0 +1,1099 @@
+//! How the commands print what they found, in each [`Format`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use iwd_playground::client::NetworkRow;
+use iwd_playground::types::{
+	Adapter, DeviceMode, Network, NetworkType, P2pDisplay, SignalStrength,
+	Ssid, StationState, WifiBand,
+};
+use serde::Serialize;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use zbus::zvariant::OwnedObjectPath;
+
+use super::escape::{escape_field, escape_markup, sanitize_ssid};
+use crate::{
+	json_line, Config, Format, JsonAdapter, JsonBss, JsonKnownNetwork,
+	JsonPeer, JsonReport, JsonStatus,
+};
+
+/// How to show SSIDs in human-readable output.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum SsidFormat {
+	/// As text, with control characters escaped by [`sanitize_ssid`].
+	#[default]
+	Text,
+	/// As hex bytes, followed by the text in parentheses if it's UTF-8.
+	Hex,
+}
+
+impl SsidFormat {
+	pub(crate) fn render(self, ssid: &Ssid) -> String {
+		match (self, ssid.as_str()) {
+			(SsidFormat::Text, _) => {
+				sanitize_ssid(&ssid.to_str_lossy()).into_owned()
+			}
+			(SsidFormat::Hex, Some(s)) => {
+				format!("{} ({})", ssid.to_hex(), sanitize_ssid(s))
+			}
+			(SsidFormat::Hex, None) => ssid.to_hex(),
+		}
+	}
+}
+
+/// How to show signal strength in human-readable output.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum SignalFormat {
+	#[default]
+	Dbm,
+	Percent,
+	Bars,
+}
+
+impl SignalFormat {
+	pub(crate) fn render(self, strength: SignalStrength) -> String {
+		match self {
+			SignalFormat::Dbm => strength.to_string(),
+			SignalFormat::Percent => format!("{}%", strength.percent()),
+			SignalFormat::Bars => strength.meter().to_owned(),
+		}
+	}
+}
+
+/// When to color human-readable output.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum ColorChoice {
+	/// Only if stdout is a terminal and `NO_COLOR` isn't set.
+	#[default]
+	Auto,
+	Always,
+	Never,
+}
+
+impl ColorChoice {
+	fn enabled(self) -> bool {
+		use std::io::IsTerminal;
+
+		match self {
+			ColorChoice::Always => true,
+			ColorChoice::Never => false,
+			ColorChoice::Auto => {
+				std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+					&& std::io::stdout().is_terminal()
+			}
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum Style {
+	#[default]
+	Plain,
+	Green,
+	Yellow,
+	Dim,
+}
+
+impl Style {
+	fn sgr(self) -> Option<&'static str> {
+		match self {
+			Style::Plain => None,
+			Style::Green => Some("32"),
+			Style::Yellow => Some("33"),
+			Style::Dim => Some("2"),
+		}
+	}
+}
+
+/// Table cell: the text and how to highlight it when color is enabled.
+#[derive(Debug)]
+struct Cell {
+	text: String,
+	style: Style,
+}
+
+impl Cell {
+	fn styled(text: impl Into<String>, style: Style) -> Self {
+		Cell {
+			text: text.into(),
+			style,
+		}
+	}
+}
+
+impl From<String> for Cell {
+	fn from(text: String) -> Self {
+		Cell::styled(text, Style::Plain)
+	}
+}
+
+impl From<&str> for Cell {
+	fn from(text: &str) -> Self {
+		Cell::styled(text, Style::Plain)
+	}
+}
+
+/// Signal strength below which it is highlighted as weak.
+const WEAK_SIGNAL: SignalStrength = SignalStrength::from_dbm(-70);
+
+/// Plain-text table with every column padded to its widest cell.
+///
+/// Widths are measured in terminal columns rather than bytes, so wide
+/// characters in SSIDs don't break the alignment. Escape codes are only
+/// emitted here, and only when enabled with [`Table::color`].
+pub(crate) struct Table {
+	headers: Vec<Cell>,
+	limits: Vec<Option<usize>>,
+	rows: Vec<Vec<Cell>>,
+	color: bool,
+}
+
+impl Table {
+	fn new(headers: &[&str]) -> Self {
+		Table {
+			headers: headers.iter().map(|&h| h.into()).collect(),
+			limits: vec![None; headers.len()],
+			rows: Vec::new(),
+			color: false,
+		}
+	}
+
+	/// Cuts cells of column `col` wider than `width` short with an ellipsis.
+	fn limit(mut self, col: usize, width: usize) -> Self {
+		self.limits[col] = Some(width);
+		self
+	}
+
+	fn color(mut self, enabled: bool) -> Self {
+		self.color = enabled;
+		self
+	}
+
+	fn push(&mut self, row: Vec<Cell>) {
+		debug_assert_eq!(row.len(), self.headers.len());
+		self.rows.push(row);
+	}
+}
+
+fn truncate(s: &str, width: usize) -> Cow<'_, str> {
+	if s.width() <= width {
+		return Cow::Borrowed(s);
+	}
+
+	let mut out = String::new();
+	let mut used = 0;
+	for c in s.chars() {
+		let w = c.width().unwrap_or(0);
+		// leave a column for the ellipsis
+		if used + w >= width {
+			break;
+		}
+		used += w;
+		out.push(c);
+	}
+	out.push('…');
+	Cow::Owned(out)
+}
+
+impl fmt::Display for Table {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let rows: Vec<Vec<(Cow<str>, Style)>> = std::iter::once(&self.headers)
+			.chain(&self.rows)
+			.map(|row| {
+				row.iter()
+					.zip(&self.limits)
+					.map(|(cell, limit)| {
+						let text = match limit {
+							Some(width) => truncate(&cell.text, *width),
+							None => Cow::Borrowed(cell.text.as_str()),
+						};
+						(text, cell.style)
+					})
+					.collect()
+			})
+			.collect();
+
+		let mut widths = vec![0; self.headers.len()];
+		for row in &rows {
+			for (width, (text, _)) in widths.iter_mut().zip(row) {
+				*width = (*width).max(text.width());
+			}
+		}
+
+		let mut line = String::new();
+		for row in &rows {
+			line.clear();
+			for ((text, style), width) in row.iter().zip(&widths) {
+				match style.sgr() {
+					Some(sgr) if self.color && !text.is_empty() => {
+						line.push_str(&format!("\x1b[{sgr}m{text}\x1b[0m"));
+					}
+					_ => line.push_str(text),
+				}
+				let pad = width - text.width() + 2;
+				line.extend(std::iter::repeat_n(' ', pad));
+			}
+			writeln!(f, "{}", line.trim_end())?;
+		}
+		Ok(())
+	}
+}
+
+/// One line per network, fields separated by tabs, in this order:
+///
+/// 1. SSID
+/// 2. security type, as iwd names it (`open`, `psk`, `8021x`, ...)
+/// 3. signal strength in 1/100 dBm
+/// 4. `true` if there is a known network profile for it, `false` otherwise
+/// 5. `true` if connected, `false` otherwise
+/// 6. interface name of the device, empty if unknown
+/// 7. bands in GHz joined with `+`, e.g. `2.4+5`, empty if unknown
+/// 8. object path of the network
+///
+/// Fields are escaped with [`escape_field`]. The order is stable; new fields
+/// may only be inserted before the object path, which always comes last.
+pub(crate) fn porcelain_lines(
+	networks: &[NetworkRow],
+	device_names: &HashMap<OwnedObjectPath, String>,
+) -> String {
+	networks
+		.iter()
+		.map(|net| {
+			let device =
+				device_names.get(&net.device).map_or("", String::as_str);
+			format!(
+				"{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+				escape_field(
+					&net.ssid().map(Ssid::to_str_lossy).unwrap_or_default()
+				),
+				net.network_type().map_or("", |t| t.as_str()),
+				net.strength.centi_dbm(),
+				net.is_known(),
+				net.is_connected(),
+				escape_field(device),
+				WifiBand::joined(&net.bands),
+				net.path.path().as_str(),
+			)
+		})
+		.collect()
+}
+
+/// One line per network for an external picker, fields separated by tabs:
+///
+/// 1. SSID, made safe to show with [`sanitize_ssid`]
+/// 2. security, see [`NetworkType::security_label`]
+/// 3. signal strength, as `signal` renders it
+/// 4. `connected`, `known` or nothing
+/// 5. object path of the network
+///
+/// The object path is what [`Selection::parse`] reads back; the SSID only
+/// if the path was cut off.
+///
+/// [`Selection::parse`]: crate::Selection::parse
+pub(crate) fn menu_lines(
+	networks: &[NetworkRow],
+	signal: SignalFormat,
+) -> String {
+	networks
+		.iter()
+		.map(|net| {
+			format!(
+				"{}\t{}\t{}\t{}\t{}\n",
+				sanitize_ssid(
+					&net.ssid().map(Ssid::to_str_lossy).unwrap_or_default(),
+				),
+				net.network_type().map_or("", |t| t.security_label()),
+				signal.render(net.strength),
+				match (net.is_connected(), net.is_known()) {
+					(true, _) => "connected",
+					(false, true) => "known",
+					(false, false) => "",
+				},
+				net.path.path().as_str(),
+			)
+		})
+		.collect()
+}
+
+/// `list`'s output in `config.format`.
+pub(crate) fn render_list(
+	config: &Config,
+	report: &JsonReport,
+	device_names: &HashMap<OwnedObjectPath, String>,
+	hidden: &[(&str, usize)],
+) -> anyhow::Result<String> {
+	let table = match config.format {
+		Format::Json => return json_line(report),
+		Format::Porcelain => {
+			return Ok(porcelain_lines(&report.networks, device_names));
+		}
+		Format::Table => {
+			let mut table = Table::new(&[
+				"", "SSID", "SECURITY", "BAND", "SIGNAL", "KNOWN", "DEVICE",
+			])
+			.limit(1, 32)
+			.color(config.color.enabled());
+			for net in &report.networks {
+				let style = if net.is_connected() {
+					Style::Green
+				} else if !net.is_known()
+					|| net.network_type() == Some(NetworkType::Open)
+				{
+					Style::Dim
+				} else {
+					Style::Plain
+				};
+				let signal_style = if net.strength < WEAK_SIGNAL {
+					Style::Yellow
+				} else {
+					style
+				};
+				let device =
+					device_names.get(&net.device).cloned().unwrap_or_default();
+
+				table.push(vec![
+					Cell::styled(
+						if net.is_connected() { "*" } else { "" },
+						style,
+					),
+					Cell::styled(
+						match net.merged {
+							0 => net
+								.ssid()
+								.map(|s| config.ssid.render(s))
+								.unwrap_or_default(),
+							n => format!(
+								"{} (+{n})",
+								net.ssid()
+									.map(|s| config.ssid.render(s))
+									.unwrap_or_default()
+							),
+						},
+						style,
+					),
+					Cell::styled(
+						net.network_type().map_or("", |t| t.as_str()),
+						style,
+					),
+					Cell::styled(WifiBand::joined(&net.bands), style),
+					Cell::styled(net.strength.to_string(), signal_style),
+					Cell::styled(
+						if net.is_known() { "yes" } else { "" },
+						style,
+					),
+					Cell::styled(device, style),
+				]);
+			}
+			table
+		}
+	};
+
+	let mut out = table.to_string();
+	if !hidden.is_empty() {
+		let total: usize = hidden.iter().map(|(_, n)| n).sum();
+		let by: Vec<_> = hidden
+			.iter()
+			.map(|(filter, n)| format!("{n} by {filter}"))
+			.collect();
+		out += &format!("{total} networks hidden: {}\n", by.join(", "));
+	}
+	Ok(out)
+}
+
+/// `status`'s output in `config.format`; `network` is the connected one.
+pub(crate) fn render_status(
+	config: &Config,
+	status: &JsonStatus,
+	network: Option<&Network>,
+) -> anyhow::Result<String> {
+	let JsonStatus {
+		device,
+		path,
+		station: s,
+	} = status;
+	Ok(match config.format {
+		Format::Json => json_line(status)?,
+		Format::Porcelain => format!(
+			"{}\t{}\t{}\t{}\t{}\n",
+			escape_field(device),
+			s.state().as_str(),
+			s.is_scanning(),
+			escape_field(
+				&network.map(|n| n.name().to_str_lossy()).unwrap_or_default()
+			),
+			path.as_str(),
+		),
+		Format::Table => {
+			let mut out = format!(
+				"device    {device}\nstate     {}\n",
+				s.state().as_str()
+			);
+			if let Some(network) = network {
+				out += &format!(
+					"network   {} ({})\n",
+					config.ssid.render(network.name()),
+					network.network_type().as_str(),
+				);
+			}
+			if s.is_scanning() {
+				out += "scanning\n";
+			}
+			out
+		}
+	})
+}
+
+/// `known`'s output in `config.format`.
+pub(crate) fn render_known(
+	config: &Config,
+	known: &[JsonKnownNetwork],
+) -> anyhow::Result<String> {
+	Ok(match config.format {
+		Format::Json => json_line(&known)?,
+		Format::Porcelain => known
+			.iter()
+			.map(|known| {
+				let k = known.known_network;
+				format!(
+					"{}\t{}\t{}\t{}\t{}\t{}\n",
+					escape_field(&k.name().to_str_lossy()),
+					k.network_type().as_str(),
+					k.is_hidden(),
+					k.auto_connect(),
+					escape_field(k.last_connected_time()),
+					known.path.as_str(),
+				)
+			})
+			.collect(),
+		Format::Table => {
+			let mut table = Table::new(&[
+				"NAME",
+				"SECURITY",
+				"HIDDEN",
+				"AUTOCONNECT",
+				"LAST CONNECTED",
+			])
+			.limit(0, 32)
+			.color(config.color.enabled());
+			for JsonKnownNetwork {
+				known_network: k, ..
+			} in known
+			{
+				let yes = |b| if b { "yes" } else { "" };
+				table.push(vec![
+					config.ssid.render(k.name()).into(),
+					k.network_type().as_str().into(),
+					yes(k.is_hidden()).into(),
+					yes(k.auto_connect()).into(),
+					k.last_connected_time().into(),
+				]);
+			}
+			table.to_string()
+		}
+	})
+}
+
+/// `bss`' output in `config.format`.
+pub(crate) fn render_bss(
+	config: &Config,
+	bss: &[JsonBss],
+) -> anyhow::Result<String> {
+	let band = |b: &JsonBss| b.band.map_or("", WifiBand::as_str);
+	let frequency = |b: &JsonBss| {
+		b.bss
+			.frequency()
+			.map(|mhz| mhz.to_string())
+			.unwrap_or_default()
+	};
+
+	Ok(match config.format {
+		Format::Json => json_line(&bss)?,
+		Format::Porcelain => bss
+			.iter()
+			.map(|b| {
+				let rssi = b.bss.strength().map(|s| s.centi_dbm() / 100);
+				format!(
+					"{}\t{}\t{}\t{}\t{}\t{}\n",
+					b.bss.address(),
+					b.type_.as_str(),
+					band(b),
+					frequency(b),
+					rssi.map(|dbm| dbm.to_string()).unwrap_or_default(),
+					b.path.as_str(),
+				)
+			})
+			.collect(),
+		Format::Table => {
+			let mut table = Table::new(&[
+				"BSSID",
+				"SECURITY",
+				"BAND",
+				"FREQUENCY",
+				"SIGNAL",
+			])
+			.color(config.color.enabled());
+			for b in bss {
+				table.push(vec![
+					b.bss.address().to_string().into(),
+					b.type_.security_label().into(),
+					match b.band {
+						Some(band) => band.to_string().into(),
+						None => "".into(),
+					},
+					match b.bss.frequency() {
+						Some(mhz) => format!("{mhz} MHz").into(),
+						None => "".into(),
+					},
+					match b.bss.strength() {
+						Some(strength) => config.signal.render(strength).into(),
+						None => "".into(),
+					},
+				]);
+			}
+			table.to_string()
+		}
+	})
+}
+
+/// `peers`' output in `config.format`.
+pub(crate) fn render_peers(
+	config: &Config,
+	peers: &[JsonPeer],
+) -> anyhow::Result<String> {
+	Ok(match config.format {
+		Format::Json => json_line(&peers)?,
+		Format::Porcelain => peers
+			.iter()
+			.map(|p| {
+				format!(
+					"{}\t{}\t{}\t{}\t{}\n",
+					escape_field(p.peer.name()),
+					escape_field(p.peer.device_category().unwrap_or("")),
+					p.peer.is_connected(),
+					p.display.map(P2pDisplay::roles).unwrap_or_default(),
+					p.path.as_str(),
+				)
+			})
+			.collect(),
+		Format::Table => {
+			let mut table =
+				Table::new(&["NAME", "CATEGORY", "CONNECTED", "DISPLAY"])
+					.color(config.color.enabled());
+			for p in peers {
+				table.push(vec![
+					p.peer.name().into(),
+					p.peer.device_category().unwrap_or("").into(),
+					if p.peer.is_connected() { "yes" } else { "" }.into(),
+					p.display.map(wfd_label).unwrap_or_default().into(),
+				]);
+			}
+			table.to_string()
+		}
+	})
+}
+
+/// `peer-connect`'s output in `config.format`: the group it made.
+pub(crate) fn render_peer_connection(
+	config: &Config,
+	peer: &JsonPeer,
+) -> anyhow::Result<String> {
+	let p = peer.peer;
+	let interface = p.connected_interface().unwrap_or("");
+	let ip = p.connected_ip().unwrap_or("");
+	Ok(match config.format {
+		Format::Json => json_line(peer)?,
+		Format::Porcelain => format!(
+			"{}\t{}\t{}\t{}\n",
+			escape_field(p.name()),
+			escape_field(interface),
+			escape_field(ip),
+			peer.path.as_str(),
+		),
+		Format::Table => {
+			let mut out = format!(
+				"peer      {}\ninterface {interface}\nip        {ip}\n",
+				p.name()
+			);
+			if let Some(display) = peer.display {
+				out += &format!("display   {}", wfd_label(display));
+				if let Some(port) = display.port() {
+					out += &format!(", port {port}");
+				}
+				out += "\n";
+			}
+			out
+		}
+	})
+}
+
+/// What a Wi-Fi Display peer can do, e.g. `WFD sink`; `WFD` if it doesn't
+/// say.
+fn wfd_label(display: &P2pDisplay) -> String {
+	match display.roles().as_str() {
+		"" => "WFD".to_owned(),
+		roles => format!("WFD {roles}"),
+	}
+}
+
+/// `adapters`' output in `config.format`.
+pub(crate) fn render_adapters(
+	config: &Config,
+	adapters: &[(&OwnedObjectPath, &Adapter)],
+) -> anyhow::Result<String> {
+	let modes = |a: &Adapter| {
+		let modes: Vec<_> =
+			a.supported_modes().iter().map(DeviceMode::as_str).collect();
+		modes.join(",")
+	};
+
+	Ok(match config.format {
+		Format::Json => {
+			let adapters: Vec<_> = adapters
+				.iter()
+				.map(|&(path, adapter)| JsonAdapter { path, adapter })
+				.collect();
+			json_line(&adapters)?
+		}
+		Format::Porcelain => adapters
+			.iter()
+			.map(|(path, a)| {
+				format!(
+					"{}\t{}\t{}\t{}\t{}\t{}\n",
+					escape_field(a.name()),
+					a.is_powered(),
+					escape_field(a.vendor().unwrap_or_default()),
+					escape_field(a.model().unwrap_or_default()),
+					modes(a),
+					path.as_str(),
+				)
+			})
+			.collect(),
+		Format::Table => {
+			let mut table =
+				Table::new(&["NAME", "POWERED", "ADAPTER", "MODES"])
+					.limit(2, 48)
+					.color(config.color.enabled());
+			for (_, a) in adapters {
+				table.push(vec![
+					a.name().into(),
+					if a.is_powered() { "yes" } else { "no" }.into(),
+					a.description().into(),
+					modes(a).into(),
+				]);
+			}
+			table.to_string()
+		}
+	})
+}
+
+/// What a status bar shows about a station.
+#[derive(Debug)]
+pub(crate) struct LinkStatus {
+	/// Interface name of the device.
+	pub(crate) device: String,
+	pub(crate) state: StationState,
+	/// The connected network, if any.
+	pub(crate) connected: Option<NetworkRow>,
+	/// Visible networks, in iwd's order.
+	pub(crate) networks: Vec<NetworkRow>,
+}
+
+/// One update of a Waybar `custom` module with `return-type: json`.
+///
+/// Waybar reads `text` and `tooltip` as Pango markup.
+#[derive(Debug, Serialize)]
+pub(crate) struct WaybarLine {
+	text: String,
+	tooltip: String,
+	/// The station state, or `none` without a station.
+	class: &'static str,
+	/// Signal quality of the connected network, for `format-icons`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	percentage: Option<u8>,
+}
+
+impl WaybarLine {
+	pub(crate) fn new(status: Option<&LinkStatus>, ssid: SsidFormat) -> Self {
+		let Some(status) = status else {
+			return WaybarLine {
+				text: "no wifi".to_owned(),
+				tooltip: "No device in station mode".to_owned(),
+				class: "none",
+				percentage: None,
+			};
+		};
+
+		let text = match &status.connected {
+			Some(net) => {
+				format!(
+					"{} ({})",
+					net.ssid().map(|s| ssid.render(s)).unwrap_or_default(),
+					net.strength
+				)
+			}
+			None => status.state.as_str().to_owned(),
+		};
+		let mut tooltip =
+			format!("{}: {}", status.device, status.state.as_str());
+		for net in &status.networks {
+			tooltip.push_str(&format!(
+				"\n{} {} {} {}",
+				if net.is_connected() { "*" } else { " " },
+				net.strength.meter(),
+				net.ssid().map(|s| ssid.render(s)).unwrap_or_default(),
+				net.network_type().map_or("", |t| t.as_str()),
+			));
+		}
+
+		WaybarLine {
+			text: escape_markup(&text).into_owned(),
+			tooltip: escape_markup(&tooltip).into_owned(),
+			class: status.state.as_str(),
+			percentage: status
+				.connected
+				.as_ref()
+				.map(|net| net.strength.percent()),
+		}
+	}
+}
+
+/// `bar`'s line for `status`: `SSID (-63 dBm)`, or `down` when not
+/// connected.
+pub(crate) fn bar_line(config: &Config, status: Option<&LinkStatus>) -> String {
+	match status.and_then(|status| status.connected.as_ref()) {
+		Some(net) => format!(
+			"{}{} ({}){}",
+			config.prefix,
+			net.ssid()
+				.map(|s| config.ssid.render(s))
+				.unwrap_or_default(),
+			config.signal.render(net.strength),
+			config.suffix,
+		),
+		None => "down".to_owned(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use iwd_playground::types::{KnownNetwork, Station};
+	use serde::de::DeserializeOwned;
+	use zbus::zvariant::serialized::Context;
+	use zbus::zvariant::{self, ObjectPath, Value};
+
+	use super::*;
+	use crate::{JsonReport, JsonStatus};
+
+	#[test]
+	fn truncates_by_columns() {
+		assert_eq!(truncate("home", 4), "home");
+		assert_eq!(truncate("network", 4), "net…");
+		// each of these takes two columns
+		assert_eq!(truncate("日本語", 5), "日本…");
+	}
+
+	#[test]
+	fn tables_align_on_width() {
+		let mut table = Table::new(&["SSID", "SIGNAL"]).limit(0, 6);
+		table.push(vec!["日本".into(), "-54 dBm".into()]);
+		table.push(vec!["a long name".into(), "".into()]);
+		assert_eq!(
+			table.to_string(),
+			"SSID    SIGNAL\n日本    -54 dBm\na lon…\n"
+		);
+	}
+
+	#[test]
+	fn color_only_when_enabled() {
+		let mut table = Table::new(&["SSID"]).color(true);
+		table.push(vec![Cell::styled("home", Style::Green)]);
+		assert_eq!(table.to_string(), "SSID\n\x1b[32mhome\x1b[0m\n");
+	}
+
+	const DEVICE: &str = "/net/connman/iwd/0/4";
+	const ADAPTER: &str = "/net/connman/iwd/0";
+
+	/// `T` out of `properties`, the way iwd sends them.
+	fn from_properties<T>(properties: Vec<(&str, Value<'_>)>) -> T
+	where
+		T: DeserializeOwned + zvariant::Type,
+	{
+		let properties: HashMap<_, _> = properties.into_iter().collect();
+		let ctxt = Context::new_dbus(zvariant::LE, 0);
+		let data = zvariant::to_bytes(ctxt, &properties).unwrap();
+		data.deserialize().unwrap().0
+	}
+
+	fn path(path: String) -> OwnedObjectPath {
+		OwnedObjectPath::try_from(path).unwrap()
+	}
+
+	/// How iwd names the objects of a network, e.g. `686f6d65_psk`.
+	fn path_id(ssid: &str, type_: &str) -> String {
+		let hex: String = ssid.bytes().map(|b| format!("{b:02x}")).collect();
+		format!("{hex}_{type_}")
+	}
+
+	/// What the snapshots are rendered from: the station at [`DEVICE`],
+	/// called `wlan0`, and the adapter at [`ADAPTER`].
+	struct Scene {
+		station: Station,
+		networks: Vec<NetworkRow>,
+		known: Vec<(OwnedObjectPath, KnownNetwork)>,
+		adapter: Adapter,
+	}
+
+	impl Scene {
+		/// `networks` as (SSID, type, strength, known), best first, and
+		/// connected to the one called `connected`.
+		fn new(
+			networks: &[(&str, &str, i16, bool)],
+			connected: Option<&str>,
+		) -> Self {
+			let mut scene = Scene {
+				station: from_properties(vec![
+					(
+						"State",
+						Value::from(match connected {
+							Some(_) => "connected",
+							None => "disconnected",
+						}),
+					),
+					("Scanning", Value::from(false)),
+				]),
+				networks: Vec::new(),
+				known: Vec::new(),
+				adapter: Scene::adapter(
+					Some("Mock Wireless"),
+					Some("Mock Inc."),
+				),
+			};
+			for &(ssid, type_, strength, known) in networks {
+				let id = path_id(ssid, type_);
+				let network_path = path(format!("{DEVICE}/{id}"));
+				let known_path = path(format!("/net/connman/iwd/{id}"));
+				let mut properties = vec![
+					("Name", Value::from(ssid)),
+					("Type", Value::from(type_)),
+					("Connected", Value::from(connected == Some(ssid))),
+					(
+						"Device",
+						Value::from(ObjectPath::from_static_str_unchecked(
+							DEVICE,
+						)),
+					),
+				];
+				if known {
+					properties.push((
+						"KnownNetwork",
+						Value::from(known_path.clone()),
+					));
+					scene.known.push((
+						known_path,
+						from_properties(vec![
+							("Name", Value::from(ssid)),
+							("Type", Value::from(type_)),
+							("Hidden", Value::from(false)),
+							(
+								"LastConnectedTime",
+								Value::from("2024-03-01T10:00:00Z"),
+							),
+							("AutoConnect", Value::from(true)),
+						]),
+					));
+				}
+				if connected == Some(ssid) {
+					scene.station = from_properties(vec![
+						("State", Value::from("connected")),
+						("ConnectedNetwork", Value::from(network_path.clone())),
+						("Scanning", Value::from(false)),
+					]);
+				}
+				scene.networks.push(NetworkRow {
+					path: network_path.into(),
+					network: Some(from_properties(properties)),
+					strength: SignalStrength::from_centi_dbm(strength),
+					device: path(DEVICE.to_owned()),
+					merged: 0,
+					bands: Vec::new(),
+				});
+			}
+			scene
+		}
+
+		fn adapter(model: Option<&str>, vendor: Option<&str>) -> Adapter {
+			let mut properties = vec![
+				("Name", Value::from("phy0")),
+				("Powered", Value::from(true)),
+				("SupportedModes", Value::from(vec!["station", "ap"])),
+			];
+			properties.extend(model.map(|model| ("Model", Value::from(model))));
+			properties
+				.extend(vendor.map(|vendor| ("Vendor", Value::from(vendor))));
+			from_properties(properties)
+		}
+
+		/// Every command in every format, then `menu`, `bar` and `waybar`.
+		fn render(&self) -> String {
+			let device = path(DEVICE.to_owned());
+			let adapter = path(ADAPTER.to_owned());
+			let device_names: HashMap<_, _> =
+				[(device.clone(), "wlan0".to_owned())].into();
+			let status = JsonStatus {
+				device: "wlan0",
+				path: &device,
+				station: &self.station,
+			};
+			let known = || -> Vec<_> {
+				self.known
+					.iter()
+					.map(|(path, known_network)| JsonKnownNetwork {
+						path,
+						known_network,
+					})
+					.collect()
+			};
+			let connected = self.networks.iter().find(|net| net.is_connected());
+
+			let mut out = String::new();
+			for command in ["list", "status", "known", "adapters"] {
+				for (format, flag) in [
+					(Format::Table, ""),
+					(Format::Json, " --json"),
+					(Format::Porcelain, " --porcelain"),
+				] {
+					let config = Config {
+						format,
+						color: ColorChoice::Never,
+						..Config::default()
+					};
+					let rendered = match command {
+						"list" => {
+							let report = JsonReport {
+								status: Some(JsonStatus { ..status }),
+								networks: self.networks.clone(),
+								known_networks: known(),
+							};
+							render_list(&config, &report, &device_names, &[])
+						}
+						"status" => render_status(
+							&config,
+							&status,
+							connected.and_then(|net| net.network.as_ref()),
+						),
+						"known" => render_known(&config, &known()),
+						_ => render_adapters(
+							&config,
+							&[(&adapter, &self.adapter)],
+						),
+					};
+					out += &format!("$ {command}{flag}\n{}", rendered.unwrap());
+				}
+			}
+
+			let link = LinkStatus {
+				device: "wlan0".to_owned(),
+				state: self.station.state(),
+				connected: connected.cloned(),
+				networks: self.networks.clone(),
+			};
+			let waybar = WaybarLine::new(Some(&link), SsidFormat::Text);
+			out += "$ menu\n";
+			out += &menu_lines(&self.networks, SignalFormat::Dbm);
+			out += "$ bar\n";
+			out += &bar_line(&Config::default(), Some(&link));
+			out += "\n$ waybar\n";
+			out += &serde_json::to_string(&waybar).unwrap();
+			out += "\n";
+			out
+		}
+	}
+
+	/// Compares what `scene` renders with `tests/snapshots/<name>.txt`, or
+	/// writes it there if `UPDATE_SNAPSHOTS` is set.
+	fn assert_snapshot(scene: &Scene, name: &str) {
+		let actual = scene.render();
+		let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join("tests/snapshots")
+			.join(format!("{name}.txt"));
+		if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(&path, actual).unwrap();
+			return;
+		}
+		let expected = std::fs::read_to_string(&path).unwrap_or_default();
+		assert!(
+			actual == expected,
+			"{} is out of date, rerun with UPDATE_SNAPSHOTS=1 and review \
+			 the diff:\n{actual}",
+			path.display(),
+		);
+	}
+
+	fn default_scene() -> Scene {
+		let mut scene = Scene::new(
+			&[
+				("home", "psk", -5400, true),
+				("café", "open", -7200, false),
+				("work", "8021x", -8100, true),
+			],
+			Some("home"),
+		);
+		let bands = [
+			vec![WifiBand::TwoPointFour, WifiBand::Five],
+			vec![WifiBand::TwoPointFour],
+			vec![WifiBand::Six],
+		];
+		for (net, bands) in scene.networks.iter_mut().zip(bands) {
+			net.bands = bands;
+		}
+		scene
+	}
+
+	#[test]
+	fn snapshot_connected_and_known() {
+		assert_snapshot(&default_scene(), "connected-and-known");
+	}
+
+	#[test]
+	fn snapshot_empty() {
+		assert_snapshot(&Scene::new(&[], None), "empty");
+	}
+
+	#[test]
+	fn snapshot_combining_characters() {
+		let scene = Scene::new(
+			&[
+				("Cafe\u{301}", "psk", -6000, true),
+				("\u{1f600} e\u{308}x", "open", -8800, false),
+			],
+			Some("Cafe\u{301}"),
+		);
+		assert_snapshot(&scene, "combining-characters");
+	}
+
+	/// Tables get the SSIDs escaped by `sanitize_ssid`, while JSON and
+	/// porcelain output have them as they are, left to their own escaping.
+	#[test]
+	fn snapshot_control_characters() {
+		let scene = Scene::new(
+			&[
+				("\x1b]0;owned\x07", "psk", -6000, true),
+				("evil\u{202e}gpj.exe", "open", -7000, false),
+			],
+			Some("\x1b]0;owned\x07"),
+		);
+		assert_snapshot(&scene, "control-characters");
+	}
+
+	#[test]
+	fn snapshot_adapter_without_model() {
+		let mut scene = default_scene();
+		scene.adapter = Scene::adapter(None, None);
+		assert_snapshot(&scene, "adapter-without-model");
+	}
+}
@@ -0,0 +1,1102 @@
+//! The commands themselves, run against a snapshot of iwd's objects.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_io::Async;
+use futures_lite::{FutureExt, StreamExt};
+use iwd_playground::agent::SignalLevelAgent;
+use iwd_playground::client::{
+	connect_peer, discover_peers, find_known_network, join_all, peers_named,
+	receive_iwd_owner_changes, scan_and_wait, station_diagnostics, traced,
+	visible_networks, CallTimeouts, NetworkRow,
+};
+use iwd_playground::error::IwdErrorKind;
+use iwd_playground::objects::IwdObjectMap;
+use iwd_playground::opath::OPath;
+use iwd_playground::proxy::{
+	KnownNetworkProxy, NetworkProxy, P2pDeviceProxy, P2pPeerProxy, StationProxy,
+};
+use iwd_playground::store::ObjectStore;
+use iwd_playground::types::{
+	Device, Network, Provisioning, SignalStrength, Ssid, Station, StationState,
+	WifiBand,
+};
+use iwd_playground::{IwdClient, IwdError};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{zvariant, Connection};
+
+use super::escape::{escape_field, sanitize_ssid};
+use super::metrics::{serve_metrics, Exporter};
+use super::render::{
+	bar_line, menu_lines, render_adapters, render_bss, render_known,
+	render_list, render_peer_connection, render_peers, render_status,
+	LinkStatus, WaybarLine,
+};
+use crate::{
+	dedup_networks, print_json, rt, CliError, Command, Config, Format, JsonBss,
+	JsonKnownNetwork, JsonPeer, JsonReport, JsonStatus, Selection,
+	DISCOVERY_PERIOD,
+};
+
+#[zbus::proxy(
+	interface = "org.freedesktop.Notifications",
+	default_service = "org.freedesktop.Notifications",
+	default_path = "/org/freedesktop/Notifications",
+	gen_blocking = false
+)]
+trait Notifications {
+	#[allow(clippy::too_many_arguments)]
+	fn notify(
+		&self,
+		app_name: &str,
+		replaces_id: u32,
+		app_icon: &str,
+		summary: &str,
+		body: &str,
+		actions: &[&str],
+		hints: HashMap<&str, zvariant::Value<'_>>,
+		expire_timeout: i32,
+	) -> zbus::Result<u32>;
+}
+
+/// Desktop notifications on the session bus, each one replacing the last so
+/// they don't pile up while a connection flaps.
+///
+/// Without a session bus or notification daemon this does nothing.
+struct Notifier {
+	proxy: Option<NotificationsProxy<'static>>,
+	timeouts: CallTimeouts,
+	/// ID of the notification to replace, 0 for none.
+	id: u32,
+}
+
+impl Notifier {
+	async fn new(timeouts: CallTimeouts) -> Self {
+		let proxy = async {
+			let conn = Connection::session().await?;
+			NotificationsProxy::new(&conn).await
+		};
+		let proxy = match proxy.await {
+			Ok(proxy) => Some(proxy),
+			Err(err) => {
+				tracing::debug!(%err, "no session bus, not notifying");
+				None
+			}
+		};
+		Notifier {
+			proxy,
+			timeouts,
+			id: 0,
+		}
+	}
+
+	async fn notify(&mut self, summary: &str, body: &str) {
+		let Some(proxy) = &self.proxy else { return };
+		let call = proxy.notify(
+			env!("CARGO_PKG_NAME"),
+			self.id,
+			"network-wireless",
+			summary,
+			body,
+			&[],
+			HashMap::new(),
+			-1,
+		);
+		let path = proxy.inner().path();
+		match traced(&self.timeouts, "Notify", path, call).await {
+			Ok(id) => self.id = id,
+			Err(err) => {
+				tracing::debug!(%err, "failed to notify");
+				if err
+					.is_dbus_error("org.freedesktop.DBus.Error.ServiceUnknown")
+				{
+					self.proxy = None;
+				}
+			}
+		}
+	}
+}
+
+/// Connects to the first network in `networks` called `ssid`.
+///
+/// This returns once iwd finished connecting, or failed to.
+pub(crate) async fn connect_by_ssid(
+	client: &IwdClient,
+	networks: &[NetworkRow],
+	ssid: &str,
+) -> anyhow::Result<()> {
+	let Some(network) = networks
+		.iter()
+		.find(|net| net.ssid().and_then(Ssid::as_str) == Some(ssid))
+	else {
+		return Err(CliError::NoSuchNetwork(ssid.to_owned()).into());
+	};
+	connect_network(client, network.path.path()).await
+}
+
+async fn connect_network(
+	client: &IwdClient,
+	path: &OwnedObjectPath,
+) -> anyhow::Result<()> {
+	let proxy: NetworkProxy = OPath::from(path.clone()).proxy(client).await?;
+	let timeouts = client.timeouts();
+	traced(timeouts, "Connect", proxy.inner().path(), proxy.connect())
+		.await
+		.map_err(|err| {
+			// iwd doesn't tell a rejected passphrase apart from other
+			// handshake failures.
+			if err.is_iwd(IwdErrorKind::Failed)
+				|| err.is_iwd(IwdErrorKind::InvalidFormat)
+			{
+				CliError::AuthenticationFailed(err).into()
+			} else {
+				anyhow::Error::from(err)
+			}
+		})
+}
+
+/// [`CliError::P2pUnsupported`] if iwd won't discover peers, `err` as it
+/// is otherwise.
+fn p2p_error(err: IwdError) -> anyhow::Error {
+	if err.is_iwd(IwdErrorKind::NotSupported) {
+		CliError::P2pUnsupported.into()
+	} else {
+		err.into()
+	}
+}
+
+/// What every command starts from: the bus connection and a snapshot of
+/// iwd's objects.
+pub(crate) struct Session {
+	config: Config,
+	client: IwdClient,
+	objects: IwdObjectMap,
+	/// What [`watch_status`](Session::watch_status) printed last, carried
+	/// over to the session after an iwd restart so it isn't printed twice.
+	pub(crate) last_line: Mutex<Option<String>>,
+}
+
+impl Session {
+	pub(crate) async fn new(
+		config: Config,
+		conn: Connection,
+	) -> anyhow::Result<Self> {
+		let client = IwdClient::with_service(conn, config.service())
+			.await?
+			.with_timeouts(config.timeouts());
+		let objects = match config.command {
+			// looks up the one known network itself
+			Command::Forget { .. } => IwdObjectMap::default(),
+			_ => client.objects().await?,
+		};
+		Ok(Session {
+			config,
+			client,
+			objects,
+			last_line: Mutex::default(),
+		})
+	}
+
+	pub(crate) async fn run(&self) -> anyhow::Result<()> {
+		match &self.config.command {
+			Command::List => self.list().await,
+			Command::Scan => self.scan().await,
+			Command::Status if self.config.short => self.short_status().await,
+			Command::Status => self.status(),
+			Command::Connect { ssid } => self.connect(ssid).await,
+			Command::Disconnect => self.disconnect().await,
+			Command::Known => self.known(),
+			Command::Forget { ssid } => self.forget(ssid).await,
+			Command::Bss { ssid } => self.bss(ssid),
+			Command::Adapters => self.adapters(),
+			Command::Peers => self.peers().await,
+			Command::PeerConnect { name } => self.peer_connect(name).await,
+			Command::PeerDisconnect { name } => {
+				self.peer_disconnect(name).await
+			}
+			Command::Monitor => self.monitor().await,
+			Command::Waybar => self.waybar().await,
+			Command::Bar => self.bar().await,
+			Command::Metrics => self.metrics().await,
+			Command::Menu => self.menu().await,
+			Command::Select => self.select().await,
+		}
+	}
+
+	fn device_names(&self) -> HashMap<OwnedObjectPath, String> {
+		self.objects
+			.devices()
+			.map(|(path, d)| (path.clone(), d.name().to_owned()))
+			.collect()
+	}
+
+	/// The device named by `--device`, or else the first one in station
+	/// mode; `None` only in the latter case.
+	fn find_station(
+		&self,
+	) -> anyhow::Result<Option<(&OwnedObjectPath, &Station, &Device)>> {
+		let Some(name) = &self.config.device else {
+			return Ok(self.objects.devices().find_map(|(path, d)| {
+				let (_, s) = self.objects.find_station_for_device(path)?;
+				Some((path, s, d))
+			}));
+		};
+
+		let Some((path, d)) =
+			self.objects.devices().find(|(_, d)| d.name() == name)
+		else {
+			anyhow::bail!("no device called {name:?}");
+		};
+		let Some((_, s)) = self.objects.find_station_for_device(path) else {
+			anyhow::bail!("{name} is not in station mode");
+		};
+		Ok(Some((path, s, d)))
+	}
+
+	/// The device named by `--device`, or else every one in station mode,
+	/// by name.
+	fn stations(
+		&self,
+	) -> anyhow::Result<Vec<(&OwnedObjectPath, &Station, &Device)>> {
+		if self.config.device.is_some() {
+			return Ok(self.find_station()?.into_iter().collect());
+		}
+		let mut stations: Vec<_> = self
+			.objects
+			.devices()
+			.filter_map(|(path, d)| {
+				let (_, s) = self.objects.find_station_for_device(path)?;
+				Some((path, s, d))
+			})
+			.collect();
+		stations.sort_by(|(_, _, a), (_, _, b)| a.name().cmp(b.name()));
+		Ok(stations)
+	}
+
+	fn station(&self) -> anyhow::Result<(&OwnedObjectPath, &Station, &Device)> {
+		self.find_station()?
+			.ok_or_else(|| anyhow::anyhow!("no device in station mode"))
+	}
+
+	async fn station_proxy(
+		&self,
+		path: &OwnedObjectPath,
+	) -> anyhow::Result<StationProxy<'static>> {
+		tracing::debug!(%path, "using station");
+		Ok(self.client.station(path).await?)
+	}
+
+	async fn list(&self) -> anyhow::Result<()> {
+		let config = &self.config;
+		let device_names = self.device_names();
+
+		let mut hidden = Vec::new();
+		let mut report = JsonReport {
+			known_networks: self
+				.objects
+				.known_networks()
+				.map(|(path, known_network)| JsonKnownNetwork {
+					path,
+					known_network,
+				})
+				.collect(),
+			..Default::default()
+		};
+
+		let stations = self.stations()?;
+		if let Some(&(path, s, d)) = stations.first() {
+			for (path, s, d) in &stations {
+				tracing::debug!(
+					?path,
+					name = d.name(),
+					connected = s.connected_network().is_some(),
+					scanning = s.is_scanning(),
+					"found station",
+				);
+			}
+			report.status = Some(JsonStatus {
+				device: d.name(),
+				path,
+				station: s,
+			});
+
+			let proxies = join_all(
+				stations.iter().map(|(path, ..)| self.station_proxy(path)),
+			)
+			.await
+			.into_iter()
+			.collect::<anyhow::Result<Vec<_>>>()?;
+
+			#[cfg(feature = "tui")]
+			if config.tui {
+				let station = proxies.into_iter().next().unwrap();
+				return super::tui::run(&self.client, station).await;
+			}
+
+			let scans = proxies.iter().map(|s| scan_and_wait(&self.client, s));
+			for res in join_all(scans).await {
+				if let Err(err) = res {
+					tracing::warn!(%err, "scan failed, listing earlier results");
+				}
+			}
+			let visible = stations.iter().zip(&proxies).map(
+				|((path, ..), station)| async move {
+					let mut networks = visible_networks(
+						&self.client,
+						&self.objects,
+						path,
+						station,
+					)
+					.await?;
+					// before iwd 2, only the connected network's is known
+					let connected = networks
+						.iter_mut()
+						.find(|net| net.is_connected() && net.bands.is_empty());
+					if let Some(net) = connected {
+						net.bands.extend(self.diagnostic_band(path).await);
+					}
+					Ok::<_, IwdError>(networks)
+				},
+			);
+			for networks in join_all(visible).await {
+				report.networks.extend(networks?);
+			}
+			hidden = config.filters.apply(&mut report.networks);
+			if config.dedup {
+				report.networks = dedup_networks(report.networks);
+			}
+			config.sort.sort(&mut report.networks);
+		}
+
+		report
+			.known_networks
+			.sort_by(|a, b| a.known_network.name().cmp(b.known_network.name()));
+		print!("{}", render_list(config, &report, &device_names, &hidden)?);
+		Ok(())
+	}
+
+	async fn scan(&self) -> anyhow::Result<()> {
+		let (path, ..) = self.station()?;
+		let station = self.station_proxy(path).await?;
+		scan_and_wait(&self.client, &station).await?;
+		Ok(())
+	}
+
+	fn status(&self) -> anyhow::Result<()> {
+		let (path, s, d) = self.station()?;
+		let network = s
+			.connected_network()
+			.and_then(|connected| self.objects.get_network(connected));
+
+		let status = JsonStatus {
+			device: d.name(),
+			path,
+			station: s,
+		};
+		print!("{}", render_status(&self.config, &status, network)?);
+		Ok(())
+	}
+
+	/// `status --short`, for shell prompts and status bars: always five
+	/// fields, none of which contains a space.
+	async fn short_status(&self) -> anyhow::Result<()> {
+		let (path, s, d) = self.station()?;
+		let state = match s.state() {
+			StationState::Disconnected if s.is_scanning() => "SCANNING",
+			state => state.as_str(),
+		};
+
+		let network = match s.connected_network() {
+			Some(connected) => {
+				let station = self.station_proxy(path).await?;
+				visible_networks(&self.client, &self.objects, path, &station)
+					.await?
+					.into_iter()
+					.find(|net| net.path == *connected)
+			}
+			None => None,
+		};
+		let (ssid, signal, security) = match &network {
+			Some(net) => (
+				sanitize_ssid(
+					&net.ssid().map(Ssid::to_str_lossy).unwrap_or_default(),
+				)
+				.replace(' ', "\\x20"),
+				format!("{}dBm", net.strength.centi_dbm() / 100),
+				net.network_type().map_or("", |t| t.security_label()),
+			),
+			None => ("N/A".to_owned(), "N/A".to_owned(), "N/A"),
+		};
+
+		println!(
+			"{} {} {ssid} {signal} {security}",
+			state.to_uppercase(),
+			d.name(),
+		);
+		Ok(())
+	}
+
+	async fn connect(&self, ssid: &str) -> anyhow::Result<()> {
+		let (path, ..) = self.station()?;
+		let station = self.station_proxy(path).await?;
+
+		let mut networks =
+			visible_networks(&self.client, &self.objects, path, &station)
+				.await?;
+		if !networks
+			.iter()
+			.any(|net| net.ssid().and_then(Ssid::as_str) == Some(ssid))
+		{
+			tracing::debug!(ssid, "not seen yet, scanning");
+			scan_and_wait(&self.client, &station).await?;
+			let objects = self.client.objects().await?;
+			networks = visible_networks(&self.client, &objects, path, &station)
+				.await?;
+		}
+
+		connect_by_ssid(&self.client, &networks, ssid).await
+	}
+
+	async fn menu(&self) -> anyhow::Result<()> {
+		let (path, ..) = self.station()?;
+		let station = self.station_proxy(path).await?;
+
+		if let Err(err) = scan_and_wait(&self.client, &station).await {
+			tracing::warn!(%err, "scan failed, listing earlier results");
+		}
+		let objects = self.client.objects().await?;
+		let mut networks =
+			visible_networks(&self.client, &objects, path, &station).await?;
+		self.config.filters.apply(&mut networks);
+		if self.config.dedup {
+			networks = dedup_networks(networks);
+		}
+		self.config.sort.sort(&mut networks);
+
+		print!("{}", menu_lines(&networks, self.config.signal));
+		Ok(())
+	}
+
+	async fn select(&self) -> anyhow::Result<()> {
+		let line = rt::unblock(|| {
+			let mut line = String::new();
+			std::io::stdin().read_line(&mut line).map(|_| line)
+		})
+		.await?;
+		match Selection::parse(&line) {
+			None => Err(CliError::NothingSelected.into()),
+			Some(Selection::Ssid(ssid)) => self.connect(&ssid).await,
+			Some(Selection::Path(path)) => {
+				if !self.objects.iter_typed::<Network>().any(|p| p == path) {
+					let path = path.to_string();
+					return Err(CliError::NoSuchNetwork(path).into());
+				}
+				connect_network(&self.client, &path).await
+			}
+		}
+	}
+
+	async fn disconnect(&self) -> anyhow::Result<()> {
+		let (path, ..) = self.station()?;
+		let station = self.station_proxy(path).await?;
+		let timeouts = self.client.timeouts();
+		let path = station.inner().path();
+		traced(timeouts, "Disconnect", path, station.disconnect()).await?;
+		Ok(())
+	}
+
+	fn known(&self) -> anyhow::Result<()> {
+		let mut known: Vec<_> = self
+			.objects
+			.known_networks()
+			.map(|(path, known_network)| JsonKnownNetwork {
+				path,
+				known_network,
+			})
+			.collect();
+		known
+			.sort_by(|a, b| a.known_network.name().cmp(b.known_network.name()));
+
+		print!("{}", render_known(&self.config, &known)?);
+		Ok(())
+	}
+
+	/// `ssid` is taken for an object path if it is one.
+	async fn forget(&self, ssid: &str) -> anyhow::Result<()> {
+		let client = &self.client;
+		let path = match zvariant::ObjectPath::try_from(ssid) {
+			Ok(path) => path.into(),
+			Err(_) => {
+				let known =
+					find_known_network(client, &Ssid::Utf8(ssid.to_owned()))
+						.await?;
+				let Some((path, _)) = known else {
+					let ssid = ssid.to_owned();
+					return Err(CliError::NoSuchKnownNetwork(ssid).into());
+				};
+				path
+			}
+		};
+		let proxy: KnownNetworkProxy = OPath::from(path).proxy(client).await?;
+		let timeouts = client.timeouts();
+		traced(timeouts, "Forget", proxy.inner().path(), proxy.forget())
+			.await?;
+		Ok(())
+	}
+
+	/// The access points of the networks called `ssid`, strongest first.
+	fn bss(&self, ssid: &str) -> anyhow::Result<()> {
+		let networks: Vec<_> = self
+			.objects
+			.networks()
+			.filter(|(_, network)| network.name().as_str() == Some(ssid))
+			.collect();
+		if networks.is_empty() {
+			return Err(CliError::NoSuchNetwork(ssid.to_owned()).into());
+		}
+		if self.objects.basic_service_sets().next().is_none() {
+			return Err(CliError::NoBssInfo.into());
+		}
+
+		let mut bss: Vec<_> = networks
+			.into_iter()
+			.flat_map(|(network_path, network)| {
+				self.objects
+					.bss_for_network(network_path)
+					.map(|(path, bss)| JsonBss {
+						path,
+						type_: network.network_type(),
+						band: bss.band(),
+						bss,
+					})
+			})
+			.collect();
+		bss.sort_by_key(|b| std::cmp::Reverse(b.bss.strength()));
+		print!("{}", render_bss(&self.config, &bss)?);
+		Ok(())
+	}
+
+	fn adapters(&self) -> anyhow::Result<()> {
+		let mut adapters: Vec<_> = self.objects.adapters().collect();
+		adapters.sort_by(|(_, a), (_, b)| a.name().cmp(b.name()));
+		print!("{}", render_adapters(&self.config, &adapters)?);
+		Ok(())
+	}
+
+	/// Discovers Wi-Fi Direct peers with the first P2P device for
+	/// `--discover`, or until interrupted, and prints what it found.
+	async fn peers(&self) -> anyhow::Result<()> {
+		let (device, proxy) = self.p2p_device().await?;
+		let store = ObjectStore::new(self.client.clone(), Duration::ZERO);
+		let objects = discover_peers(&store, &proxy, self.discovery_period()?)
+			.await
+			.map_err(p2p_error)?;
+		let mut peers: Vec<_> = objects
+			.peers_for_device(&device)
+			.map(|(path, peer)| JsonPeer::new(&objects, path, peer))
+			.collect();
+		peers.sort_by(|a, b| a.peer.name().cmp(b.peer.name()));
+		print!("{}", render_peers(&self.config, &peers)?);
+		Ok(())
+	}
+
+	/// Connects to the strongest peer called `name`, discovering until it
+	/// shows up, with `--pin` if given and the push button otherwise.
+	async fn peer_connect(&self, name: &str) -> anyhow::Result<()> {
+		let (_, proxy) = self.p2p_device().await?;
+		let store = ObjectStore::new(self.client.clone(), Duration::ZERO);
+		let period = self.discovery_period()?;
+		let named = |objects: &IwdObjectMap| {
+			objects.p2p_peers().any(|(_, peer)| peer.name() == name)
+		};
+		let seen = async {
+			loop {
+				// an error comes up again with the read ending discovery
+				if store.objects().await.is_ok_and(|o| named(&o)) {
+					break;
+				}
+				async_io::Timer::after(Duration::from_millis(100)).await;
+			}
+		};
+		let objects = discover_peers(&store, &proxy, period.or(seen)).await;
+		let objects = objects.map_err(p2p_error)?;
+
+		let peers = peers_named(&self.client, &proxy, &objects, name).await?;
+		let Some(path) = peers.into_iter().next() else {
+			return Err(CliError::NoSuchPeer(name.to_owned()).into());
+		};
+		let provisioning = match &self.config.pin {
+			Some(pin) => Provisioning::Pin(pin.clone()),
+			None => Provisioning::PushButton,
+		};
+		let peer_proxy = path.clone().proxy(&self.client).await?;
+		let peer = connect_peer(&self.client, &peer_proxy, &provisioning)
+			.await
+			.map_err(|err| {
+				// a wrong PIN fails like a wrong passphrase
+				if err.is_iwd(IwdErrorKind::Failed) {
+					CliError::AuthenticationFailed(err).into()
+				} else {
+					anyhow::Error::from(err)
+				}
+			})?;
+		let path = path.path().clone();
+		let peer = JsonPeer::new(&objects, &path, &peer);
+		print!("{}", render_peer_connection(&self.config, &peer)?);
+		Ok(())
+	}
+
+	/// Disconnects from the connected peer called `name`.
+	async fn peer_disconnect(&self, name: &str) -> anyhow::Result<()> {
+		let peer = self
+			.objects
+			.p2p_peers()
+			.find(|(_, peer)| peer.name() == name && peer.is_connected());
+		let Some((path, _)) = peer else {
+			return Err(CliError::NoSuchPeer(name.to_owned()).into());
+		};
+		let proxy: P2pPeerProxy =
+			OPath::from(path.clone()).proxy(&self.client).await?;
+		let timeouts = self.client.timeouts();
+		traced(
+			timeouts,
+			"Disconnect",
+			proxy.inner().path(),
+			proxy.disconnect(),
+		)
+		.await?;
+		Ok(())
+	}
+
+	/// The first P2P device, which is what `peers` and the like use.
+	async fn p2p_device(
+		&self,
+	) -> anyhow::Result<(OPath<P2pDeviceProxy<'static>>, P2pDeviceProxy<'_>)> {
+		let Some(device) = self.objects.p2p_devices().next() else {
+			return Err(CliError::P2pUnsupported.into());
+		};
+		let proxy = device.clone().proxy(&self.client).await?;
+		Ok((device, proxy))
+	}
+
+	/// Completes after `--discover`, or once interrupted.
+	fn discovery_period(&self) -> std::io::Result<impl Future<Output = ()>> {
+		let period = self.config.discover.unwrap_or(DISCOVERY_PERIOD);
+		let mut signals = async_signal::Signals::new([
+			async_signal::Signal::Int,
+			async_signal::Signal::Term,
+		])?;
+		Ok(async move {
+			let interrupted = async {
+				signals.next().await;
+			};
+			async {
+				async_io::Timer::after(period).await;
+			}
+			.or(interrupted)
+			.await;
+		})
+	}
+
+	/// Prints every `PropertiesChanged` signal iwd sends, restricted to the
+	/// objects below `--device` if given, until interrupted.
+	async fn monitor(&self) -> anyhow::Result<()> {
+		let mut notifier = None;
+		if self.config.notify {
+			notifier = Some(Notifier::new(*self.client.timeouts()).await);
+		}
+
+		let mut rule = zbus::MatchRule::builder()
+			.msg_type(zbus::message::Type::Signal)
+			.sender(self.client.service())?
+			.interface("org.freedesktop.DBus.Properties")?
+			.member("PropertiesChanged")?;
+		if self.config.device.is_some() {
+			let (path, ..) = self.station()?;
+			rule = rule.path_namespace(path.as_str())?;
+		}
+		let mut stream = zbus::MessageStream::for_match_rule(
+			rule.build(),
+			self.client.connection(),
+			None,
+		)
+		.await?;
+
+		while let Some(msg) = stream.next().await {
+			let msg = msg?;
+			let header = msg.header();
+			let Some(path) = header.path() else { continue };
+			let body = msg.body();
+			let (interface, changed, invalidated): (
+				String,
+				HashMap<String, zvariant::Value<'_>>,
+				Vec<String>,
+			) = body.deserialize()?;
+			let interface = interface
+				.strip_prefix("net.connman.iwd.")
+				.unwrap_or(&interface);
+
+			let mut changed: Vec<_> = changed.into_iter().collect();
+			changed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+			match self.config.format {
+				Format::Json => print_json(&serde_json::json!({
+					"path": path.as_str(),
+					"interface": interface,
+					"changed": changed
+						.iter()
+						.map(|(name, value)| (name, value.to_string()))
+						.collect::<HashMap<_, _>>(),
+					"invalidated": invalidated,
+				}))?,
+				Format::Porcelain => {
+					for (name, value) in &changed {
+						println!(
+							"{}\t{interface}\t{name}\t{}",
+							path.as_str(),
+							escape_field(&value.to_string()),
+						);
+					}
+				}
+				Format::Table => {
+					for (name, value) in &changed {
+						println!("{path} {interface}.{name} = {value}");
+					}
+					for name in &invalidated {
+						println!("{path} {interface}.{name} invalidated");
+					}
+				}
+			}
+
+			let state = changed.iter().find_map(|(name, value)| match value {
+				zvariant::Value::Str(state) if name == "State" => Some(state),
+				_ => None,
+			});
+			if let (Some(notifier), "Station", Some(state)) =
+				(&mut notifier, interface, state)
+			{
+				let path = OwnedObjectPath::from(path.to_owned());
+				self.notify_state(notifier, &path, state).await;
+			}
+		}
+		Ok(())
+	}
+
+	/// The status of the station at `path`, as of `objects`.
+	async fn link_status(
+		&self,
+		objects: &IwdObjectMap,
+		path: &OwnedObjectPath,
+		station: &StationProxy<'_>,
+	) -> anyhow::Result<Option<LinkStatus>> {
+		let Some((_, s)) = objects.find_station_for_device(path) else {
+			return Ok(None);
+		};
+		let device = objects
+			.devices()
+			.find(|(device, _)| *device == path)
+			.map(|(_, d)| d.name().to_owned())
+			.unwrap_or_default();
+		// `GetDiagnostics` fails unless connected
+		let rssi = async {
+			match s.connected_network() {
+				Some(_) => self.diagnostic_rssi(path).await,
+				None => None,
+			}
+		};
+		let (networks, rssi) = futures_lite::future::zip(
+			visible_networks(&self.client, objects, path, station),
+			rssi,
+		)
+		.await;
+		let networks = networks?;
+
+		let mut connected =
+			networks.iter().find(|net| net.is_connected()).cloned();
+		if let (Some(net), Some(rssi)) = (&mut connected, rssi) {
+			net.strength = rssi;
+		}
+
+		Ok(Some(LinkStatus {
+			device,
+			state: s.state(),
+			connected,
+			networks,
+		}))
+	}
+
+	/// The RSSI `StationDiagnostic` reports for the connected network,
+	/// which is fresher than the last scan.
+	async fn diagnostic_rssi(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Option<SignalStrength> {
+		let diagnostics = station_diagnostics(&self.client, path).await?;
+		let rssi: i16 = diagnostics.get("RSSI")?.downcast_ref().ok()?;
+		Some(SignalStrength::from_dbm(rssi))
+	}
+
+	/// The band of the connected network, by `StationDiagnostic`'s
+	/// `Frequency`.
+	async fn diagnostic_band(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Option<WifiBand> {
+		let diagnostics = station_diagnostics(&self.client, path).await?;
+		let mhz: u32 = diagnostics.get("Frequency")?.downcast_ref().ok()?;
+		WifiBand::from_frequency(mhz)
+	}
+
+	/// Prints the line `emit` makes of the status of the station, and then
+	/// again whenever it changes state or the signal crosses a 10 dBm step
+	/// with `--follow`, and every `--interval`.
+	///
+	/// Fails with [`CliError::IwdRestarted`] when iwd's name changes hands,
+	/// for the caller to start over.
+	async fn watch_status(
+		&self,
+		emit: impl Fn(Option<&LinkStatus>) -> anyhow::Result<String>,
+	) -> anyhow::Result<()> {
+		const AGENT_PATH: &str = "/iwd_get_networks/signal_level_agent";
+
+		#[derive(PartialEq)]
+		enum Wake {
+			Update,
+			IwdRestarted,
+		}
+
+		let print = |line: String| {
+			println!("{line}");
+			*self.last_line.lock().unwrap_or_else(|e| e.into_inner()) =
+				Some(line);
+		};
+
+		let Some((path, ..)) = self.find_station()? else {
+			print(emit(None)?);
+			return Ok(());
+		};
+		let station = self.station_proxy(path).await?;
+		let line = emit(
+			self.link_status(&self.objects, path, &station)
+				.await?
+				.as_ref(),
+		)?;
+		// the same as before iwd restarted
+		if self
+			.last_line
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.as_ref() != Some(&line)
+		{
+			print(line);
+		}
+		if !self.config.follow && self.config.interval.is_none() {
+			return Ok(());
+		}
+
+		let (tx, rx) = async_channel::unbounded();
+
+		let agent_path =
+			zvariant::ObjectPath::from_static_str_unchecked(AGENT_PATH);
+		let mut agent_registered = false;
+		if self.config.follow {
+			let agent_tx = tx.clone();
+			let agent =
+				SignalLevelAgent::new(vec![-50, -60, -70, -80], move |_, _| {
+					let _ = agent_tx.try_send(Wake::Update);
+				});
+			match agent
+				.register(&self.client, &station, agent_path.clone())
+				.await
+			{
+				Ok(()) => agent_registered = true,
+				Err(err) => {
+					tracing::warn!(%err, "can't follow the signal strength");
+				}
+			}
+		}
+
+		let res = async {
+			if self.config.follow {
+				let rule = zbus::MatchRule::builder()
+					.msg_type(zbus::message::Type::Signal)
+					.sender(self.client.service())?
+					.path(path.as_str())?
+					.interface("org.freedesktop.DBus.Properties")?
+					.member("PropertiesChanged")?
+					.build();
+				let mut changes = zbus::MessageStream::for_match_rule(
+					rule,
+					self.client.connection(),
+					None,
+				)
+				.await?;
+				let tx = tx.clone();
+				rt::spawn(async move {
+					while changes.next().await.is_some() {
+						if tx.send(Wake::Update).await.is_err() {
+							break;
+						}
+					}
+				});
+			}
+
+			if let Some(interval) = self.config.interval {
+				let tx = tx.clone();
+				rt::spawn(async move {
+					loop {
+						async_io::Timer::after(interval).await;
+						if tx.send(Wake::Update).await.is_err() {
+							break;
+						}
+					}
+				});
+			}
+
+			{
+				let mut owner_changes =
+					receive_iwd_owner_changes(&self.client).await?;
+				let tx = tx.clone();
+				rt::spawn(async move {
+					if owner_changes.next().await.is_some() {
+						let _ = tx.send(Wake::IwdRestarted).await;
+					}
+				});
+			}
+
+			// ends the loop below if nothing else holds a sender
+			drop(tx);
+
+			while let Ok(mut wake) = rx.recv().await {
+				// one update for a burst of changes
+				while let Ok(next) = rx.try_recv() {
+					if wake == Wake::Update {
+						wake = next;
+					}
+				}
+				if wake == Wake::IwdRestarted {
+					return Err(CliError::IwdRestarted.into());
+				}
+
+				let objects = self.client.objects().await?;
+				let status = self.link_status(&objects, path, &station).await?;
+				print(emit(status.as_ref())?);
+			}
+			Ok(())
+		}
+		.await;
+
+		// the next session serves its own, after iwd restarted
+		if agent_registered {
+			let unregister = SignalLevelAgent::unregister(
+				&self.client,
+				&station,
+				agent_path,
+			);
+			if let Err(err) = unregister.await {
+				tracing::debug!(%err, "failed to unregister the agent");
+			}
+		}
+		res
+	}
+
+	async fn waybar(&self) -> anyhow::Result<()> {
+		self.watch_status(|status| {
+			Ok(serde_json::to_string(&WaybarLine::new(
+				status,
+				self.config.ssid,
+			))?)
+		})
+		.await
+	}
+
+	/// Prints `SSID (-63 dBm)`, or `down` when not connected, for
+	/// i3status, polybar and the like.
+	async fn bar(&self) -> anyhow::Result<()> {
+		self.watch_status(|status| Ok(bar_line(&self.config, status)))
+			.await
+	}
+
+	/// Serves Prometheus metrics on `--listen` until interrupted.
+	async fn metrics(&self) -> anyhow::Result<()> {
+		use anyhow::Context;
+
+		let addr = self
+			.config
+			.listen
+			.as_deref()
+			.unwrap_or(Exporter::DEFAULT_LISTEN);
+		let listener =
+			std::net::TcpListener::bind(addr)
+				.and_then(Async::new)
+				.with_context(|| format!("failed to listen on {addr}"))?;
+		tracing::info!(addr = %listener.get_ref().local_addr()?, "serving metrics");
+
+		let exporter = Arc::new(Exporter::new(self.client.clone()));
+		let watchers: Vec<_> = self
+			.objects
+			.iter_typed::<Station>()
+			.map(|path| rt::spawn(exporter.clone().watch_scans(path.into())))
+			.collect();
+		let server = rt::spawn(serve_metrics(listener, exporter));
+
+		let mut signals = async_signal::Signals::new([
+			async_signal::Signal::Int,
+			async_signal::Signal::Term,
+		])?;
+		signals.next().await;
+		tracing::info!("shutting down");
+
+		server.cancel().await;
+		for watcher in watchers {
+			watcher.cancel().await;
+		}
+		Ok(())
+	}
+
+	/// Notifies about the station at `path` having entered `state`, if it
+	/// is connected or disconnected.
+	async fn notify_state(
+		&self,
+		notifier: &mut Notifier,
+		path: &OwnedObjectPath,
+		state: &str,
+	) {
+		let device = self
+			.objects
+			.devices()
+			.find(|(device, _)| *device == path)
+			.map_or("Wi-Fi", |(_, d)| d.name());
+
+		match state {
+			"connected" => {
+				// the snapshot is older than the connection
+				let objects = match self.client.objects().await {
+					Ok(objects) => objects,
+					Err(err) => {
+						tracing::warn!(%err, "can't look up the new network");
+						IwdObjectMap::default()
+					}
+				};
+				let ssid = objects
+					.find_station_for_device(path)
+					.and_then(|(_, s)| {
+						objects.get_network(s.connected_network()?)
+					})
+					.map(|network| {
+						sanitize_ssid(&network.name().to_str_lossy())
+							.into_owned()
+					})
+					.unwrap_or_default();
+				notifier.notify(&format!("{device} connected"), &ssid).await;
+			}
+			"disconnected" => {
+				notifier.notify(&format!("{device} disconnected"), "").await;
+			}
+			_ => {}
+		}
+	}
+}
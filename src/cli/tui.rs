@@ -0,0 +1,573 @@
+//! Interactive network picker, built with `--features tui`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use futures_lite::StreamExt;
+use iwd_playground::client::{traced, visible_networks, NetworkRow};
+use iwd_playground::proxy::{AgentManagerProxy, StationProxy};
+use iwd_playground::types::{NetworkType, Ssid, StationState};
+use iwd_playground::{IwdClient, IwdError};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use super::escape::sanitize_ssid;
+use super::session::connect_by_ssid;
+use crate::rt;
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// How long to wait for a key press before redrawing.
+const TICK: Duration = Duration::from_millis(100);
+
+const AGENT_PATH: &str = "/iwd_get_networks/agent";
+
+/// What happened outside the key loop since the last tick.
+enum Update {
+	/// The agent wants a secret typed in.
+	Prompt(Prompt),
+	/// iwd gave up on the request the current prompt is for.
+	Cancel,
+	State(StationState),
+	/// A `Connect` started from the picker finished.
+	Connected(String, anyhow::Result<()>),
+}
+
+struct Prompt {
+	label: String,
+	/// Whether to echo the input, i.e. not a password.
+	visible: bool,
+	reply: Sender<String>,
+}
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "net.connman.iwd.Agent.Error")]
+enum AgentError {
+	#[zbus(error)]
+	ZBus(zbus::Error),
+	Canceled(String),
+}
+
+/// Server side of `net.connman.iwd.Agent`, asking for secrets in the
+/// picker's status line.
+struct Agent {
+	client: IwdClient,
+	updates: Sender<Update>,
+}
+
+impl Agent {
+	/// Serves the agent and registers it as this client's agent.
+	async fn register(
+		client: &IwdClient,
+		updates: Sender<Update>,
+	) -> Result<(), IwdError> {
+		let agent = Agent {
+			client: client.clone(),
+			updates,
+		};
+		client
+			.connection()
+			.object_server()
+			.at(AGENT_PATH, agent)
+			.await?;
+		let manager = AgentManagerProxy::builder(client.connection())
+			.destination(client.service().clone())?
+			.build()
+			.await?;
+		let path = ObjectPath::from_static_str_unchecked(AGENT_PATH);
+		traced(
+			client.timeouts(),
+			"RegisterAgent",
+			manager.inner().path(),
+			manager.register_agent(&path),
+		)
+		.await
+	}
+
+	async fn unregister(client: &IwdClient) -> Result<(), IwdError> {
+		let conn = client.connection();
+		let manager = AgentManagerProxy::builder(conn)
+			.destination(client.service().clone())?
+			.build()
+			.await?;
+		let path = ObjectPath::from_static_str_unchecked(AGENT_PATH);
+		traced(
+			client.timeouts(),
+			"UnregisterAgent",
+			manager.inner().path(),
+			manager.unregister_agent(&path),
+		)
+		.await?;
+		conn.object_server().remove::<Agent, _>(AGENT_PATH).await?;
+		Ok(())
+	}
+
+	/// Name of the network for prompts, the path if it can't be looked
+	/// up.
+	async fn network_name(&self, path: &OwnedObjectPath) -> String {
+		let objects = match self.client.objects().await {
+			Ok(objects) => objects,
+			Err(_) => return path.to_string(),
+		};
+		let name = objects
+			.networks()
+			.find(|(p, _)| *p == path)
+			.map(|(_, net)| sanitize_ssid(&net.name().to_str_lossy()).into());
+		name.unwrap_or_else(|| path.to_string())
+	}
+
+	/// Shows a prompt and waits until it is answered or dismissed.
+	async fn ask(
+		&self,
+		label: String,
+		visible: bool,
+	) -> Result<String, AgentError> {
+		let (reply, answer) = async_channel::bounded(1);
+		let prompt = Prompt {
+			label,
+			visible,
+			reply,
+		};
+		self.updates
+			.send(Update::Prompt(prompt))
+			.await
+			.map_err(|_| AgentError::Canceled("picker closed".into()))?;
+		answer
+			.recv()
+			.await
+			.map_err(|_| AgentError::Canceled("canceled by user".into()))
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.Agent")]
+impl Agent {
+	fn release(&self) {
+		tracing::debug!("agent released");
+	}
+
+	async fn request_passphrase(
+		&self,
+		network: OwnedObjectPath,
+	) -> Result<String, AgentError> {
+		let name = self.network_name(&network).await;
+		self.ask(format!("Passphrase for {name}"), false).await
+	}
+
+	async fn request_private_key_passphrase(
+		&self,
+		network: OwnedObjectPath,
+	) -> Result<String, AgentError> {
+		let name = self.network_name(&network).await;
+		self.ask(format!("Private key passphrase for {name}"), false)
+			.await
+	}
+
+	async fn request_user_name_and_password(
+		&self,
+		network: OwnedObjectPath,
+	) -> Result<(String, String), AgentError> {
+		let name = self.network_name(&network).await;
+		let user = self.ask(format!("Username for {name}"), true).await?;
+		let password = self
+			.ask(format!("Password for {user} on {name}"), false)
+			.await?;
+		Ok((user, password))
+	}
+
+	async fn request_user_password(
+		&self,
+		network: OwnedObjectPath,
+		user: String,
+	) -> Result<String, AgentError> {
+		let name = self.network_name(&network).await;
+		self.ask(format!("Password for {user} on {name}"), false)
+			.await
+	}
+
+	async fn cancel(&self, reason: String) {
+		tracing::debug!(reason, "agent request canceled");
+		let _ = self.updates.send(Update::Cancel).await;
+	}
+}
+
+struct App<'a> {
+	client: &'a IwdClient,
+	station: StationProxy<'static>,
+	networks: Vec<NetworkRow>,
+	table: TableState,
+	scanning: Arc<AtomicBool>,
+	state: Option<StationState>,
+	updates: Receiver<Update>,
+	/// For connections started from the picker to report back.
+	updates_tx: Sender<Update>,
+	/// SSID being connected to.
+	connecting: Option<String>,
+	prompt: Option<Prompt>,
+	/// What has been typed into the prompt, or the number typed to pick
+	/// a network when there is none.
+	input: String,
+	tick: usize,
+	status: String,
+}
+
+pub(crate) async fn run(
+	client: &IwdClient,
+	station: StationProxy<'static>,
+) -> anyhow::Result<()> {
+	let (updates_tx, updates) = async_channel::unbounded();
+
+	let scanning = Arc::new(AtomicBool::new(station.scanning().await?));
+	let watcher = {
+		let scanning = scanning.clone();
+		let mut changes = station.receive_scanning_changed().await;
+		rt::spawn(async move {
+			while let Some(change) = changes.next().await {
+				if let Ok(value) = change.get().await {
+					scanning.store(value, Ordering::Relaxed);
+				}
+			}
+		})
+	};
+	let state_watcher = {
+		let tx = updates_tx.clone();
+		let mut changes = station.receive_state_changed().await;
+		rt::spawn(async move {
+			while let Some(change) = changes.next().await {
+				if let Ok(state) = change.get().await {
+					let _ = tx.send(Update::State(state)).await;
+				}
+			}
+		})
+	};
+
+	let mut status = String::new();
+	let agent = Agent::register(client, updates_tx.clone()).await;
+	if let Err(err) = &agent {
+		tracing::warn!(%err, "failed to register agent");
+		status = "No agent, connecting to new networks will fail".to_owned();
+	}
+
+	let mut app = App {
+		client,
+		state: station.state().await.ok(),
+		station,
+		networks: Vec::new(),
+		table: TableState::default(),
+		scanning,
+		updates,
+		updates_tx,
+		connecting: None,
+		prompt: None,
+		input: String::new(),
+		tick: 0,
+		status,
+	};
+	app.refresh().await?;
+
+	// also restores the terminal on panic
+	let mut terminal = ratatui::init();
+	let res = app.run(&mut terminal).await;
+	ratatui::restore();
+
+	if agent.is_ok() {
+		if let Err(err) = Agent::unregister(client).await {
+			tracing::debug!(%err, "failed to unregister agent");
+		}
+	}
+	watcher.cancel().await;
+	state_watcher.cancel().await;
+	res
+}
+
+impl App<'_> {
+	async fn run(
+		&mut self,
+		terminal: &mut DefaultTerminal,
+	) -> anyhow::Result<()> {
+		let mut was_scanning = self.scanning.load(Ordering::Relaxed);
+
+		loop {
+			terminal.draw(|frame| self.draw(frame))?;
+
+			let event = rt::unblock(|| {
+				if event::poll(TICK)? {
+					event::read().map(Some)
+				} else {
+					Ok(None)
+				}
+			})
+			.await?;
+			self.tick = self.tick.wrapping_add(1);
+
+			let scanning = self.scanning.load(Ordering::Relaxed);
+			if was_scanning && !scanning {
+				self.status = "Scan finished".to_owned();
+				self.refresh_or_report().await;
+			}
+			was_scanning = scanning;
+
+			while let Ok(update) = self.updates.try_recv() {
+				self.update(update).await;
+			}
+
+			let Some(Event::Key(key)) = event else {
+				continue;
+			};
+			if key.kind != KeyEventKind::Press {
+				continue;
+			}
+
+			if let Some(prompt) = &self.prompt {
+				match key.code {
+					KeyCode::Char(c) => self.input.push(c),
+					KeyCode::Backspace => {
+						self.input.pop();
+					}
+					KeyCode::Enter => {
+						let input = std::mem::take(&mut self.input);
+						let _ = prompt.reply.try_send(input);
+						self.prompt = None;
+					}
+					// dropping the reply sender cancels the request
+					KeyCode::Esc => {
+						self.input.clear();
+						self.prompt = None;
+					}
+					_ => {}
+				}
+				continue;
+			}
+
+			match key.code {
+				KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+				KeyCode::Up | KeyCode::Char('k') => {
+					self.table.select_previous()
+				}
+				KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+				KeyCode::Char(c @ '0'..='9') => {
+					self.input.push(c);
+					self.select_typed();
+					continue;
+				}
+				KeyCode::Backspace => {
+					self.input.pop();
+					self.select_typed();
+					continue;
+				}
+				KeyCode::Enter => self.connect(),
+				KeyCode::Char('s') => {
+					let res = self.station.scan().await.map_err(Into::into);
+					self.report(res, "Scanning…");
+				}
+				KeyCode::Char('d') => {
+					let res =
+						self.station.disconnect().await.map_err(Into::into);
+					self.report(res, "Disconnected");
+					self.refresh_or_report().await;
+				}
+				_ => {}
+			}
+			self.input.clear();
+		}
+	}
+
+	async fn update(&mut self, update: Update) {
+		match update {
+			Update::Prompt(prompt) => {
+				self.input.clear();
+				self.prompt = Some(prompt);
+			}
+			Update::Cancel => {
+				self.input.clear();
+				self.prompt = None;
+			}
+			Update::State(state) => {
+				self.state = Some(state);
+				if let Some(ssid) = &self.connecting {
+					self.status =
+						format!("{}: {}", sanitize_ssid(ssid), state.as_str());
+				}
+				if matches!(
+					state,
+					StationState::Connected | StationState::Disconnected
+				) {
+					self.refresh_or_report().await;
+				}
+			}
+			Update::Connected(ssid, res) => {
+				self.connecting = None;
+				self.prompt = None;
+				self.report(
+					res,
+					&format!("Connected to {}", sanitize_ssid(&ssid)),
+				);
+				self.refresh_or_report().await;
+			}
+		}
+	}
+
+	/// Selects the network whose number has been typed so far.
+	fn select_typed(&mut self) {
+		let i = self
+			.input
+			.parse::<usize>()
+			.ok()
+			.and_then(|n| n.checked_sub(1));
+		if let Some(i) = i.filter(|&i| i < self.networks.len()) {
+			self.table.select(Some(i));
+		}
+	}
+
+	/// Starts connecting to the selected network in the background, so
+	/// that the agent's prompts can be answered meanwhile.
+	fn connect(&mut self) {
+		if let Some(ssid) = &self.connecting {
+			self.status =
+				format!("Still connecting to {}…", sanitize_ssid(ssid));
+			return;
+		}
+		let Some(net) =
+			self.table.selected().and_then(|i| self.networks.get(i))
+		else {
+			return;
+		};
+		let Some(ssid) = net.ssid() else {
+			self.status = "That network is gone".to_owned();
+			return;
+		};
+		let ssid = ssid.to_str_lossy().into_owned();
+		self.status = format!("Connecting to {}…", sanitize_ssid(&ssid));
+		self.connecting = Some(ssid.clone());
+
+		let client = self.client.clone();
+		let networks = self.networks.clone();
+		let tx = self.updates_tx.clone();
+		rt::spawn(async move {
+			let res = connect_by_ssid(&client, &networks, &ssid).await;
+			let _ = tx.send(Update::Connected(ssid, res)).await;
+		});
+	}
+
+	/// Shows `ok` or the error in the status line.
+	fn report(&mut self, res: anyhow::Result<()>, ok: &str) {
+		self.status = match res {
+			Ok(()) => ok.to_owned(),
+			Err(err) => format!("Error: {err}"),
+		};
+	}
+
+	/// Refreshes, leaving the status line alone unless that fails.
+	async fn refresh_or_report(&mut self) {
+		if let Err(err) = self.refresh().await {
+			self.status = format!("Error: {err}");
+		}
+	}
+
+	async fn refresh(&mut self) -> anyhow::Result<()> {
+		let objects = self.client.objects().await?;
+		let device = OwnedObjectPath::from(self.station.inner().path().clone());
+		self.networks =
+			visible_networks(self.client, &objects, &device, &self.station)
+				.await?;
+
+		match self.table.selected() {
+			_ if self.networks.is_empty() => self.table.select(None),
+			Some(i) if i >= self.networks.len() => {
+				self.table.select(Some(self.networks.len() - 1));
+			}
+			None => self.table.select(Some(0)),
+			Some(_) => {}
+		}
+		Ok(())
+	}
+
+	fn draw(&mut self, frame: &mut Frame) {
+		let [list_area, status_area, help_area] = Layout::vertical([
+			Constraint::Min(3),
+			Constraint::Length(1),
+			Constraint::Length(1),
+		])
+		.areas(frame.area());
+
+		let rows = self.networks.iter().enumerate().map(|(i, net)| {
+			let row = Row::new([
+				(i + 1).to_string(),
+				net.strength.meter().to_owned(),
+				match net.network_type() {
+					Some(NetworkType::Open) | None => "  ",
+					Some(_) => "🔒",
+				}
+				.to_owned(),
+				sanitize_ssid(
+					&net.ssid().map(Ssid::to_str_lossy).unwrap_or_default(),
+				)
+				.into_owned(),
+				match (net.is_connected(), net.is_known()) {
+					(true, _) => "connected",
+					(false, true) => "known",
+					(false, false) => "",
+				}
+				.to_owned(),
+			]);
+			if net.is_connected() {
+				row.style(Style::new().fg(Color::Green))
+			} else {
+				row
+			}
+		});
+
+		let mut title = " Networks ".to_owned();
+		if let Some(state) = self.state {
+			title = format!(" Networks · {} ", state.as_str());
+		}
+		if self.scanning.load(Ordering::Relaxed) {
+			title.push(SPINNER[self.tick % SPINNER.len()]);
+			title.push(' ');
+		}
+
+		let table = Table::new(
+			rows,
+			[
+				Constraint::Length(3),
+				Constraint::Length(4),
+				Constraint::Length(2),
+				Constraint::Fill(1),
+				Constraint::Length(9),
+			],
+		)
+		.block(Block::bordered().title(title))
+		.row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+		frame.render_stateful_widget(table, list_area, &mut self.table);
+
+		let (status, help) = match &self.prompt {
+			Some(prompt) => {
+				let input = if prompt.visible {
+					self.input.clone()
+				} else {
+					"*".repeat(self.input.chars().count())
+				};
+				(
+					format!("{}: {input}", prompt.label),
+					"enter submit · esc cancel",
+				)
+			}
+			None => (
+				self.status.clone(),
+				"0-9 pick · enter connect · s scan · d disconnect · q quit",
+			),
+		};
+		frame.render_widget(Paragraph::new(status), status_area);
+		frame.render_widget(
+			Paragraph::new(help)
+				.style(Style::new().add_modifier(Modifier::DIM)),
+			help_area,
+		);
+	}
+}
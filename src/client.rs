@@ -0,0 +1,1000 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use async_io::Timer;
+use futures_lite::{FutureExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::Instrument;
+use zbus::names::{BusName, OwnedInterfaceName};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{self, OwnedObjectPath};
+use zbus::Connection;
+
+use crate::bus::{Bus, DEFAULT_SERVICE};
+use crate::error::IwdError;
+use crate::objects::{All, DeviceInfo, IwdObjectMap};
+use crate::opath::{is_iwd_managed_path, known_network_path, OPath};
+use crate::proxy::{
+	NetworkProxy, ObjectManagerProxy, P2pDeviceProxy, P2pPeerProxy,
+	StationDiagnosticProxy, StationProxy,
+};
+use crate::store::ObjectStore;
+use crate::types::{
+	HiddenAccessPoint, KnownNetwork, Network, NetworkType, P2pPeer,
+	Provisioning, SignalStrength, Ssid, Station, StationState, WifiBand,
+};
+
+/// How long [`traced`] waits for a reply.
+#[derive(Clone, Copy, Debug)]
+pub struct CallTimeouts {
+	/// For most calls, which iwd answers right away.
+	pub quick: Duration,
+	/// For calls that only return once a connection is established or has
+	/// failed: `Connect`, of networks and peers alike, and the WPS methods;
+	/// and for `Scan`, which [`scan_and_wait`] waits out.
+	pub slow: Duration,
+}
+
+impl CallTimeouts {
+	pub const DEFAULT: Self = CallTimeouts {
+		quick: Duration::from_secs(5),
+		slow: Duration::from_secs(120),
+	};
+
+	pub fn for_method(&self, method: &str) -> Duration {
+		match method {
+			"Connect" | "PushButton" | "StartPin" | "Scan" => self.slow,
+			_ => self.quick,
+		}
+	}
+}
+
+impl Default for CallTimeouts {
+	fn default() -> Self {
+		CallTimeouts::DEFAULT
+	}
+}
+
+/// Awaits a D-Bus method call inside a span carrying the method name and
+/// target object path, and logs how long the round trip took.
+///
+/// Gives up after the `timeouts` for `method`, with [`IwdError::Timeout`].
+pub async fn traced<T>(
+	timeouts: &CallTimeouts,
+	method: &'static str,
+	path: &zvariant::ObjectPath<'_>,
+	call: impl Future<Output = zbus::Result<T>>,
+) -> Result<T, IwdError> {
+	let timeout = timeouts.for_method(method);
+	let span = tracing::trace_span!("dbus", method, %path);
+	let start = Instant::now();
+	// `async-io` timers run on their own thread, whatever the executor
+	let timed_out = async {
+		Timer::after(timeout).await;
+		Err(IwdError::Timeout {
+			method,
+			path: path.to_owned().into(),
+			timeout,
+		})
+	};
+	let res = async { call.await.map_err(IwdError::from) }
+		.instrument(span.clone())
+		.or(timed_out)
+		.await;
+	span.in_scope(|| match &res {
+		Ok(_) => tracing::trace!(elapsed = ?start.elapsed(), "call finished"),
+		Err(err) => {
+			tracing::debug!(elapsed = ?start.elapsed(), %err, "call failed")
+		}
+	});
+	res
+}
+
+/// Runs `futures` at the same time, for their outputs in the order of
+/// `futures` rather than the order they finish in.
+pub async fn join_all<F: Future>(
+	futures: impl IntoIterator<Item = F>,
+) -> Vec<F::Output> {
+	let mut futures: Vec<_> = futures.into_iter().map(Box::pin).collect();
+	let mut outputs: Vec<_> = futures.iter().map(|_| None).collect();
+	futures_lite::future::poll_fn(|cx| {
+		let mut done = true;
+		for (future, output) in futures.iter_mut().zip(&mut outputs) {
+			if output.is_none() {
+				match future.as_mut().poll(cx) {
+					Poll::Ready(out) => *output = Some(out),
+					Poll::Pending => done = false,
+				}
+			}
+		}
+		match done {
+			true => Poll::Ready(()),
+			false => Poll::Pending,
+		}
+	})
+	.await;
+	outputs.into_iter().map(Option::unwrap).collect()
+}
+
+/// Requests a scan and waits for iwd to report that it's done.
+///
+/// If iwd refuses the request because a scan is already running, this waits
+/// for that one instead. The whole wait is bounded by the client's
+/// [`CallTimeouts`] for `Scan`.
+pub async fn scan_and_wait(
+	client: &IwdClient,
+	station: &StationProxy<'_>,
+) -> Result<(), IwdError> {
+	wait_for_scan(&client.timeouts, station).await
+}
+
+/// [`scan_and_wait`], for the blocking client too.
+pub(crate) async fn wait_for_scan(
+	timeouts: &CallTimeouts,
+	station: &StationProxy<'_>,
+) -> Result<(), IwdError> {
+	let path = station.inner().path();
+	let span = tracing::trace_span!("scan", %path);
+
+	let scan = async {
+		let mut changes = station.receive_scanning_changed().await;
+		// the cache may not have caught up with the reply to `Scan` yet
+		let uncached = StationProxy::builder(station.inner().connection())
+			.destination(station.inner().destination().to_owned())?
+			.path(path.to_owned())?
+			.cache_properties(CacheProperties::No)
+			.build()
+			.await?;
+
+		if let Err(err) = station.scan().await {
+			if !uncached.scanning().await? {
+				return Err(err);
+			}
+			tracing::debug!(%err, "joining the scan in progress");
+		}
+		// it may have finished before the change could be seen
+		if !uncached.scanning().await? {
+			return Ok(());
+		}
+		while let Some(change) = changes.next().await {
+			if !change.get().await? {
+				break;
+			}
+		}
+		Ok(())
+	};
+	traced(timeouts, "Scan", path, scan).instrument(span).await
+}
+
+/// A network the station sees, as `GetOrderedNetworks` has it, joined
+/// with its properties.
+///
+/// Serializes flat, with `ssid` and `type` `null` and `known` and
+/// `connected` `false` when the properties are missing.
+#[derive(Clone, Debug)]
+pub struct NetworkRow {
+	/// Object path of the network itself.
+	pub path: OPath<NetworkProxy<'static>>,
+	/// `None` if the network isn't in the snapshot it was looked up in,
+	/// e.g. gone since.
+	pub network: Option<Network>,
+	/// Signal strength in 1/100 dBm, as reported by iwd.
+	pub strength: SignalStrength,
+	/// Object path of the device the network was seen on.
+	pub device: OwnedObjectPath,
+	/// How many other networks with the same SSID and type were folded into
+	/// this one.
+	pub merged: usize,
+	/// The bands its access points are on, lowest first; empty if unknown,
+	/// see [`add_bands`].
+	pub bands: Vec<WifiBand>,
+}
+
+impl NetworkRow {
+	pub fn ssid(&self) -> Option<&Ssid> {
+		self.network.as_ref().map(Network::name)
+	}
+
+	pub fn network_type(&self) -> Option<NetworkType> {
+		self.network.as_ref().map(Network::network_type)
+	}
+
+	pub fn is_known(&self) -> bool {
+		self.network
+			.as_ref()
+			.is_some_and(|network| network.known_network().is_some())
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.network.as_ref().is_some_and(Network::is_connected)
+	}
+}
+
+impl Serialize for NetworkRow {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		#[derive(Serialize)]
+		struct Flat<'a> {
+			ssid: Option<&'a Ssid>,
+			#[serde(rename = "type")]
+			type_: Option<NetworkType>,
+			strength: SignalStrength,
+			known: bool,
+			connected: bool,
+			device: &'a OwnedObjectPath,
+			path: &'a OPath<NetworkProxy<'static>>,
+			merged: usize,
+			bands: &'a [WifiBand],
+		}
+
+		Flat {
+			ssid: self.ssid(),
+			type_: self.network_type(),
+			strength: self.strength,
+			known: self.is_known(),
+			connected: self.is_connected(),
+			device: &self.device,
+			path: &self.path,
+			merged: self.merged,
+			bands: &self.bands,
+		}
+		.serialize(serializer)
+	}
+}
+
+/// A connection to iwd and its object manager; the entry point of this
+/// crate.
+#[derive(Clone, Debug)]
+pub struct IwdClient {
+	conn: Connection,
+	manager: ObjectManagerProxy<'static>,
+	service: BusName<'static>,
+	timeouts: CallTimeouts,
+	skip_list: Arc<HashSet<OwnedInterfaceName>>,
+}
+
+impl IwdClient {
+	/// Talks to iwd on the system bus.
+	pub async fn system() -> Result<Self, IwdError> {
+		Self::new(Connection::system().await?).await
+	}
+
+	/// Talks to iwd over `conn`, e.g. a private bus in tests.
+	pub async fn new(conn: Connection) -> Result<Self, IwdError> {
+		Self::with_service(conn, DEFAULT_SERVICE).await
+	}
+
+	/// Talks to iwd under the name `service` over `conn`, e.g. a mock
+	/// registered under a scratch name.
+	pub async fn with_service(
+		conn: Connection,
+		service: &str,
+	) -> Result<Self, IwdError> {
+		let service =
+			BusName::try_from(service.to_owned()).map_err(zbus::Error::from)?;
+		let manager =
+			ObjectManagerProxy::new(&conn, service.clone(), "/").await?;
+		Ok(IwdClient {
+			conn,
+			manager,
+			service,
+			timeouts: CallTimeouts::DEFAULT,
+			skip_list: Arc::new(All::default_skip_list()),
+		})
+	}
+
+	/// Gives up on calls after `timeouts` rather than the
+	/// [`DEFAULT`](CallTimeouts::DEFAULT) ones.
+	pub fn with_timeouts(mut self, timeouts: CallTimeouts) -> Self {
+		self.timeouts = timeouts;
+		self
+	}
+
+	/// Drops the interfaces in `skip` rather than the
+	/// [`default_skip_list`](All::default_skip_list) from what
+	/// [`objects`](IwdClient::objects) returns.
+	pub fn with_skip_list(mut self, skip: HashSet<OwnedInterfaceName>) -> Self {
+		self.skip_list = Arc::new(skip);
+		self
+	}
+
+	/// For another bus or service name than [`system`](IwdClient::system)
+	/// uses.
+	pub fn builder() -> ClientBuilder {
+		ClientBuilder::default()
+	}
+
+	pub fn connection(&self) -> &Connection {
+		&self.conn
+	}
+
+	/// The name iwd is under, that proxies made with
+	/// [`OPath::proxy`] talk to.
+	pub fn service(&self) -> &BusName<'static> {
+		&self.service
+	}
+
+	/// How long calls through this client may take, see [`traced`].
+	pub fn timeouts(&self) -> &CallTimeouts {
+		&self.timeouts
+	}
+
+	/// Every object iwd manages, with its properties, less the interfaces
+	/// in the [skip list](IwdClient::with_skip_list).
+	pub async fn objects(&self) -> Result<IwdObjectMap, IwdError> {
+		let manager = self.manager.inner();
+		let reply = traced(
+			&self.timeouts,
+			"GetManagedObjects",
+			manager.path(),
+			manager.call_method("GetManagedObjects", &()),
+		)
+		.await
+		.map_err(|err| err.at_object_manager(&self.service))?;
+		// deserialized here rather than by the proxy, so that the skip list
+		// is only set while no `.await` can move this to another thread
+		let objects: IwdObjectMap = {
+			let _guard = All::skipping(HashSet::clone(&self.skip_list));
+			reply.body().deserialize()?
+		};
+		for (path, all) in &objects {
+			tracing::trace!(%path, ?all, "managed object");
+			if all.device.is_some() && !is_iwd_managed_path(path) {
+				tracing::debug!(%path, "device at an unexpected path");
+			}
+		}
+		Ok(objects)
+	}
+
+	pub async fn station(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Result<StationProxy<'static>, IwdError> {
+		OPath::from(path.clone()).proxy(self).await
+	}
+
+	/// Networks the station at `path` sees, best first, with their
+	/// properties from `objects`; those missing there are kept, see
+	/// [`NetworkRow::network`].
+	pub async fn ordered_networks(
+		&self,
+		objects: &IwdObjectMap,
+		path: &OwnedObjectPath,
+	) -> Result<Vec<NetworkRow>, IwdError> {
+		let station = self.station(path).await?;
+		visible_networks(self, objects, path, &station).await
+	}
+
+	/// `cached`, typically from one of the [`IwdObjectMap`] lookups, or else
+	/// the properties of the object at `path` as iwd has them now. `None`
+	/// if the object is gone, or lacks the interface.
+	pub async fn or_fetch<T>(
+		&self,
+		cached: Option<&T>,
+		path: &OwnedObjectPath,
+	) -> Result<Option<T>, IwdError>
+	where
+		T: zbus::Interface + Clone + DeserializeOwned + zvariant::Type,
+	{
+		if let Some(cached) = cached {
+			return Ok(Some(cached.clone()));
+		}
+		match fetch_properties(self, path).await {
+			Ok(properties) => Ok(Some(properties)),
+			// iwd answers `InvalidArgs` for an interface the object lacks
+			Err(err)
+				if err.is_dbus_error(
+					"org.freedesktop.DBus.Error.UnknownObject",
+				) || err.is_dbus_error(
+					"org.freedesktop.DBus.Error.InvalidArgs",
+				) =>
+			{
+				Ok(None)
+			}
+			Err(err) => Err(err),
+		}
+	}
+
+	/// The network `station` is connected to, with both looked up afresh
+	/// rather than in a snapshot.
+	pub async fn connected_network(
+		&self,
+		station: &StationProxy<'_>,
+	) -> Result<Option<Network>, IwdError> {
+		let path = station.inner().path().to_owned().into();
+		let Some(station) = self.or_fetch::<Station>(None, &path).await? else {
+			return Ok(None);
+		};
+		match station.connected_network() {
+			Some(network) => self.or_fetch(None, network.path()).await,
+			None => Ok(None),
+		}
+	}
+
+	/// Connects to the network at `path`, asking the registered agent for
+	/// secrets if needed.
+	pub async fn connect(
+		&self,
+		path: &OwnedObjectPath,
+	) -> Result<(), IwdError> {
+		let proxy: NetworkProxy = OPath::from(path.clone()).proxy(self).await?;
+		let path = proxy.inner().path();
+		traced(&self.timeouts, "Connect", path, proxy.connect()).await
+	}
+}
+
+/// Builds an [`IwdClient`], see [`IwdClient::builder`].
+#[derive(Clone, Default, Debug)]
+pub struct ClientBuilder {
+	bus: Bus,
+	service: Option<String>,
+	timeouts: CallTimeouts,
+	skip_list: Option<HashSet<OwnedInterfaceName>>,
+}
+
+impl ClientBuilder {
+	pub fn bus(mut self, bus: Bus) -> Self {
+		self.bus = bus;
+		self
+	}
+
+	/// Looks for iwd under `name`, e.g. a mock registered under a scratch
+	/// name, rather than [`DEFAULT_SERVICE`].
+	pub fn service(mut self, name: impl Into<String>) -> Self {
+		self.service = Some(name.into());
+		self
+	}
+
+	/// See [`IwdClient::with_timeouts`].
+	pub fn timeouts(mut self, timeouts: CallTimeouts) -> Self {
+		self.timeouts = timeouts;
+		self
+	}
+
+	/// See [`IwdClient::with_skip_list`].
+	pub fn skip_list(mut self, skip: HashSet<OwnedInterfaceName>) -> Self {
+		self.skip_list = Some(skip);
+		self
+	}
+
+	pub async fn build(self) -> Result<IwdClient, IwdError> {
+		let service = self.service.as_deref().unwrap_or(DEFAULT_SERVICE);
+		let client =
+			IwdClient::with_service(self.bus.connect().await?, service).await?;
+		let client = client.with_timeouts(self.timeouts);
+		Ok(match self.skip_list {
+			Some(skip) => client.with_skip_list(skip),
+			None => client,
+		})
+	}
+}
+
+/// [`IwdClient::objects`] for a one-off lookup, of the iwd under
+/// [`DEFAULT_SERVICE`].
+pub async fn fetch_objects(
+	conn: &Connection,
+) -> Result<IwdObjectMap, IwdError> {
+	IwdClient::new(conn.clone()).await?.objects().await
+}
+
+/// Every device, with its adapter, station and connected network, sorted
+/// by name; see [`DeviceInfo::collect`].
+pub async fn enumerate_devices(
+	conn: &Connection,
+) -> Result<Vec<DeviceInfo>, IwdError> {
+	Ok(DeviceInfo::collect(&fetch_objects(conn).await?))
+}
+
+/// The properties of the `T` at `path` alone, with `Properties.GetAll`,
+/// rather than every object's with `GetManagedObjects`.
+pub async fn fetch_properties<T>(
+	client: &IwdClient,
+	path: &zvariant::ObjectPath<'_>,
+) -> Result<T, IwdError>
+where
+	T: zbus::object_server::Interface + DeserializeOwned + zvariant::Type,
+{
+	let reply = traced(
+		&client.timeouts,
+		"GetAll",
+		path,
+		client.conn.call_method(
+			Some(&client.service),
+			path,
+			Some("org.freedesktop.DBus.Properties"),
+			"GetAll",
+			&(T::name(),),
+		),
+	)
+	.await?;
+	reply.body().deserialize().map_err(|err| match err {
+		zbus::Error::Variant(source) => IwdError::Deserialize {
+			path: path.to_owned().into(),
+			interface: T::name().to_string(),
+			source,
+		},
+		err => err.into(),
+	})
+}
+
+/// The known network called `ssid`, found by trying the path iwd would put
+/// it at for each type rather than listing them all; `None` if there is
+/// none. Hotspots are left out, as they're not named after an SSID.
+pub async fn find_known_network(
+	client: &IwdClient,
+	ssid: &Ssid,
+) -> Result<Option<(OwnedObjectPath, KnownNetwork)>, IwdError> {
+	let types = [
+		NetworkType::Psk,
+		NetworkType::Open,
+		NetworkType::Eap,
+		NetworkType::Wep,
+	];
+	for type_ in types {
+		let path = known_network_path(ssid, type_);
+		match fetch_properties::<KnownNetwork>(client, &path).await {
+			Ok(known) => return Ok(Some((path, known))),
+			Err(err) if err.is_unknown_object() => {}
+			Err(err) => return Err(err),
+		}
+	}
+	Ok(None)
+}
+
+/// [`IwdClient::connected_network`] for a one-off lookup, of the iwd under
+/// [`DEFAULT_SERVICE`].
+pub async fn fetch_connected_network(
+	conn: &Connection,
+	station: &StationProxy<'_>,
+) -> Result<Option<Network>, IwdError> {
+	IwdClient::new(conn.clone())
+		.await?
+		.connected_network(station)
+		.await
+}
+
+/// Looks up the `GetOrderedNetworks` results of the station at `device` in
+/// `objects` one by one, keeping iwd's order and the entries missing there.
+pub fn ordered<'a>(
+	networks: impl IntoIterator<
+		Item = (OPath<NetworkProxy<'static>>, SignalStrength),
+		IntoIter: 'a,
+	>,
+	objects: &'a IwdObjectMap,
+	device: &'a OwnedObjectPath,
+) -> impl Iterator<Item = NetworkRow> + 'a {
+	networks.into_iter().map(|(path, strength)| {
+		let network = objects.get_network(&path).cloned();
+		if network.is_none() {
+			tracing::debug!(
+				path = %path.path(),
+				"ordered network not in the snapshot"
+			);
+		}
+		NetworkRow {
+			path,
+			network,
+			strength,
+			device: device.clone(),
+			merged: 0,
+			bands: Vec::new(),
+		}
+	})
+}
+
+/// Fills in the bands of `networks` from the access points in `objects`,
+/// or else the networks' own frequency; only iwd 2 and later report either.
+pub fn add_bands(networks: &mut [NetworkRow], objects: &IwdObjectMap) {
+	for net in networks {
+		net.bands = objects.network_bands(net.path.path());
+		if net.bands.is_empty() {
+			net.bands
+				.extend(net.network.as_ref().and_then(Network::band));
+		}
+	}
+}
+
+/// What one scan found.
+#[derive(Clone, Debug)]
+pub struct ScanResult {
+	/// What `GetOrderedNetworks` returned, best first, each joined with its
+	/// properties.
+	pub networks: Vec<NetworkRow>,
+	/// Empty unless asked for, strongest first.
+	///
+	/// Kept apart from `networks`: iwd reports these as address, strength
+	/// and type, with neither an SSID nor a network object to put in a
+	/// [`NetworkRow`].
+	pub hidden: Vec<HiddenAccessPoint>,
+	/// When the scan finished.
+	pub scanned_at: Instant,
+}
+
+impl ScanResult {
+	pub fn is_stale(&self, max_age: Duration) -> bool {
+		self.scanned_at.elapsed() > max_age
+	}
+}
+
+/// The last [`ScanResult`], for callers to share, see [`shared_scan`].
+pub type SharedScan = Arc<async_lock::Mutex<Option<ScanResult>>>;
+
+/// Hidden access points `station` has seen, strongest first; none if iwd
+/// doesn't implement `GetHiddenAccessPoints`.
+pub async fn hidden_access_points(
+	client: &IwdClient,
+	station: &StationProxy<'_>,
+) -> Result<Vec<HiddenAccessPoint>, IwdError> {
+	let res = traced(
+		&client.timeouts,
+		"GetHiddenAccessPoints",
+		station.inner().path(),
+		station.get_hidden_access_points(),
+	)
+	.await;
+	match res {
+		Ok(hidden) => {
+			let mut hidden = hidden.into_vec();
+			hidden.sort_by_key(|ap| std::cmp::Reverse(ap.strength()));
+			Ok(hidden)
+		}
+		Err(err)
+			if err
+				.is_dbus_error("org.freedesktop.DBus.Error.UnknownMethod") =>
+		{
+			Ok(Vec::new())
+		}
+		Err(err) => Err(err),
+	}
+}
+
+/// Has iwd look for Wi-Fi Direct peers on `device` until `until` completes,
+/// following what it finds in `store`; discovery is released again however
+/// that ends. The objects as they are then.
+pub async fn discover_peers(
+	store: &ObjectStore,
+	device: &P2pDeviceProxy<'_>,
+	until: impl Future<Output = ()>,
+) -> Result<Arc<IwdObjectMap>, IwdError> {
+	let (path, timeouts) = (device.inner().path(), store.client().timeouts());
+	let mut updates = store.subscribe().await?;
+	traced(
+		timeouts,
+		"RequestDiscovery",
+		path,
+		device.request_discovery(),
+	)
+	.await?;
+	let found = async {
+		until.await;
+		Ok(())
+	}
+	.or(updates.run())
+	.await;
+	let released = traced(
+		timeouts,
+		"ReleaseDiscovery",
+		path,
+		device.release_discovery(),
+	)
+	.await;
+	found.and(released)?;
+	store.objects().await
+}
+
+/// The peers `device` found with the name `name`, strongest first; names
+/// aren't unique.
+pub async fn peers_named(
+	client: &IwdClient,
+	device: &P2pDeviceProxy<'_>,
+	objects: &IwdObjectMap,
+	name: &str,
+) -> Result<Vec<OPath<P2pPeerProxy<'static>>>, IwdError> {
+	let path = device.inner().path();
+	let peers = device.get_peers();
+	let mut peers = traced(&client.timeouts, "GetPeers", path, peers).await?;
+	peers.sort_by_key(|(_, strength)| std::cmp::Reverse(*strength));
+	Ok(peers
+		.into_iter()
+		.filter(|(path, _)| {
+			let peer = objects
+				.object(path)
+				.and_then(|all| all.extract::<P2pPeer>());
+			peer.is_some_and(|peer| peer.name() == name)
+		})
+		.map(|(path, _)| OPath::from(path))
+		.collect())
+}
+
+/// Connects to `peer`, which takes until the peer accepted, and returns it
+/// as it is then, with the group's interface and address.
+pub async fn connect_peer(
+	client: &IwdClient,
+	peer: &P2pPeerProxy<'_>,
+	provisioning: &Provisioning,
+) -> Result<P2pPeer, IwdError> {
+	let path = peer.inner().path();
+	let (method, pin) = (provisioning.method(), provisioning.pin());
+	traced(&client.timeouts, "Connect", path, peer.connect(method, pin))
+		.await?;
+	fetch_properties(client, path).await
+}
+
+/// How long ago the last scan of `station` finished, if iwd says; zero if
+/// that is in the future, as far as the clock here goes.
+pub fn scan_results_age(station: &Station) -> Option<Duration> {
+	let last_scan = station.last_scan()?;
+	Some(last_scan.elapsed().unwrap_or_default())
+}
+
+/// Whether the last scan of `station` is older than `max_age`, or iwd
+/// doesn't say when it was.
+pub fn needs_fresh_scan(station: &Station, max_age: Duration) -> bool {
+	scan_results_age(station).is_none_or(|age| age > max_age)
+}
+
+/// How old the results of iwd's last scan may be for [`scan_and_collect`]
+/// to use them rather than scan again.
+pub const SCAN_RESULTS_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Scans, then joins what `station` sees with the network properties in
+/// `objects`, see [`visible_networks`]. With
+/// `include_hidden`, hidden access points are collected as well, into
+/// [`ScanResult::hidden`] rather than among the networks.
+///
+/// Doesn't scan if `objects` says the last scan is younger than
+/// [`SCAN_RESULTS_MAX_AGE`].
+pub async fn scan_and_collect(
+	client: &IwdClient,
+	station: &StationProxy<'_>,
+	objects: &IwdObjectMap,
+	include_hidden: bool,
+) -> Result<ScanResult, IwdError> {
+	let path = OwnedObjectPath::from(station.inner().path().clone());
+	let age = objects
+		.find_station_for_device(&path)
+		.filter(|(_, s)| !needs_fresh_scan(s, SCAN_RESULTS_MAX_AGE))
+		.and_then(|(_, s)| scan_results_age(s));
+	let scanned_at = match age {
+		Some(age) => {
+			tracing::debug!(?age, "using the last scan's results");
+			Instant::now().checked_sub(age).unwrap_or_else(Instant::now)
+		}
+		None => {
+			scan_and_wait(client, station).await?;
+			Instant::now()
+		}
+	};
+	let networks = visible_networks(client, objects, &path, station).await?;
+	let hidden = match include_hidden {
+		true => hidden_access_points(client, station).await?,
+		false => Vec::new(),
+	};
+	Ok(ScanResult {
+		networks,
+		hidden,
+		scanned_at,
+	})
+}
+
+/// The result in `shared` unless it is older than `max_age`, otherwise a
+/// new scan that then replaces it. The lock is held while scanning, so
+/// that callers racing each other wait for the same scan.
+pub async fn shared_scan(
+	client: &IwdClient,
+	shared: &SharedScan,
+	station: &StationProxy<'_>,
+	objects: &IwdObjectMap,
+	max_age: Duration,
+) -> Result<ScanResult, IwdError> {
+	let mut last = shared.lock().await;
+	if let Some(res) = last.as_ref().filter(|res| !res.is_stale(max_age)) {
+		return Ok(res.clone());
+	}
+	let res = scan_and_collect(client, station, objects, false).await?;
+	*last = Some(res.clone());
+	Ok(res)
+}
+
+/// Known networks in `networks`, strongest first.
+pub fn best_known(mut networks: Vec<NetworkRow>) -> Vec<NetworkRow> {
+	networks.retain(NetworkRow::is_known);
+	networks.sort_by_key(|net| std::cmp::Reverse(net.strength));
+	networks
+}
+
+/// Scans and connects to the strongest known network in range, falling
+/// back to the next one for as long as connecting fails.
+pub async fn auto_connect_to_best_known(
+	station: &StationProxy<'_>,
+	client: &IwdClient,
+	objects: &IwdObjectMap,
+) -> Result<(), IwdError> {
+	let scan = scan_and_collect(client, station, objects, false).await?;
+	let candidates = best_known(scan.networks);
+	for net in &candidates {
+		let proxy = match net.path.clone().proxy(client).await {
+			Ok(proxy) => proxy,
+			Err(err) => {
+				tracing::info!(path = %net.path.path(), %err, "no proxy for it");
+				continue;
+			}
+		};
+		let path = proxy.inner().path();
+		match traced(&client.timeouts, "Connect", path, proxy.connect()).await {
+			Ok(()) => return Ok(()),
+			Err(err) => {
+				tracing::info!(path = %net.path.path(), %err, "connecting failed");
+			}
+		}
+	}
+
+	Err(IwdError::Failed(match candidates.len() {
+		0 => "no known network in range".to_owned(),
+		n => format!("failed to connect to all {n} known networks in range"),
+	}))
+}
+
+/// Delays that double from the first one up to a maximum, for waiting on
+/// iwd to come back.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+	next: Duration,
+	max: Duration,
+}
+
+impl Backoff {
+	pub const DEFAULT: Self =
+		Backoff::new(Duration::from_millis(250), Duration::from_secs(30));
+
+	pub const fn new(first: Duration, max: Duration) -> Self {
+		Backoff { next: first, max }
+	}
+
+	pub fn next_delay(&mut self) -> Duration {
+		let delay = self.next;
+		self.next = (self.next * 2).min(self.max);
+		delay
+	}
+}
+
+/// Signals iwd's name changing hands: iwd going away, or coming back.
+pub async fn receive_iwd_owner_changes(
+	client: &IwdClient,
+) -> Result<zbus::fdo::NameOwnerChangedStream<'static>, IwdError> {
+	let dbus = zbus::fdo::DBusProxy::new(&client.conn).await?;
+	Ok(dbus
+		.receive_name_owner_changed_with_args(&[(0, client.service.as_str())])
+		.await?)
+}
+
+/// Waits until iwd owns its name on the bus again, looking whenever the
+/// name changes hands and after each delay of `backoff`, in case the bus
+/// doesn't say.
+pub async fn wait_for_iwd(
+	client: &IwdClient,
+	mut backoff: Backoff,
+) -> Result<(), IwdError> {
+	let dbus = zbus::fdo::DBusProxy::new(&client.conn).await?;
+	let mut changes = receive_iwd_owner_changes(client).await?;
+
+	loop {
+		let has_owner = dbus
+			.name_has_owner(client.service.clone())
+			.await
+			.map_err(zbus::Error::from)?;
+		if has_owner {
+			return Ok(());
+		}
+		let delay = backoff.next_delay();
+		tracing::debug!(?delay, "waiting for iwd");
+		async {
+			changes.next().await;
+		}
+		.or(async {
+			Timer::after(delay).await;
+		})
+		.await;
+	}
+}
+
+/// Reconnects `station` whenever iwd drops its connection, retrying every
+/// `retry_interval` until it's connected again.
+///
+/// A dropped connection goes from connected straight to disconnected, while
+/// an explicit `Disconnect` passes through disconnecting, which is left
+/// alone. To run it in the background, spawn it with owned arguments on
+/// whichever executor the caller uses.
+pub async fn connection_watchdog(
+	station: &StationProxy<'_>,
+	client: &IwdClient,
+	mut objects: IwdObjectMap,
+	retry_interval: Duration,
+) -> ! {
+	loop {
+		let mut changes = station.receive_state_changed().await;
+		let mut last = station.state().await.ok();
+
+		while let Some(change) = changes.next().await {
+			let Ok(state) = change.get().await else {
+				continue;
+			};
+			let dropped = last == Some(StationState::Connected)
+				&& state == StationState::Disconnected;
+			last = Some(state);
+			if !dropped {
+				continue;
+			}
+
+			tracing::info!(?retry_interval, "connection dropped, reconnecting");
+			loop {
+				Timer::after(retry_interval).await;
+				match client.objects().await {
+					Ok(fresh) => objects = fresh,
+					Err(err) => tracing::warn!(%err, "using stale objects"),
+				}
+				match auto_connect_to_best_known(station, client, &objects)
+					.await
+				{
+					Ok(()) => break,
+					Err(err) => tracing::warn!(%err, "reconnecting failed"),
+				}
+				// somebody else took care of it
+				if station.state().await.ok()
+					!= Some(StationState::Disconnected)
+				{
+					break;
+				}
+			}
+		}
+
+		// iwd went away; its replacement gets a new stream
+		tracing::warn!("lost track of the station state");
+		Timer::after(retry_interval).await;
+	}
+}
+
+/// `GetOrderedNetworks`, joined with the network properties in `objects`;
+/// the networks missing there are kept, see [`ordered`].
+pub async fn visible_networks(
+	client: &IwdClient,
+	objects: &IwdObjectMap,
+	path: &OwnedObjectPath,
+	station: &StationProxy<'_>,
+) -> Result<Vec<NetworkRow>, IwdError> {
+	let entries = traced(
+		&client.timeouts,
+		"GetOrderedNetworks",
+		station.inner().path(),
+		station.get_ordered_networks(),
+	)
+	.await?;
+	let mut networks: Vec<_> =
+		ordered(entries.into_vec(), objects, path).collect();
+	add_bands(&mut networks, objects);
+	Ok(networks)
+}
+
+/// `StationDiagnostic.GetDiagnostics` of the station at `path`. Not every
+/// iwd offers it, and it fails when not connected, hence the `Option`.
+pub async fn station_diagnostics(
+	client: &IwdClient,
+	path: &OwnedObjectPath,
+) -> Option<HashMap<String, zvariant::OwnedValue>> {
+	let proxy: StationDiagnosticProxy =
+		OPath::from(path.clone()).proxy(client).await.ok()?;
+	traced(
+		&client.timeouts,
+		"GetDiagnostics",
+		proxy.inner().path(),
+		proxy.get_diagnostics(),
+	)
+	.await
+	.ok()
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::time::Duration;
+
+use zbus::zvariant::{self, OwnedObjectPath};
+
+/// Whether `err` is the D-Bus error called `name`.
+pub fn is_dbus_error(err: &zbus::Error, name: &str) -> bool {
+	use zbus::DBusError;
+
+	match err {
+		zbus::Error::FDO(err) => err.name() == name,
+		zbus::Error::MethodError(err_name, _, _) => err_name.as_str() == name,
+		_ => false,
+	}
+}
+
+pub fn is_service_unknown(err: &zbus::Error) -> bool {
+	is_dbus_error(err, "org.freedesktop.DBus.Error.ServiceUnknown")
+}
+
+/// Whether `err` says nobody owns the name called, the way the bus says so
+/// for a method call or a name lookup.
+pub fn is_name_unowned(err: &zbus::Error) -> bool {
+	is_service_unknown(err)
+		|| is_dbus_error(err, "org.freedesktop.DBus.Error.NameHasNoOwner")
+}
+
+/// Whether `err` says the object or interface called isn't there.
+pub fn is_unknown_object(err: &zbus::Error) -> bool {
+	[
+		"org.freedesktop.DBus.Error.UnknownObject",
+		"org.freedesktop.DBus.Error.UnknownInterface",
+		"org.freedesktop.DBus.Error.UnknownMethod",
+	]
+	.iter()
+	.any(|name| is_dbus_error(err, name))
+}
+
+macro_rules! iwd_error_kinds {
+	($($kind:ident => $desc:literal),+ $(,)?) => {
+		/// The errors iwd replies with, `net.connman.iwd.<kind>`.
+		#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+		pub enum IwdErrorKind {
+			$($kind,)+
+			/// One this crate doesn't know about yet, by its full name.
+			Other(String),
+		}
+
+		impl IwdErrorKind {
+			/// `None` unless `name` is in iwd's namespace.
+			pub fn from_name(name: &str) -> Option<Self> {
+				let kind = name.strip_prefix("net.connman.iwd.")?;
+				Some(match kind {
+					$(stringify!($kind) => IwdErrorKind::$kind,)+
+					_ => IwdErrorKind::Other(name.to_owned()),
+				})
+			}
+
+			fn description(&self) -> &str {
+				match self {
+					$(IwdErrorKind::$kind => $desc,)+
+					IwdErrorKind::Other(name) => name,
+				}
+			}
+		}
+	};
+}
+
+iwd_error_kinds! {
+	Busy => "iwd is busy with another operation",
+	Failed => "the operation failed",
+	InvalidArguments => "invalid arguments",
+	InvalidFormat => "invalid format",
+	AlreadyExists => "it already exists",
+	NotFound => "not found",
+	NotSupported => "not supported",
+	NoAgent => "no agent is registered to ask for secrets",
+	NotConnected => "not connected",
+	NotConfigured => "not configured",
+	NotImplemented => "not implemented",
+	ServiceSetOverlap => "more than one network matches",
+	AlreadyProvisioned => "already provisioned",
+	NotHidden => "the network is not hidden",
+	Aborted => "the operation was aborted",
+	NotAvailable => "not available",
+}
+
+impl fmt::Display for IwdErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.description())
+	}
+}
+
+/// Everything the library's own functions fail with.
+#[derive(Debug, thiserror::Error)]
+pub enum IwdError {
+	/// iwd refused, with one of its own errors; `message` is the one iwd
+	/// sent along, if any.
+	#[error("iwd: {}", message.as_deref().unwrap_or(kind.description()))]
+	Iwd {
+		kind: IwdErrorKind,
+		message: Option<String>,
+	},
+	#[error("{method} on {path} timed out after {timeout:?}")]
+	Timeout {
+		method: &'static str,
+		path: OwnedObjectPath,
+		timeout: Duration,
+	},
+	/// The properties of an interface didn't match what this crate expects.
+	#[error("unexpected {interface} properties on {path}")]
+	Deserialize {
+		path: OwnedObjectPath,
+		interface: String,
+		#[source]
+		source: zvariant::Error,
+	},
+	#[error(transparent)]
+	Dbus(zbus::Error),
+	/// Building the proxy of the object at `path` failed.
+	#[error("failed to create {proxy} at {}", path.as_str())]
+	Proxy {
+		/// The type of the proxy, e.g. `StationProxy`.
+		proxy: &'static str,
+		path: OwnedObjectPath,
+		#[source]
+		source: zbus::Error,
+	},
+	/// Nobody owns iwd's name on the bus.
+	#[error("iwd does not appear to be running (is iwd.service started?)")]
+	NotRunning(#[source] zbus::Error),
+	/// Something owns iwd's name, but has no object manager at `/`.
+	#[error("{service} does not look like iwd")]
+	NotIwd {
+		service: String,
+		#[source]
+		source: zbus::Error,
+	},
+	/// Every call went through, but the operation still didn't succeed.
+	#[error("{0}")]
+	Failed(String),
+	/// Not below an adapter and device, see
+	/// [`assert_iwd_path`](crate::opath::assert_iwd_path).
+	#[error("{} is not an iwd device path", .0.as_str())]
+	InvalidPath(OwnedObjectPath),
+	/// Something is served at the path an agent was to be served at.
+	#[error("{} is already served", .0.as_str())]
+	PathInUse(OwnedObjectPath),
+	/// Rejected before calling iwd, which would have refused it too.
+	#[error("invalid arguments: {0}")]
+	InvalidArguments(String),
+}
+
+impl IwdError {
+	/// Whether iwd refused with `kind`.
+	pub fn is_iwd(&self, kind: IwdErrorKind) -> bool {
+		matches!(self, IwdError::Iwd { kind: k, .. } if *k == kind)
+	}
+
+	/// Whether iwd went away under the call, e.g. to restart.
+	pub fn is_gone(&self) -> bool {
+		match self {
+			IwdError::NotRunning(_) => true,
+			IwdError::Proxy { source, .. } if is_name_unowned(source) => true,
+			err => err.is_dbus_error("org.freedesktop.DBus.Error.NoReply"),
+		}
+	}
+
+	/// For a failed `GetManagedObjects` on `/`: whoever answered to
+	/// `service` isn't iwd if it has no object manager there.
+	pub(crate) fn at_object_manager(self, service: &str) -> Self {
+		match self {
+			IwdError::Dbus(err) if is_unknown_object(&err) => {
+				IwdError::NotIwd {
+					service: service.to_owned(),
+					source: err,
+				}
+			}
+			err => err,
+		}
+	}
+
+	/// Whether the object or interface called isn't there, see
+	/// [`is_unknown_object`]; iwd says `InvalidArgs` for an interface the
+	/// object doesn't have.
+	pub fn is_unknown_object(&self) -> bool {
+		match self {
+			IwdError::Dbus(err) => {
+				is_unknown_object(err)
+					|| is_dbus_error(
+						err,
+						"org.freedesktop.DBus.Error.InvalidArgs",
+					)
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether this is the D-Bus error called `name`, see [`is_dbus_error`].
+	pub fn is_dbus_error(&self, name: &str) -> bool {
+		match self {
+			IwdError::Dbus(err)
+			| IwdError::NotRunning(err)
+			| IwdError::NotIwd { source: err, .. }
+			| IwdError::Proxy { source: err, .. } => is_dbus_error(err, name),
+			IwdError::Iwd { kind, .. } => {
+				IwdErrorKind::from_name(name).as_ref() == Some(kind)
+			}
+			_ => false,
+		}
+	}
+}
+
+impl From<zbus::Error> for IwdError {
+	fn from(err: zbus::Error) -> Self {
+		if let zbus::Error::MethodError(name, message, _) = &err {
+			if let Some(kind) = IwdErrorKind::from_name(name) {
+				return IwdError::Iwd {
+					kind,
+					message: message.clone(),
+				};
+			}
+		}
+		if is_name_unowned(&err) {
+			return IwdError::NotRunning(err);
+		}
+		IwdError::Dbus(err)
+	}
+}
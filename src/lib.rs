@@ -0,0 +1,45 @@
+//! Client for iwd's D-Bus API.
+//!
+//! [`IwdClient`] is the entry point; the property dicts and enums iwd
+//! reports are in [`types`], and the objects it manages in [`objects`].
+
+#![warn(clippy::all)]
+
+/// Gives the targets the D-Bus signature of `$ty`, the type they are sent
+/// as; generic ones as `impl<T> Target<T>`.
+macro_rules! zvar_type {
+	($ty:ty, [ $($target:ty),+ $(,)? ]) => {
+		$(zvar_type!($ty, impl<> $target);)+
+	};
+	($ty:ty, impl<$($param:ident),*> $target:ty) => {
+		impl<$($param),*> ::zbus::zvariant::Type for $target {
+			#[inline]
+			fn signature() -> ::zbus::zvariant::Signature<'static> {
+				<$ty as ::zbus::zvariant::Type>::signature()
+			}
+		}
+	};
+}
+
+/// [`zvar_type!`] for the types sent as a string.
+macro_rules! zvar_type_str {
+	($($target:ty),+ $(,)?) => {
+		zvar_type!(String, [$($target),+]);
+	};
+}
+
+pub mod agent;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod bus;
+pub mod client;
+pub mod error;
+pub mod objects;
+pub mod opath;
+pub mod profile;
+pub mod proxy;
+pub mod store;
+pub mod types;
+
+pub use client::IwdClient;
+pub use error::IwdError;
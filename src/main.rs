@@ -0,0 +1,1283 @@
+#![warn(clippy::all)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_lite::FutureExt;
+use iwd_playground::bus::{Bus, DEFAULT_SERVICE};
+use iwd_playground::client::{wait_for_iwd, Backoff, CallTimeouts, NetworkRow};
+use iwd_playground::error::{is_dbus_error, is_name_unowned, IwdErrorKind};
+use iwd_playground::objects::IwdObjectMap;
+use iwd_playground::opath::is_iwd_managed_path;
+use iwd_playground::types::{
+	Adapter, BasicServiceSet, KnownNetwork, NetworkType, P2pDisplay, P2pPeer,
+	Station, WifiBand, WpsPin,
+};
+use iwd_playground::{IwdClient, IwdError};
+use serde::Serialize;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{zvariant, Connection};
+
+mod cli;
+
+use cli::escape::unescape_field;
+use cli::render::{ColorChoice, SignalFormat, SsidFormat};
+use cli::session::Session;
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+compile_error!("the binary needs the async-std or the tokio feature");
+
+/// Spawning on the executor picked by the features: tokio's if enabled,
+/// async-std's otherwise. Everything else sticks to runtime-neutral crates.
+mod rt {
+	use std::future::Future;
+
+	#[cfg(not(feature = "tokio"))]
+	use async_std::task::{spawn as spawn_task, spawn_blocking, JoinHandle};
+	#[cfg(feature = "tokio")]
+	use tokio::task::{spawn as spawn_task, spawn_blocking, JoinHandle};
+
+	pub struct Task<T>(JoinHandle<T>);
+
+	impl<T> Task<T> {
+		/// Stops the task and waits until it's gone.
+		pub async fn cancel(self) {
+			#[cfg(feature = "tokio")]
+			{
+				self.0.abort();
+				let _ = self.0.await;
+			}
+			#[cfg(not(feature = "tokio"))]
+			self.0.cancel().await;
+		}
+	}
+
+	pub fn spawn<F>(future: F) -> Task<F::Output>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		Task(spawn_task(future))
+	}
+
+	/// Runs `f` on a thread where it may block.
+	pub async fn unblock<T: Send + 'static>(
+		f: impl FnOnce() -> T + Send + 'static,
+	) -> T {
+		let task = spawn_blocking(f);
+		#[cfg(feature = "tokio")]
+		let task = async { task.await.expect("blocking task panicked") };
+		task.await
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+enum Format {
+	/// Human-readable [`Table`].
+	///
+	/// [`Table`]: cli::render::Table
+	#[default]
+	Table,
+	/// A single [`JsonReport`] document.
+	Json,
+	/// Tab-separated lines, see [`porcelain_lines`].
+	///
+	/// [`porcelain_lines`]: cli::render::porcelain_lines
+	Porcelain,
+}
+
+/// Order of the network listing.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+enum SortKey {
+	/// iwd's own ranking, as returned by `GetOrderedNetworks`.
+	#[default]
+	Iwd,
+	/// Case-insensitively by SSID.
+	Name,
+	/// Strongest first.
+	Signal,
+	/// Open networks first, then by increasing strength of security.
+	Security,
+}
+
+impl SortKey {
+	fn sort(self, rows: &mut [NetworkRow]) {
+		// the networks gone from the snapshot, without a name, go last
+		fn by_name(a: &NetworkRow, b: &NetworkRow) -> std::cmp::Ordering {
+			let key = |row: &NetworkRow| {
+				let ssid = row.ssid();
+				(
+					ssid.is_none(),
+					ssid.map(|s| s.to_str_lossy().to_lowercase()),
+				)
+			};
+			key(a).cmp(&key(b)).then_with(|| a.ssid().cmp(&b.ssid()))
+		}
+
+		match self {
+			SortKey::Iwd => {}
+			SortKey::Name => rows.sort_by(by_name),
+			SortKey::Signal => rows.sort_by(|a, b| {
+				b.strength.cmp(&a.strength).then_with(|| by_name(a, b))
+			}),
+			SortKey::Security => rows.sort_by(|a, b| {
+				let key = |row: &NetworkRow| {
+					(row.network_type().is_none(), row.network_type())
+				};
+				key(a).cmp(&key(b)).then_with(|| by_name(a, b))
+			}),
+		}
+	}
+}
+
+/// Restrictions on the network listing, all of which have to hold.
+#[derive(Clone, Default, Debug)]
+struct Filters {
+	/// Only networks of these types; any type if empty.
+	types: Vec<NetworkType>,
+	/// Only networks with (or without) a known network profile.
+	known: Option<bool>,
+	/// Only the connected network.
+	connected: bool,
+}
+
+impl Filters {
+	/// Drops the rows not matching, and returns how many rows each filter
+	/// removed, for those that removed any.
+	fn apply(&self, rows: &mut Vec<NetworkRow>) -> Vec<(&'static str, usize)> {
+		let mut hidden = Vec::new();
+		let mut retain = |name, keep: &dyn Fn(&NetworkRow) -> bool| {
+			let before = rows.len();
+			rows.retain(|row| keep(row));
+			if rows.len() < before {
+				hidden.push((name, before - rows.len()));
+			}
+		};
+
+		if !self.types.is_empty() {
+			retain("--type", &|row| {
+				row.network_type().is_some_and(|t| self.types.contains(&t))
+			});
+		}
+		match self.known {
+			Some(true) => retain("--known", &|row| row.is_known()),
+			Some(false) => retain("--unknown", &|row| !row.is_known()),
+			None => {}
+		}
+		if self.connected {
+			retain("--connected", &|row| row.is_connected());
+		}
+
+		hidden
+	}
+}
+
+const USAGE: &str = "\
+usage: iwd-get-networks [OPTIONS] [COMMAND]
+
+Commands:
+  list            scan, then list visible networks (default)
+  scan            scan and wait for it to finish
+  status          show the state of the station
+  connect SSID    connect to a visible network
+  disconnect      disconnect the station
+  known           list known networks
+  forget SSID     forget a known network, by SSID or object path
+  bss SSID        list the access points of a network
+  adapters        list wireless adapters
+  peers           discover Wi-Fi Direct peers
+  peer-connect NAME
+                  connect to a Wi-Fi Direct peer, discovering it first
+  peer-disconnect NAME
+                  disconnect from a connected Wi-Fi Direct peer
+  monitor         print property changes as iwd reports them
+  waybar          print the status as a Waybar custom module
+  bar             print the status as one line of text, or down
+  metrics         serve Prometheus metrics over HTTP
+  menu            scan, then print networks for a picker like fzf or dmenu
+  select          connect to the network picked from menu's output, read
+                  from standard input
+
+Options:
+  --device=NAME   use this interface instead of the first station
+  --bus=BUS       find iwd on system (default), session or the bus at
+                  this address, e.g. unix:path=/run/netns/bus
+  --service=NAME  find iwd under this bus name (default net.connman.iwd)
+  --json          print JSON
+  --porcelain     print tab-separated lines, for scripts
+  --color=WHEN    auto, always or never
+  --timeout=SECS  give up after this many seconds; monitor, metrics and
+                  following the status stop then instead
+  --call-timeout=SECS
+                  wait this long for iwd to answer a call (default 5);
+                  connecting may take up to 120 seconds regardless
+  -q, --quiet     only log errors
+  -v, --verbose   log more; twice to trace every D-Bus call
+  -h, --help      print this help
+
+Options for list:
+  --type=TYPE,..  only networks of these types (open, psk, 8021x, ...)
+  --known         only networks with a known network profile
+  --unknown       only networks without one
+  --connected     only the connected network
+  --dedup         show networks with the same SSID and type once
+  --sort=KEY      iwd, name, signal or security
+  --ssid=FORMAT   text or hex
+  --signal=UNIT   dbm, percent or bars
+  --tui           pick a network to connect to interactively, asking for
+                  passphrases as needed (needs the tui feature)
+
+Options for menu:
+  --signal=UNIT   dbm, percent or bars
+
+  iwd-get-networks menu | fzf -d '\\t' --with-nth=..-2 |
+    iwd-get-networks select
+
+Options for status:
+  --short         print one line of space-separated fields: state, device,
+                  network, signal and security, with N/A for the unknown,
+                  e.g. CONNECTED wlan0 home -52dBm WPA2
+
+Options for monitor:
+  --notify        send a desktop notification on connecting and
+                  disconnecting
+
+Options for waybar and bar:
+  --follow        print a new line whenever the status changes
+  --interval=SECS print a new line this often
+  --prefix=TEXT   print this before the status (bar only)
+  --suffix=TEXT   print this after the status (bar only)
+
+Options for metrics:
+  --listen=ADDR   address and port to listen on (default 127.0.0.1:9477)
+
+Options for peers and peer-connect:
+  --discover=SECS look for peers this long (default 10)
+  --pin=PIN       connect with this 4 or 8 digit PIN instead of the push
+                  button (peer-connect only)
+
+Exit status:
+  0  success
+  1  any other failure
+  2  invalid command line
+  3  iwd is not running
+  4  no such network or peer
+  5  authentication failed
+  6  timed out
+  7  not connected
+  8  nothing selected
+";
+
+/// An invalid invocation; reported along with [`USAGE`].
+#[derive(Debug)]
+struct UsageError(String);
+
+impl fmt::Display for UsageError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl std::error::Error for UsageError {}
+
+macro_rules! usage_error {
+	($($arg:tt)*) => {
+		UsageError(format!($($arg)*))
+	};
+}
+
+/// Exit statuses, for scripts to branch on; listed in [`USAGE`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Exit {
+	Failure = 1,
+	Usage = 2,
+	IwdNotRunning = 3,
+	NoSuchNetwork = 4,
+	AuthenticationFailed = 5,
+	TimedOut = 6,
+	NotConnected = 7,
+	NothingSelected = 8,
+}
+
+impl Exit {
+	/// The status for the first cause in the chain of `err` that has a
+	/// dedicated one.
+	fn classify(err: &anyhow::Error) -> Self {
+		err.chain()
+			.find_map(|cause| {
+				if let Some(err) = cause.downcast_ref::<CliError>() {
+					return Some(err.exit());
+				}
+				if let Some(err) = cause.downcast_ref::<IwdError>() {
+					return Exit::from_iwd(err);
+				}
+				Exit::from_dbus(cause.downcast_ref::<zbus::Error>()?)
+			})
+			.unwrap_or(Exit::Failure)
+	}
+
+	fn from_iwd(err: &IwdError) -> Option<Self> {
+		match err {
+			IwdError::Dbus(err) => Exit::from_dbus(err),
+			IwdError::NotRunning(_) | IwdError::NotIwd { .. } => {
+				Some(Exit::IwdNotRunning)
+			}
+			IwdError::Timeout { .. } => Some(Exit::TimedOut),
+			IwdError::Iwd {
+				kind: IwdErrorKind::NotConnected,
+				..
+			} => Some(Exit::NotConnected),
+			_ => None,
+		}
+	}
+
+	fn from_dbus(err: &zbus::Error) -> Option<Self> {
+		const NAMES: &[(&str, Exit)] = &[
+			(
+				"org.freedesktop.DBus.Error.ServiceUnknown",
+				Exit::IwdNotRunning,
+			),
+			(
+				"org.freedesktop.DBus.Error.NameHasNoOwner",
+				Exit::IwdNotRunning,
+			),
+			("org.freedesktop.DBus.Error.NoReply", Exit::TimedOut),
+			("org.freedesktop.DBus.Error.Timeout", Exit::TimedOut),
+			("net.connman.iwd.NotConnected", Exit::NotConnected),
+		];
+		NAMES
+			.iter()
+			.find(|(name, _)| is_dbus_error(err, name))
+			.map(|(_, exit)| *exit)
+	}
+}
+
+impl From<Exit> for ExitCode {
+	fn from(exit: Exit) -> Self {
+		ExitCode::from(exit as u8)
+	}
+}
+
+/// Failures of the commands themselves that have a dedicated [`Exit`]
+/// status.
+#[derive(Debug)]
+enum CliError {
+	NoSuchNetwork(String),
+	NoSuchKnownNetwork(String),
+	NoSuchPeer(String),
+	/// `Connect` failed the way a wrong passphrase makes it fail.
+	AuthenticationFailed(IwdError),
+	TimedOut(Duration),
+	/// `select` got no line, e.g. because the picker was dismissed.
+	NothingSelected,
+	/// iwd's name changed hands while watching, so the paths are stale.
+	IwdRestarted,
+	/// iwd reports no `BasicServiceSet` objects, as before iwd 2.
+	NoBssInfo,
+	/// No adapter has a P2P device, or iwd won't discover with it.
+	P2pUnsupported,
+}
+
+impl CliError {
+	fn exit(&self) -> Exit {
+		match self {
+			CliError::NoSuchNetwork(_)
+			| CliError::NoSuchKnownNetwork(_)
+			| CliError::NoSuchPeer(_) => Exit::NoSuchNetwork,
+			CliError::AuthenticationFailed(_) => Exit::AuthenticationFailed,
+			CliError::TimedOut(_) => Exit::TimedOut,
+			CliError::NothingSelected => Exit::NothingSelected,
+			CliError::IwdRestarted => Exit::IwdNotRunning,
+			CliError::NoBssInfo | CliError::P2pUnsupported => Exit::Failure,
+		}
+	}
+}
+
+impl fmt::Display for CliError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CliError::NoSuchNetwork(ssid) => {
+				write!(f, "no network called {ssid:?} in range")
+			}
+			CliError::NoSuchKnownNetwork(ssid) => {
+				write!(f, "no known network called {ssid:?}")
+			}
+			CliError::NoSuchPeer(name) => {
+				write!(f, "no peer called {name:?} in range")
+			}
+			CliError::AuthenticationFailed(_) => {
+				f.write_str("authentication failed")
+			}
+			CliError::TimedOut(timeout) => {
+				write!(f, "timed out after {timeout:?}")
+			}
+			CliError::NothingSelected => f.write_str("nothing selected"),
+			CliError::IwdRestarted => f.write_str("iwd restarted"),
+			CliError::NoBssInfo => {
+				f.write_str("iwd doesn't report access points before iwd 2")
+			}
+			CliError::P2pUnsupported => {
+				f.write_str("Wi-Fi Direct is not supported by this adapter")
+			}
+		}
+	}
+}
+
+impl std::error::Error for CliError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			CliError::AuthenticationFailed(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+fn parse_secs(secs: &str) -> Result<Duration, UsageError> {
+	secs.parse()
+		.ok()
+		.and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+		.ok_or_else(|| usage_error!("invalid number of seconds: {secs}"))
+}
+
+/// What to do, picked by the first positional argument.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+enum Command {
+	#[default]
+	List,
+	Scan,
+	Status,
+	Connect {
+		ssid: String,
+	},
+	Disconnect,
+	Known,
+	Forget {
+		ssid: String,
+	},
+	Bss {
+		ssid: String,
+	},
+	Adapters,
+	Peers,
+	PeerConnect {
+		name: String,
+	},
+	PeerDisconnect {
+		name: String,
+	},
+	Monitor,
+	Waybar,
+	Bar,
+	Metrics,
+	Menu,
+	Select,
+}
+
+/// What to run and how, from the command line.
+#[derive(Clone, Default, Debug)]
+struct Config {
+	command: Command,
+	/// Interface name of the device to use, e.g. `wlan0`.
+	device: Option<String>,
+	bus: Bus,
+	/// Replaces [`DEFAULT_SERVICE`].
+	service: Option<String>,
+	format: Format,
+	color: ColorChoice,
+	verbosity: Verbosity,
+	/// Limit on the whole command, see [`run`].
+	timeout: Option<Duration>,
+	/// Replaces [`CallTimeouts::quick`].
+	call_timeout: Option<Duration>,
+	signal: SignalFormat,
+	ssid: SsidFormat,
+	/// Show networks with the same SSID and security type only once.
+	dedup: bool,
+	sort: SortKey,
+	filters: Filters,
+	/// Print `status` as one line of space-separated fields.
+	short: bool,
+	/// Send desktop notifications from `monitor`.
+	notify: bool,
+	/// Keep printing updates on changes.
+	follow: bool,
+	/// Keep printing updates this often.
+	interval: Option<Duration>,
+	prefix: String,
+	suffix: String,
+	/// Where `metrics` listens, [`Exporter::DEFAULT_LISTEN`] if not given.
+	///
+	/// [`Exporter::DEFAULT_LISTEN`]: cli::metrics::Exporter::DEFAULT_LISTEN
+	listen: Option<String>,
+	/// How long `peers` looks for peers, [`DISCOVERY_PERIOD`] if not given.
+	discover: Option<Duration>,
+	/// Provisioning for `peer-connect`, the push button if not given.
+	pin: Option<WpsPin>,
+	/// Start the interactive interface.
+	#[cfg(feature = "tui")]
+	tui: bool,
+}
+
+impl Config {
+	fn from_args() -> Result<Self, UsageError> {
+		Self::parse(std::env::args().skip(1))
+	}
+
+	/// Options and positional arguments may be mixed; everything after `--`
+	/// is positional.
+	fn parse(
+		args: impl IntoIterator<Item = String>,
+	) -> Result<Self, UsageError> {
+		let mut config = Config::default();
+		let mut positional = Vec::new();
+		let mut args = args.into_iter();
+
+		while let Some(arg) = args.next() {
+			if arg == "--" {
+				positional.extend(args.by_ref());
+				break;
+			}
+			if !arg.starts_with('-') {
+				positional.push(arg);
+				continue;
+			}
+			if let Some(types) = arg.strip_prefix("--type=") {
+				for ty in types.split(',') {
+					let ty = ty.parse::<NetworkType>().map_err(|err| {
+						usage_error!("invalid network type: {err}")
+					})?;
+					config.filters.types.push(ty);
+				}
+				continue;
+			}
+			if let Some(name) = arg.strip_prefix("--device=") {
+				config.device = Some(name.to_owned());
+				continue;
+			}
+			if let Some(bus) = arg.strip_prefix("--bus=") {
+				config.bus =
+					bus.parse().map_err(|err| usage_error!("{err}"))?;
+				continue;
+			}
+			if let Some(name) = arg.strip_prefix("--service=") {
+				config.service = Some(name.to_owned());
+				continue;
+			}
+			if let Some(secs) = arg.strip_prefix("--timeout=") {
+				config.timeout = Some(parse_secs(secs)?);
+				continue;
+			}
+			if let Some(secs) = arg.strip_prefix("--interval=") {
+				config.interval = Some(parse_secs(secs)?);
+				continue;
+			}
+			if let Some(prefix) = arg.strip_prefix("--prefix=") {
+				prefix.clone_into(&mut config.prefix);
+				continue;
+			}
+			if let Some(listen) = arg.strip_prefix("--listen=") {
+				config.listen = Some(listen.to_owned());
+				continue;
+			}
+			if let Some(suffix) = arg.strip_prefix("--suffix=") {
+				suffix.clone_into(&mut config.suffix);
+				continue;
+			}
+			if let Some(secs) = arg.strip_prefix("--discover=") {
+				config.discover = Some(parse_secs(secs)?);
+				continue;
+			}
+			if let Some(pin) = arg.strip_prefix("--pin=") {
+				config.pin =
+					Some(pin.parse().map_err(|err| usage_error!("{err}"))?);
+				continue;
+			}
+			if let Some(secs) = arg.strip_prefix("--call-timeout=") {
+				config.call_timeout = Some(parse_secs(secs)?);
+				continue;
+			}
+
+			match arg.as_str() {
+				"-h" | "--help" => {
+					print!("{USAGE}");
+					std::process::exit(0);
+				}
+				"-q" | "--quiet" => config.verbosity = Verbosity::Quiet,
+				"-v" | "--verbose" => {
+					config.verbosity = config.verbosity.louder()
+				}
+				"-vv" => config.verbosity = config.verbosity.louder().louder(),
+				"--json" => config.format = Format::Json,
+				"--porcelain" => config.format = Format::Porcelain,
+				"--color=auto" => config.color = ColorChoice::Auto,
+				"--color=always" => config.color = ColorChoice::Always,
+				"--color=never" => config.color = ColorChoice::Never,
+				"--dedup" => config.dedup = true,
+				"--known" => config.filters.known = Some(true),
+				"--unknown" => config.filters.known = Some(false),
+				"--connected" => config.filters.connected = true,
+				"--sort=iwd" => config.sort = SortKey::Iwd,
+				"--sort=name" => config.sort = SortKey::Name,
+				"--sort=signal" => config.sort = SortKey::Signal,
+				"--sort=security" => config.sort = SortKey::Security,
+				"--ssid=text" => config.ssid = SsidFormat::Text,
+				"--ssid=hex" => config.ssid = SsidFormat::Hex,
+				"--signal=dbm" => config.signal = SignalFormat::Dbm,
+				"--signal=percent" => config.signal = SignalFormat::Percent,
+				"--signal=bars" => config.signal = SignalFormat::Bars,
+				"--short" => config.short = true,
+				"--notify" => config.notify = true,
+				"--follow" => config.follow = true,
+				#[cfg(feature = "tui")]
+				"--tui" => config.tui = true,
+				_ => return Err(usage_error!("unknown option: {arg}")),
+			}
+		}
+
+		let mut positional = positional.into_iter();
+		let command = positional.next();
+		let mut operand = |cmd, what| {
+			positional
+				.next()
+				.ok_or_else(|| usage_error!("{cmd} needs {what}"))
+		};
+		config.command = match command.as_deref() {
+			None | Some("list") => Command::List,
+			Some("scan") => Command::Scan,
+			Some("status") => Command::Status,
+			Some("connect") => Command::Connect {
+				ssid: operand("connect", "an SSID")?,
+			},
+			Some("disconnect") => Command::Disconnect,
+			Some("known") => Command::Known,
+			Some("forget") => Command::Forget {
+				ssid: operand("forget", "an SSID")?,
+			},
+			Some("bss") => Command::Bss {
+				ssid: operand("bss", "an SSID")?,
+			},
+			Some("adapters") => Command::Adapters,
+			Some("peers") => Command::Peers,
+			Some("peer-connect") => Command::PeerConnect {
+				name: operand("peer-connect", "a peer name")?,
+			},
+			Some("peer-disconnect") => Command::PeerDisconnect {
+				name: operand("peer-disconnect", "a peer name")?,
+			},
+			Some("monitor") => Command::Monitor,
+			Some("waybar") => Command::Waybar,
+			Some("bar") => Command::Bar,
+			Some("metrics") => Command::Metrics,
+			Some("menu") => Command::Menu,
+			Some("select") => Command::Select,
+			Some(cmd) => return Err(usage_error!("unknown command: {cmd}")),
+		};
+		if let Some(arg) = positional.next() {
+			return Err(usage_error!("unexpected argument: {arg}"));
+		}
+
+		Ok(config)
+	}
+
+	/// Whether the command keeps going until interrupted.
+	fn watches(&self) -> bool {
+		match self.command {
+			Command::Monitor | Command::Metrics => true,
+			Command::Waybar | Command::Bar => {
+				self.follow || self.interval.is_some()
+			}
+			_ => false,
+		}
+	}
+
+	/// The name iwd is under.
+	fn service(&self) -> &str {
+		self.service.as_deref().unwrap_or(DEFAULT_SERVICE)
+	}
+
+	fn timeouts(&self) -> CallTimeouts {
+		CallTimeouts {
+			quick: self.call_timeout.unwrap_or(CallTimeouts::DEFAULT.quick),
+			..CallTimeouts::DEFAULT
+		}
+	}
+
+	/// Runs a one-shot command, once more after [`RESTART_DELAY`] if iwd
+	/// went away meanwhile.
+	async fn run_retrying(&self, conn: &Connection) -> anyhow::Result<()> {
+		let run = || async {
+			Session::new(self.clone(), conn.clone()).await?.run().await
+		};
+		match run().await {
+			Err(err) if iwd_gone(&err) => {
+				tracing::info!(%err, "iwd went away, trying again");
+				async_io::Timer::after(RESTART_DELAY).await;
+				run().await
+			}
+			res => res,
+		}
+	}
+
+	/// Runs a watching command, starting over with fresh objects whenever
+	/// iwd comes back after going away.
+	async fn run_resuming(&self, conn: &Connection) -> anyhow::Result<()> {
+		let mut last_line = None;
+		loop {
+			let res = match Session::new(self.clone(), conn.clone()).await {
+				Ok(mut session) => {
+					session.last_line = Mutex::new(last_line.take());
+					let res = session.run().await;
+					last_line = session
+						.last_line
+						.into_inner()
+						.unwrap_or_else(|e| e.into_inner());
+					res
+				}
+				Err(err) => Err(err),
+			};
+			match res {
+				Err(err) if iwd_gone(&err) => {
+					tracing::warn!(%err, "waiting for iwd to come back");
+					let client =
+						IwdClient::with_service(conn.clone(), self.service())
+							.await?;
+					wait_for_iwd(&client, Backoff::DEFAULT).await?;
+				}
+				res => return res,
+			}
+		}
+	}
+}
+
+/// How long one-shot commands wait before trying again after iwd went away.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// How long `peers` looks for peers unless `--discover` says.
+const DISCOVERY_PERIOD: Duration = Duration::from_secs(10);
+
+/// Whether `err` comes from iwd going away, or restarting.
+fn iwd_gone(err: &anyhow::Error) -> bool {
+	err.chain().any(|cause| {
+		if let Some(err) = cause.downcast_ref::<IwdError>() {
+			return err.is_gone();
+		}
+		if let Some(err) = cause.downcast_ref::<zbus::Error>() {
+			return is_name_unowned(err)
+				|| is_dbus_error(err, "org.freedesktop.DBus.Error.NoReply");
+		}
+		matches!(cause.downcast_ref(), Some(CliError::IwdRestarted))
+	})
+}
+
+/// Top-level document printed by `--json`.
+///
+/// Field names are stable; enums are spelled the way iwd spells them on the
+/// wire (`psk`, `8021x`, `ad-hoc`, ...).
+#[derive(Default, Debug, Serialize)]
+struct JsonReport<'a> {
+	/// The station used for scanning, `null` if there is none.
+	status: Option<JsonStatus<'a>>,
+	/// Visible networks, in iwd's order (best first) unless `--sort` is given.
+	networks: Vec<NetworkRow>,
+	/// Every known network profile, sorted by name.
+	known_networks: Vec<JsonKnownNetwork<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonStatus<'a> {
+	/// Interface name of the device, e.g. `wlan0`.
+	device: &'a str,
+	/// Object path of the station.
+	path: &'a OwnedObjectPath,
+	#[serde(flatten)]
+	station: &'a Station,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonKnownNetwork<'a> {
+	/// Object path of the known network.
+	path: &'a OwnedObjectPath,
+	#[serde(flatten)]
+	known_network: &'a KnownNetwork,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonBss<'a> {
+	/// Object path of the access point.
+	path: &'a OwnedObjectPath,
+	/// The type of the network it belongs to.
+	#[serde(rename = "type")]
+	type_: NetworkType,
+	band: Option<WifiBand>,
+	#[serde(flatten)]
+	bss: &'a BasicServiceSet,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonPeer<'a> {
+	/// Object path of the peer.
+	path: &'a OwnedObjectPath,
+	#[serde(flatten)]
+	peer: &'a P2pPeer,
+	/// Left out unless it does Wi-Fi Display.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	display: Option<&'a P2pDisplay>,
+}
+
+impl<'a> JsonPeer<'a> {
+	/// `peer` at `path`, with its display capabilities from `objects`.
+	fn new(
+		objects: &'a IwdObjectMap,
+		path: &'a OwnedObjectPath,
+		peer: &'a P2pPeer,
+	) -> Self {
+		let display = objects.object(path).and_then(|all| all.extract());
+		JsonPeer {
+			path,
+			peer,
+			display,
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+struct JsonAdapter<'a> {
+	/// Object path of the adapter.
+	path: &'a OwnedObjectPath,
+	#[serde(flatten)]
+	adapter: &'a Adapter,
+}
+
+/// A network picked from [`menu_lines`]'s output.
+///
+/// [`menu_lines`]: cli::render::menu_lines
+#[derive(Debug)]
+enum Selection {
+	Path(OwnedObjectPath),
+	Ssid(String),
+}
+
+impl Selection {
+	/// Takes the object path from the end of `line`, or if there is none,
+	/// e.g. because only the SSID was passed on, the first field as the
+	/// SSID. `None` for a blank line.
+	fn parse(line: &str) -> Option<Self> {
+		let line = line.trim_end_matches(['\n', '\r']);
+		if line.trim().is_empty() {
+			return None;
+		}
+
+		let last = line.rsplit('\t').next().unwrap_or(line);
+		if let Ok(path) = zvariant::ObjectPath::try_from(last) {
+			if is_iwd_managed_path(&path) {
+				return Some(Selection::Path(path.into()));
+			}
+		}
+
+		let ssid = line.split('\t').next().unwrap_or(line);
+		Some(Selection::Ssid(
+			unescape_field(ssid).unwrap_or_else(|| ssid.to_owned()),
+		))
+	}
+}
+
+/// Folds networks sharing both SSID and security type into a single entry,
+/// as seen with dual-band access points.
+///
+/// The connected network is kept if it's one of them, the strongest one
+/// otherwise; the entry stays where the first of the group was.
+fn dedup_networks(networks: Vec<NetworkRow>) -> Vec<NetworkRow> {
+	use std::collections::hash_map::Entry;
+
+	let mut out: Vec<NetworkRow> = Vec::with_capacity(networks.len());
+	let mut seen = HashMap::new();
+
+	for net in networks {
+		let Some(network) = &net.network else {
+			out.push(net);
+			continue;
+		};
+		match seen.entry((network.name().clone(), network.network_type())) {
+			Entry::Vacant(e) => {
+				e.insert(out.len());
+				out.push(net);
+			}
+			Entry::Occupied(e) => {
+				let kept = &mut out[*e.get()];
+				let merged = kept.merged + net.merged + 1;
+				let mut bands = [&kept.bands[..], &net.bands].concat();
+				bands.sort_unstable();
+				bands.dedup();
+				if (net.is_connected(), net.strength)
+					> (kept.is_connected(), kept.strength)
+				{
+					*kept = net;
+				}
+				kept.merged = merged;
+				kept.bands = bands;
+			}
+		}
+	}
+
+	out
+}
+
+/// How much to log, set by `-q` and `-v`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+enum Verbosity {
+	/// Errors only.
+	Quiet,
+	/// Warnings and up.
+	#[default]
+	Normal,
+	/// Which station is used, scans, and other steps along the way.
+	Verbose,
+	/// Every D-Bus call with its timing, and every object iwd reported.
+	Trace,
+}
+
+impl Verbosity {
+	fn level(self) -> tracing_subscriber::filter::LevelFilter {
+		use tracing_subscriber::filter::LevelFilter;
+
+		match self {
+			Verbosity::Quiet => LevelFilter::ERROR,
+			Verbosity::Normal => LevelFilter::WARN,
+			Verbosity::Verbose => LevelFilter::DEBUG,
+			Verbosity::Trace => LevelFilter::TRACE,
+		}
+	}
+
+	fn louder(self) -> Self {
+		match self {
+			Verbosity::Quiet => Verbosity::Normal,
+			Verbosity::Normal => Verbosity::Verbose,
+			Verbosity::Verbose | Verbosity::Trace => Verbosity::Trace,
+		}
+	}
+}
+
+/// Logs go to stderr, filtered by `RUST_LOG` if set. Otherwise our own
+/// events are filtered by `verbosity`, and dependencies only get to warn.
+fn init_tracing(verbosity: Verbosity, json: bool) {
+	use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+
+	let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| {
+		let level = verbosity.level();
+		let crate_name = env!("CARGO_CRATE_NAME");
+		format!("{},{crate_name}={level}", level.min(LevelFilter::WARN))
+	});
+	let filter = EnvFilter::builder().parse_lossy(directives);
+	let subscriber = tracing_subscriber::fmt()
+		.with_env_filter(filter)
+		.with_writer(std::io::stderr);
+
+	if json {
+		subscriber.json().init();
+	} else {
+		subscriber.init();
+	}
+}
+
+/// Prints `value` as a single line of JSON.
+fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+	print!("{}", json_line(value)?);
+	Ok(())
+}
+
+/// `value` as a single line of JSON.
+fn json_line(value: &impl Serialize) -> anyhow::Result<String> {
+	Ok(serde_json::to_string(value)? + "\n")
+}
+
+/// Runs the command on `conn`, giving up after `--timeout` if given;
+/// commands that watch just stop watching then.
+async fn run(conn: Connection, config: Config) -> anyhow::Result<()> {
+	let watches = config.watches();
+	let run = async {
+		if watches {
+			config.run_resuming(&conn).await
+		} else {
+			config.run_retrying(&conn).await
+		}
+	};
+
+	match config.timeout {
+		Some(timeout) => {
+			let timed_out = async {
+				async_io::Timer::after(timeout).await;
+				if watches {
+					Ok(())
+				} else {
+					Err(CliError::TimedOut(timeout).into())
+				}
+			};
+			run.or(timed_out).await
+		}
+		None => run.await,
+	}
+}
+
+#[cfg_attr(feature = "tokio", tokio::main)]
+#[cfg_attr(not(feature = "tokio"), async_std::main)]
+async fn main() -> ExitCode {
+	let config = match Config::from_args() {
+		Ok(config) => config,
+		Err(err) => {
+			eprint!("error: {err}\n\n{USAGE}");
+			return Exit::Usage.into();
+		}
+	};
+	init_tracing(config.verbosity, config.format == Format::Json);
+
+	let res = match config.bus.connect().await {
+		Ok(conn) => run(conn, config).await,
+		Err(err) => Err(err.into()),
+	};
+	match res {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(err) => {
+			eprintln!("Error: {err:?}");
+			Exit::classify(&err).into()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use iwd_playground::types::SignalStrength;
+	use zbus::zvariant::serialized::Context;
+	use zbus::zvariant::{ObjectPath, Value};
+
+	use super::*;
+	use crate::cli::render::menu_lines;
+
+	/// An error reply called `name`, the way a failed call returns it.
+	fn method_error(name: &str) -> zbus::Error {
+		let call = zbus::Message::method("/", "Call").unwrap().build(&());
+		let reply = zbus::Message::method_error(&call.unwrap(), name);
+		zbus::Error::from(reply.unwrap().build(&()).unwrap())
+	}
+
+	const ERROR_NAMES: [(&str, Option<u8>); 9] = [
+		("org.freedesktop.DBus.Error.ServiceUnknown", Some(3)),
+		("org.freedesktop.DBus.Error.NameHasNoOwner", Some(3)),
+		("org.freedesktop.DBus.Error.NoReply", Some(6)),
+		("org.freedesktop.DBus.Error.Timeout", Some(6)),
+		("net.connman.iwd.NotConnected", Some(7)),
+		("org.freedesktop.DBus.Error.AccessDenied", None),
+		("org.freedesktop.DBus.Error.UnknownObject", None),
+		("net.connman.iwd.Failed", None),
+		("net.connman.iwd.Busy", None),
+	];
+
+	#[test]
+	fn dbus_errors_by_name() {
+		for (name, code) in ERROR_NAMES {
+			let exit = Exit::from_dbus(&method_error(name));
+			assert_eq!(exit.map(|exit| exit as u8), code, "{name}");
+
+			let exit = Exit::from_iwd(&IwdError::from(method_error(name)));
+			assert_eq!(exit.map(|exit| exit as u8), code, "{name}");
+
+			let err = anyhow::Error::from(method_error(name)).context("list");
+			let exit = Exit::classify(&err);
+			assert_eq!(exit as u8, code.unwrap_or(1), "{name}");
+		}
+	}
+
+	#[test]
+	fn iwd_errors() {
+		let path = OwnedObjectPath::try_from("/net/connman/iwd/0/4").unwrap();
+		let cases = [
+			(
+				IwdError::Timeout {
+					method: "Connect",
+					path: path.clone(),
+					timeout: Duration::from_secs(1),
+				},
+				Some(Exit::TimedOut),
+			),
+			(
+				IwdError::NotIwd {
+					service: DEFAULT_SERVICE.to_owned(),
+					source: method_error("org.freedesktop.DBus.Error.Failed"),
+				},
+				Some(Exit::IwdNotRunning),
+			),
+			(IwdError::Failed("no".to_owned()), None),
+			(IwdError::InvalidPath(path), None),
+		];
+		for (err, exit) in cases {
+			assert_eq!(Exit::from_iwd(&err), exit, "{err}");
+		}
+	}
+
+	#[test]
+	fn cli_errors_come_first() {
+		let auth = IwdError::from(method_error("net.connman.iwd.Failed"));
+		let cases = [
+			(CliError::NoSuchNetwork("home".to_owned()), 4),
+			(CliError::NoSuchKnownNetwork("home".to_owned()), 4),
+			(CliError::NoSuchPeer("tv".to_owned()), 4),
+			(CliError::AuthenticationFailed(auth), 5),
+			(CliError::TimedOut(Duration::from_secs(1)), 6),
+			(CliError::NothingSelected, 8),
+			(CliError::IwdRestarted, 3),
+			(CliError::NoBssInfo, 1),
+			(CliError::P2pUnsupported, 1),
+		];
+		for (err, code) in cases {
+			let name = err.to_string();
+			let err = anyhow::Error::from(err).context("outer");
+			assert_eq!(Exit::classify(&err) as u8, code, "{name}");
+		}
+
+		let err = anyhow::anyhow!("something else");
+		assert_eq!(Exit::classify(&err), Exit::Failure);
+	}
+
+	const WLAN0: &str = "/net/connman/iwd/0/4";
+	const WLAN1: &str = "/net/connman/iwd/1/5";
+
+	/// The network `ssid` of `type_` as seen by the station at `device`.
+	fn row(
+		device: &str,
+		ssid: &str,
+		type_: &str,
+		strength: i16,
+		connected: bool,
+	) -> NetworkRow {
+		let properties: HashMap<_, _> = [
+			("Name", Value::from(ssid)),
+			("Type", Value::from(type_)),
+			("Connected", Value::from(connected)),
+			("Device", Value::from(ObjectPath::try_from(device).unwrap())),
+		]
+		.into();
+		let ctxt = Context::new_dbus(zvariant::LE, 0);
+		let data = zvariant::to_bytes(ctxt, &properties).unwrap();
+		let hex: String = ssid.bytes().map(|b| format!("{b:02x}")).collect();
+		let path = format!("{device}/{hex}_{type_}");
+		NetworkRow {
+			path: OwnedObjectPath::try_from(path).unwrap().into(),
+			network: Some(data.deserialize().unwrap().0),
+			strength: SignalStrength::from_centi_dbm(strength),
+			device: OwnedObjectPath::try_from(device).unwrap(),
+			merged: 0,
+			bands: Vec::new(),
+		}
+	}
+
+	fn summary(rows: &[NetworkRow]) -> Vec<(&str, &str, usize)> {
+		rows.iter()
+			.map(|row| {
+				let ssid = row.ssid().unwrap().as_str().unwrap();
+				(ssid, row.device.as_str(), row.merged)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn dedup_keeps_types_apart() {
+		let rows = dedup_networks(vec![
+			row(WLAN0, "home", "psk", -5000, false),
+			row(WLAN1, "home", "open", -6000, false),
+		]);
+		assert_eq!(summary(&rows), [("home", WLAN0, 0), ("home", WLAN1, 0)]);
+	}
+
+	#[test]
+	fn dedup_keeps_the_strongest_in_place() {
+		let mut weak = row(WLAN0, "home", "psk", -7000, false);
+		weak.bands = vec![WifiBand::TwoPointFour];
+		let mut strong = row(WLAN1, "home", "psk", -5000, false);
+		strong.bands = vec![WifiBand::Five];
+		let rows = dedup_networks(vec![
+			weak,
+			row(WLAN0, "cafe", "open", -6000, false),
+			strong,
+		]);
+		assert_eq!(summary(&rows), [("home", WLAN1, 1), ("cafe", WLAN0, 0)]);
+		assert_eq!(rows[0].strength, SignalStrength::from_centi_dbm(-5000));
+		assert_eq!(rows[0].bands, [WifiBand::TwoPointFour, WifiBand::Five]);
+	}
+
+	#[test]
+	fn dedup_keeps_the_connected_entry() {
+		let rows = dedup_networks(vec![
+			row(WLAN1, "home", "psk", -4000, false),
+			row(WLAN0, "home", "psk", -8000, true),
+		]);
+		assert_eq!(summary(&rows), [("home", WLAN0, 1)]);
+		assert!(rows[0].is_connected());
+	}
+
+	#[test]
+	fn dedup_counts_every_merge() {
+		let rows = dedup_networks(vec![
+			row(WLAN0, "home", "psk", -6000, false),
+			row(WLAN1, "home", "psk", -5000, false),
+			row(WLAN0, "home", "psk", -7000, false),
+		]);
+		assert_eq!(summary(&rows), [("home", WLAN1, 2)]);
+	}
+
+	#[test]
+	fn dedup_passes_missing_networks_through() {
+		let mut gone = row(WLAN1, "home", "psk", -5000, false);
+		gone.network = None;
+		let rows =
+			dedup_networks(vec![row(WLAN0, "home", "psk", -6000, false), gone]);
+		assert_eq!(rows.len(), 2);
+		assert!(rows[1].network.is_none());
+		assert_eq!(rows[0].merged, 0);
+	}
+
+	fn ssid(line: &str) -> Option<String> {
+		match Selection::parse(line)? {
+			Selection::Ssid(ssid) => Some(ssid),
+			Selection::Path(path) => panic!("{line:?} gave {path}"),
+		}
+	}
+
+	#[test]
+	fn menu_lines_select_their_network() {
+		let rows = vec![
+			row(WLAN0, "my home", "psk", -5000, true),
+			row(WLAN0, "tab\there", "open", -6000, false),
+			row(WLAN0, "\x1b[31mred\\", "8021x", -7000, false),
+			row(WLAN1, "日本 ", "psk", -8000, false),
+		];
+		for signal in [SignalFormat::Dbm, SignalFormat::Bars] {
+			let menu = menu_lines(&rows, signal);
+			let lines: Vec<_> = menu.split_inclusive('\n').collect();
+			assert_eq!(lines.len(), rows.len(), "{menu:?}");
+			for (line, row) in lines.into_iter().zip(&rows) {
+				match Selection::parse(line) {
+					Some(Selection::Path(path)) => {
+						assert_eq!(path.as_str(), row.path.path().as_str())
+					}
+					other => panic!("{line:?} gave {other:?}"),
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn bare_ssids_select_by_name() {
+		assert_eq!(ssid("my home").as_deref(), Some("my home"));
+		assert_eq!(ssid("my home\r\n").as_deref(), Some("my home"));
+		assert_eq!(ssid(" padded ").as_deref(), Some(" padded "));
+		assert_eq!(ssid("tab\\there").as_deref(), Some("tab\there"));
+		assert_eq!(ssid("back\\\\slash").as_deref(), Some("back\\slash"));
+		assert_eq!(ssid("odd\\q").as_deref(), Some("odd\\q"));
+		assert_eq!(ssid("home\tpsk\t-50 dBm").as_deref(), Some("home"));
+		assert_eq!(ssid("/home").as_deref(), Some("/home"));
+		assert_eq!(ssid(""), None);
+		assert_eq!(ssid(" \t\n"), None);
+	}
+}
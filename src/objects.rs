@@ -0,0 +1,901 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use zbus::fdo;
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath};
+
+use crate::opath::{FromObjectPath, OPath};
+use crate::proxy::{
+	AdapterProxy, BasicServiceSetProxy, DeviceProxy, KnownNetworkProxy,
+	NetworkProxy, P2pDeviceProxy, P2pDisplayProxy, P2pPeerProxy, StationProxy,
+};
+use crate::types::{
+	Adapter, BasicServiceSet, Device, DeviceMode, KnownNetwork, Network,
+	P2pDisplay, P2pPeer, Station, WifiBand,
+};
+
+// The properties as iwd has them, so the structs can stand in for iwd's
+// objects; the getters are named apart from the structs' own accessors.
+
+/// What the getters of optional properties fail with, which leaves them
+/// out of `GetAll` like iwd does.
+fn unset() -> fdo::Error {
+	fdo::Error::UnknownProperty("not set".to_owned())
+}
+
+#[zbus::interface(name = "net.connman.iwd.Station")]
+impl Station {
+	#[zbus(property, name = "State")]
+	fn state_property(&self) -> &str {
+		self.state().as_str()
+	}
+
+	#[zbus(property, name = "ConnectedNetwork")]
+	fn connected_network_property(&self) -> fdo::Result<ObjectPath<'_>> {
+		self.connected_network()
+			.map(ObjectPath::from)
+			.ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "DisconnectedReason")]
+	fn disconnected_reason_property(&self) -> fdo::Result<&str> {
+		self.disconnected_reason().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Scanning")]
+	fn scanning_property(&self) -> bool {
+		self.is_scanning()
+	}
+
+	#[zbus(property, name = "LastScan")]
+	fn last_scan_property(&self) -> fdo::Result<u64> {
+		let last_scan = self.last_scan().ok_or_else(unset)?;
+		let since_epoch = last_scan.duration_since(SystemTime::UNIX_EPOCH);
+		Ok(since_epoch.unwrap_or_default().as_secs())
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.Device")]
+impl Device {
+	#[zbus(property, name = "Name")]
+	fn name_property(&self) -> &str {
+		self.name()
+	}
+
+	#[zbus(property, name = "Address")]
+	fn address_property(&self) -> String {
+		self.address().to_string()
+	}
+
+	#[zbus(property, name = "Powered")]
+	fn powered_property(&self) -> bool {
+		self.is_powered()
+	}
+
+	#[zbus(property, name = "Adapter")]
+	fn adapter_property(&self) -> ObjectPath<'_> {
+		self.adapter().into()
+	}
+
+	#[zbus(property, name = "Mode")]
+	fn mode_property(&self) -> &str {
+		self.mode().as_str()
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.Network")]
+impl Network {
+	#[zbus(property, name = "Name")]
+	fn name_property(&self) -> String {
+		self.name().to_string()
+	}
+
+	#[zbus(property, name = "Type")]
+	fn type_property(&self) -> &str {
+		self.network_type().as_str()
+	}
+
+	#[zbus(property, name = "Connected")]
+	fn connected_property(&self) -> bool {
+		self.is_connected()
+	}
+
+	#[zbus(property, name = "Device")]
+	fn device_property(&self) -> ObjectPath<'_> {
+		self.device().into()
+	}
+
+	#[zbus(property, name = "KnownNetwork")]
+	fn known_network_property(&self) -> fdo::Result<ObjectPath<'_>> {
+		self.known_network().map(ObjectPath::from).ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Frequency")]
+	fn frequency_property(&self) -> fdo::Result<u32> {
+		self.frequency().ok_or_else(unset)
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.KnownNetwork")]
+impl KnownNetwork {
+	#[zbus(property, name = "Name")]
+	fn name_property(&self) -> String {
+		self.name().to_string()
+	}
+
+	#[zbus(property, name = "Type")]
+	fn type_property(&self) -> &str {
+		self.network_type().as_str()
+	}
+
+	#[zbus(property, name = "Hidden")]
+	fn hidden_property(&self) -> bool {
+		self.is_hidden()
+	}
+
+	#[zbus(property, name = "LastConnectedTime")]
+	fn last_connected_time_property(&self) -> &str {
+		self.last_connected_time()
+	}
+
+	#[zbus(property, name = "AutoConnect")]
+	fn auto_connect_property(&self) -> bool {
+		self.auto_connect()
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.Adapter")]
+impl Adapter {
+	#[zbus(property, name = "Name")]
+	fn name_property(&self) -> &str {
+		self.name()
+	}
+
+	#[zbus(property, name = "Powered")]
+	fn powered_property(&self) -> bool {
+		self.is_powered()
+	}
+
+	#[zbus(property, name = "Model")]
+	fn model_property(&self) -> fdo::Result<&str> {
+		self.model().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Vendor")]
+	fn vendor_property(&self) -> fdo::Result<&str> {
+		self.vendor().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "SupportedModes")]
+	fn supported_modes_property(&self) -> Vec<&str> {
+		self.supported_modes()
+			.iter()
+			.map(DeviceMode::as_str)
+			.collect()
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.BasicServiceSet")]
+impl BasicServiceSet {
+	#[zbus(property, name = "Address")]
+	fn address_property(&self) -> String {
+		self.address().to_string()
+	}
+
+	#[zbus(property, name = "Frequency")]
+	fn frequency_property(&self) -> fdo::Result<u32> {
+		self.frequency().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "RSSI")]
+	fn rssi_property(&self) -> fdo::Result<i16> {
+		let strength = self.strength().ok_or_else(unset)?;
+		Ok(strength.centi_dbm() / 100)
+	}
+
+	#[zbus(property, name = "Network")]
+	fn network_property(&self) -> fdo::Result<ObjectPath<'_>> {
+		self.network().map(ObjectPath::from).ok_or_else(unset)
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.p2p.Peer")]
+impl P2pPeer {
+	#[zbus(property, name = "Name")]
+	fn name_property(&self) -> &str {
+		self.name()
+	}
+
+	#[zbus(property, name = "DeviceCategory")]
+	fn device_category_property(&self) -> fdo::Result<&str> {
+		self.device_category().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "DeviceSubcategory")]
+	fn device_subcategory_property(&self) -> fdo::Result<&str> {
+		self.device_subcategory().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Device")]
+	fn device_property(&self) -> fdo::Result<ObjectPath<'_>> {
+		self.device().map(ObjectPath::from).ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Connected")]
+	fn connected_property(&self) -> bool {
+		self.is_connected()
+	}
+
+	#[zbus(property, name = "ConnectedInterface")]
+	fn connected_interface_property(&self) -> fdo::Result<&str> {
+		self.connected_interface().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "ConnectedIP")]
+	fn connected_ip_property(&self) -> fdo::Result<&str> {
+		self.connected_ip().ok_or_else(unset)
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.p2p.Display")]
+impl P2pDisplay {
+	#[zbus(property, name = "Source")]
+	fn source_property(&self) -> fdo::Result<bool> {
+		self.source().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Sink")]
+	fn sink_property(&self) -> fdo::Result<bool> {
+		self.sink().ok_or_else(unset)
+	}
+
+	#[zbus(property, name = "Port")]
+	fn port_property(&self) -> fdo::Result<u16> {
+		self.port().ok_or_else(unset)
+	}
+}
+
+/// The interface of P2P devices, which has no struct of its own; the
+/// device is usually another object than the station of the same adapter.
+pub const P2P_DEVICE_INTERFACE: &str = "net.connman.iwd.p2p.Device";
+
+/// Properties of the interfaces without a struct of their own.
+pub type Rest = HashMap<
+	zbus::names::OwnedInterfaceName,
+	HashMap<String, zvariant::OwnedValue>,
+>;
+
+/// The interfaces of one object iwd manages, with their properties.
+#[derive(Default, PartialEq, Debug)]
+pub struct All {
+	pub(crate) station: Option<Station>,
+	pub(crate) device: Option<Device>,
+	pub(crate) network: Option<Network>,
+	pub(crate) known_network: Option<KnownNetwork>,
+	pub(crate) adapter: Option<Adapter>,
+	pub(crate) basic_service_set: Option<BasicServiceSet>,
+	pub(crate) p2p_peer: Option<P2pPeer>,
+	pub(crate) p2p_display: Option<P2pDisplay>,
+	pub(crate) rest: Rest,
+}
+
+zvar_type!(Rest, [All]);
+
+/// [`All::clone_lossy`].
+impl Clone for All {
+	fn clone(&self) -> Self {
+		self.clone_lossy()
+	}
+}
+
+/// What an object is to iwd, see [`All::dominant_role`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum IwdRole {
+	Station,
+	Device,
+	Network,
+	KnownNetwork,
+	Adapter,
+	BasicServiceSet,
+	P2pPeer,
+	P2pDisplay,
+	/// None of the interfaces with a struct of their own.
+	Unknown,
+}
+
+thread_local! {
+	/// Interfaces dropped instead of being collected into [`All::rest`].
+	static SKIP_LIST: RefCell<HashSet<zbus::names::OwnedInterfaceName>> =
+		RefCell::new(All::default_skip_list());
+}
+
+/// Puts back the skip list it replaced once dropped, see [`All::skipping`].
+/// It's tied to its thread, so it can't be held across an `.await` in a
+/// task that may move to another.
+#[must_use]
+#[derive(Debug)]
+pub struct SkipListGuard {
+	previous: HashSet<zbus::names::OwnedInterfaceName>,
+	_thread: PhantomData<*const ()>,
+}
+
+impl Drop for SkipListGuard {
+	fn drop(&mut self) {
+		let previous = std::mem::take(&mut self.previous);
+		SKIP_LIST.set(previous);
+	}
+}
+
+impl All {
+	/// The interfaces every object implements, which carry no properties:
+	/// `org.freedesktop.DBus.{Properties,Introspectable,Peer}`.
+	pub fn default_skip_list() -> HashSet<zbus::names::OwnedInterfaceName> {
+		[
+			"org.freedesktop.DBus.Properties",
+			"org.freedesktop.DBus.Introspectable",
+			"org.freedesktop.DBus.Peer",
+		]
+		.into_iter()
+		.map(|name| {
+			zbus::names::InterfaceName::from_static_str_unchecked(name).into()
+		})
+		.collect()
+	}
+
+	/// Skips `skip` rather than the [`default_skip_list`] while
+	/// deserializing on this thread, until the guard is dropped.
+	///
+	/// Deserialization happens deep inside zbus where no state can be passed
+	/// in, in whichever thread awaits the reply. For
+	/// [`IwdClient::objects`], use [`IwdClient::with_skip_list`] instead.
+	///
+	/// [`default_skip_list`]: All::default_skip_list
+	/// [`IwdClient::objects`]: crate::IwdClient::objects
+	/// [`IwdClient::with_skip_list`]: crate::IwdClient::with_skip_list
+	pub fn skipping(
+		skip: HashSet<zbus::names::OwnedInterfaceName>,
+	) -> SkipListGuard {
+		SkipListGuard {
+			previous: SKIP_LIST.replace(skip),
+			_thread: PhantomData,
+		}
+	}
+
+	/// A copy, failing if one of the properties in [`rest`] is a file
+	/// descriptor that can't be duplicated; `OwnedValue` isn't `Clone` for
+	/// that reason.
+	///
+	/// [`rest`]: All::rest_interfaces
+	pub fn try_clone(&self) -> zvariant::Result<Self> {
+		let rest = self
+			.rest
+			.iter()
+			.map(|(interface, props)| {
+				let props = props
+					.iter()
+					.map(|(k, v)| Ok((k.clone(), v.try_clone()?)))
+					.collect::<zvariant::Result<_>>()?;
+				Ok((interface.clone(), props))
+			})
+			.collect::<zvariant::Result<_>>()?;
+		Ok(self.with_rest(rest))
+	}
+
+	/// A copy that leaves out the properties [`try_clone`] fails on, which
+	/// iwd never sends; what [`Clone`] does.
+	///
+	/// [`try_clone`]: All::try_clone
+	pub fn clone_lossy(&self) -> Self {
+		let rest = self
+			.rest
+			.iter()
+			.map(|(interface, props)| {
+				let props = props
+					.iter()
+					.filter_map(|(k, v)| Some((k.clone(), v.try_clone().ok()?)))
+					.collect();
+				(interface.clone(), props)
+			})
+			.collect();
+		self.with_rest(rest)
+	}
+
+	fn with_rest(&self, rest: Rest) -> Self {
+		All {
+			station: self.station.clone(),
+			device: self.device.clone(),
+			network: self.network.clone(),
+			known_network: self.known_network.clone(),
+			adapter: self.adapter.clone(),
+			basic_service_set: self.basic_service_set.clone(),
+			p2p_peer: self.p2p_peer.clone(),
+			p2p_display: self.p2p_display.clone(),
+			rest,
+		}
+	}
+
+	/// The interfaces without a struct of their own, e.g. ones a newer iwd
+	/// added.
+	pub fn rest_interfaces(
+		&self,
+	) -> impl Iterator<Item = &zbus::names::OwnedInterfaceName> {
+		self.rest.keys()
+	}
+
+	/// Whether the object implements the interface called `name`, known to
+	/// this crate or not; skipped ones don't count.
+	pub fn has_interface(&self, name: &str) -> bool {
+		let known = [
+			(<Station as zbus::Interface>::name(), self.station.is_some()),
+			(<Device as zbus::Interface>::name(), self.device.is_some()),
+			(<Network as zbus::Interface>::name(), self.network.is_some()),
+			(
+				<KnownNetwork as zbus::Interface>::name(),
+				self.known_network.is_some(),
+			),
+			(<Adapter as zbus::Interface>::name(), self.adapter.is_some()),
+			(
+				<BasicServiceSet as zbus::Interface>::name(),
+				self.basic_service_set.is_some(),
+			),
+			(
+				<P2pPeer as zbus::Interface>::name(),
+				self.p2p_peer.is_some(),
+			),
+			(
+				<P2pDisplay as zbus::Interface>::name(),
+				self.p2p_display.is_some(),
+			),
+		];
+		known
+			.iter()
+			.any(|(n, present)| *present && n.as_str() == name)
+			|| self.rest_interfaces().any(|n| n.as_str() == name)
+	}
+
+	/// The most specific of the interfaces present, in this order: station,
+	/// device, network, known network, adapter, BSS, P2P peer, and its
+	/// Wi-Fi Display capabilities.
+	///
+	/// A device in station mode is both of the first two, so it's a station,
+	/// and a peer is one with its display capabilities; iwd puts none of the
+	/// others on the same object.
+	pub fn dominant_role(&self) -> IwdRole {
+		if self.station.is_some() {
+			IwdRole::Station
+		} else if self.device.is_some() {
+			IwdRole::Device
+		} else if self.network.is_some() {
+			IwdRole::Network
+		} else if self.known_network.is_some() {
+			IwdRole::KnownNetwork
+		} else if self.adapter.is_some() {
+			IwdRole::Adapter
+		} else if self.basic_service_set.is_some() {
+			IwdRole::BasicServiceSet
+		} else if self.p2p_peer.is_some() {
+			IwdRole::P2pPeer
+		} else if self.p2p_display.is_some() {
+			IwdRole::P2pDisplay
+		} else {
+			IwdRole::Unknown
+		}
+	}
+
+	/// The interfaces with a struct of their own that are present.
+	pub fn roles(&self) -> Vec<IwdRole> {
+		[
+			(IwdRole::Station, self.station.is_some()),
+			(IwdRole::Device, self.device.is_some()),
+			(IwdRole::Network, self.network.is_some()),
+			(IwdRole::KnownNetwork, self.known_network.is_some()),
+			(IwdRole::Adapter, self.adapter.is_some()),
+			(IwdRole::BasicServiceSet, self.basic_service_set.is_some()),
+			(IwdRole::P2pPeer, self.p2p_peer.is_some()),
+			(IwdRole::P2pDisplay, self.p2p_display.is_some()),
+		]
+		.into_iter()
+		.filter_map(|(role, present)| present.then_some(role))
+		.collect()
+	}
+
+	/// The interface of `T`, if present, e.g. `all.extract::<Station>()`.
+	pub fn extract<T: IwdInterface>(&self) -> Option<&T> {
+		T::extract(self)
+	}
+
+	pub fn extract_mut<T: IwdInterface>(&mut self) -> Option<&mut T> {
+		T::extract_mut(self)
+	}
+
+	/// Whether there are no interfaces at all, e.g. after all of them were
+	/// removed.
+	pub fn is_empty(&self) -> bool {
+		self.roles().is_empty() && self.rest.is_empty()
+	}
+
+	/// Adds the interfaces of `other`, replacing those present in both, the
+	/// way `InterfacesAdded` does.
+	pub(crate) fn merge(&mut self, other: All) {
+		self.station = other.station.or(self.station.take());
+		self.device = other.device.or(self.device.take());
+		self.network = other.network.or(self.network.take());
+		self.known_network = other.known_network.or(self.known_network.take());
+		self.adapter = other.adapter.or(self.adapter.take());
+		self.basic_service_set =
+			other.basic_service_set.or(self.basic_service_set.take());
+		self.p2p_peer = other.p2p_peer.or(self.p2p_peer.take());
+		self.p2p_display = other.p2p_display.or(self.p2p_display.take());
+		self.rest.extend(other.rest);
+	}
+
+	/// Removes the interfaces called `names`, the way `InterfacesRemoved`
+	/// does.
+	pub(crate) fn remove_interfaces(&mut self, names: &[String]) {
+		for name in names {
+			let is = |interface: zbus::names::InterfaceName<'_>| {
+				interface.as_str() == name
+			};
+			if is(<Station as zbus::Interface>::name()) {
+				self.station = None;
+			} else if is(<Device as zbus::Interface>::name()) {
+				self.device = None;
+			} else if is(<Network as zbus::Interface>::name()) {
+				self.network = None;
+			} else if is(<KnownNetwork as zbus::Interface>::name()) {
+				self.known_network = None;
+			} else if is(<Adapter as zbus::Interface>::name()) {
+				self.adapter = None;
+			} else if is(<BasicServiceSet as zbus::Interface>::name()) {
+				self.basic_service_set = None;
+			} else if is(<P2pPeer as zbus::Interface>::name()) {
+				self.p2p_peer = None;
+			} else if is(<P2pDisplay as zbus::Interface>::name()) {
+				self.p2p_display = None;
+			} else {
+				self.rest.retain(|interface, _| interface.as_str() != name);
+			}
+		}
+	}
+
+	/// Whether every interface without a struct is one of the standard
+	/// `org.freedesktop.DBus.*` ones.
+	pub fn is_pure_standard(&self) -> bool {
+		self.rest_interfaces()
+			.all(|name| name.starts_with("org.freedesktop.DBus."))
+	}
+}
+
+impl<'de> serde::Deserialize<'de> for All {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		struct Visit;
+		impl<'de> serde::de::Visitor<'de> for Visit {
+			type Value = All;
+
+			fn expecting(
+				&self,
+				formatter: &mut std::fmt::Formatter,
+			) -> std::fmt::Result {
+				formatter.write_str("a map")
+			}
+
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+			where
+				A: serde::de::MapAccess<'de>,
+			{
+				let mut res = All::default();
+				while let Some(key) = map.next_key()? {
+					if key == <Station as zbus::Interface>::name() {
+						res.station = Some(map.next_value()?);
+					} else if key == <Device as zbus::Interface>::name() {
+						res.device = Some(map.next_value()?);
+					} else if key == <Network as zbus::Interface>::name() {
+						res.network = Some(map.next_value()?);
+					} else if key == <KnownNetwork as zbus::Interface>::name() {
+						res.known_network = Some(map.next_value()?);
+					} else if key == <Adapter as zbus::Interface>::name() {
+						res.adapter = Some(map.next_value()?);
+					} else if key
+						== <BasicServiceSet as zbus::Interface>::name()
+					{
+						res.basic_service_set = Some(map.next_value()?);
+					} else if key == <P2pPeer as zbus::Interface>::name() {
+						res.p2p_peer = Some(map.next_value()?);
+					} else if key == <P2pDisplay as zbus::Interface>::name() {
+						res.p2p_display = Some(map.next_value()?);
+					} else if SKIP_LIST.with_borrow(|skip| skip.contains(&key))
+					{
+						map.next_value::<HashMap<String, zvariant::OwnedValue>>()?;
+					} else {
+						res.rest.insert(key, map.next_value()?);
+					}
+				}
+				Ok(res)
+			}
+		}
+		deserializer.deserialize_map(Visit)
+	}
+}
+
+impl serde::Serialize for All {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		use serde::ser::SerializeStruct;
+
+		// Property maps of unknown interfaces are `OwnedValue`s, which don't
+		// serialize into anything useful outside of D-Bus, so only the
+		// interface names are emitted.
+		let mut rest_interfaces: Vec<_> = self.rest_interfaces().collect();
+		rest_interfaces.sort_unstable();
+
+		let mut s = serializer.serialize_struct("All", 9)?;
+		s.serialize_field("station", &self.station)?;
+		s.serialize_field("device", &self.device)?;
+		s.serialize_field("network", &self.network)?;
+		s.serialize_field("known_network", &self.known_network)?;
+		s.serialize_field("adapter", &self.adapter)?;
+		s.serialize_field("basic_service_set", &self.basic_service_set)?;
+		s.serialize_field("p2p_peer", &self.p2p_peer)?;
+		s.serialize_field("p2p_display", &self.p2p_display)?;
+		s.serialize_field("rest_interfaces", &rest_interfaces)?;
+		s.end()
+	}
+}
+
+/// Every object `GetManagedObjects` returned, by path.
+#[derive(Clone, Default, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct IwdObjectMap(HashMap<OwnedObjectPath, All>);
+
+zvar_type!(HashMap<OwnedObjectPath, All>, [IwdObjectMap]);
+
+/// The structs of the interfaces iwd's objects implement, for code over
+/// any of them, e.g. [`IwdObjectMap::iter_typed`].
+pub trait IwdInterface: Sized {
+	/// What an [`OPath`] to an object with this interface is for.
+	type Proxy: FromObjectPath;
+
+	/// The D-Bus name, e.g. `net.connman.iwd.Station`.
+	fn interface_name() -> &'static str;
+
+	/// This interface of the object of `all`, if it has it.
+	fn extract(all: &All) -> Option<&Self>;
+
+	fn extract_mut(all: &mut All) -> Option<&mut Self>;
+}
+
+macro_rules! iwd_object_map_getters {
+	($(
+		$name:ident, $get:ident:
+			$field:ident => $ty:ident($proxy:ident, $interface:literal)
+	),+ $(,)?) => {
+		$(
+		impl IwdInterface for $ty {
+			type Proxy = $proxy<'static>;
+
+			fn interface_name() -> &'static str {
+				$interface
+			}
+
+			fn extract(all: &All) -> Option<&Self> {
+				all.$field.as_ref()
+			}
+
+			fn extract_mut(all: &mut All) -> Option<&mut Self> {
+				all.$field.as_mut()
+			}
+		}
+		)+
+
+		impl IwdObjectMap {
+			$(
+			pub fn $name(&self) -> impl Iterator<Item = (&OwnedObjectPath, &$ty)> {
+				self.0
+					.iter()
+					.filter_map(|(path, all)| Some((path, all.$field.as_ref()?)))
+			}
+
+			/// `None` if the object is gone, or lacks the interface.
+			pub fn $get(&self, path: &OPath<$proxy<'_>>) -> Option<&$ty> {
+				self.0.get(path.path())?.$field.as_ref()
+			}
+			)+
+		}
+	};
+}
+
+iwd_object_map_getters! {
+	stations, get_station:
+		station => Station(StationProxy, "net.connman.iwd.Station"),
+	devices, get_device:
+		device => Device(DeviceProxy, "net.connman.iwd.Device"),
+	networks, get_network:
+		network => Network(NetworkProxy, "net.connman.iwd.Network"),
+	known_networks, get_known_network:
+		known_network => KnownNetwork(
+			KnownNetworkProxy,
+			"net.connman.iwd.KnownNetwork"
+		),
+	adapters, get_adapter:
+		adapter => Adapter(AdapterProxy, "net.connman.iwd.Adapter"),
+	basic_service_sets, get_basic_service_set:
+		basic_service_set => BasicServiceSet(
+			BasicServiceSetProxy,
+			"net.connman.iwd.BasicServiceSet"
+		),
+	p2p_peers, get_p2p_peer:
+		p2p_peer => P2pPeer(P2pPeerProxy, "net.connman.iwd.p2p.Peer"),
+	p2p_displays, get_p2p_display:
+		p2p_display => P2pDisplay(P2pDisplayProxy, "net.connman.iwd.p2p.Display"),
+}
+
+impl IwdObjectMap {
+	/// Every interface of the object at `path`.
+	pub fn object(&self, path: &OwnedObjectPath) -> Option<&All> {
+		self.0.get(path)
+	}
+
+	/// The paths of the objects with the interface of `T`, e.g.
+	/// `iter_typed::<Station>()` for the stations, to make proxies of.
+	pub fn iter_typed<T: IwdInterface>(
+		&self,
+	) -> impl Iterator<Item = OPath<T::Proxy>> + '_ {
+		self.0
+			.iter()
+			.filter(|(_, all)| all.extract::<T>().is_some())
+			.map(|(path, _)| OPath::from(path.clone()))
+	}
+
+	pub(crate) fn object_mut(&mut self, path: OwnedObjectPath) -> &mut All {
+		self.0.entry(path).or_default()
+	}
+
+	pub(crate) fn remove(&mut self, path: &OwnedObjectPath) -> Option<All> {
+		self.0.remove(path)
+	}
+
+	/// The station of the device at `device_path`, if it is in station mode.
+	///
+	/// iwd adds the `Station` interface to the device's own object rather
+	/// than creating a separate one, so this is a lookup of the same path
+	/// that also makes sure it really is a device.
+	pub fn find_station_for_device<'a>(
+		&'a self,
+		device_path: &OwnedObjectPath,
+	) -> Option<(OwnedObjectPath, &'a Station)> {
+		let all = self.0.get(device_path)?;
+		all.extract::<Device>()?;
+		Some((device_path.clone(), all.extract()?))
+	}
+
+	/// Networks seen by the device at `device_path`.
+	pub fn networks_for_device<'a>(
+		&'a self,
+		device_path: &'a OwnedObjectPath,
+	) -> impl Iterator<Item = (&'a OwnedObjectPath, &'a Network)> {
+		self.networks()
+			.filter(move |(_, network)| network.device() == device_path)
+	}
+
+	/// The access points of the network at `network_path`, which only iwd 2
+	/// and later report.
+	pub fn bss_for_network<'a>(
+		&'a self,
+		network_path: &'a OwnedObjectPath,
+	) -> impl Iterator<Item = (&'a OwnedObjectPath, &'a BasicServiceSet)> {
+		self.basic_service_sets().filter(move |(_, bss)| {
+			bss.network().is_some_and(|path| path == network_path)
+		})
+	}
+
+	/// The objects with the P2P device interface, for Wi-Fi Direct; none if
+	/// no adapter supports it.
+	pub fn p2p_devices(
+		&self,
+	) -> impl Iterator<Item = OPath<P2pDeviceProxy<'static>>> + '_ {
+		self.0
+			.iter()
+			.filter(|(_, all)| all.has_interface(P2P_DEVICE_INTERFACE))
+			.map(|(path, _)| OPath::from(path.clone()))
+	}
+
+	/// The peers the P2P device at `device_path` found.
+	pub fn peers_for_device<'a>(
+		&'a self,
+		device_path: &'a OPath<P2pDeviceProxy<'static>>,
+	) -> impl Iterator<Item = (&'a OwnedObjectPath, &'a P2pPeer)> {
+		self.p2p_peers()
+			.filter(move |(_, peer)| peer.device() == Some(device_path))
+	}
+
+	/// The bands the access points of the network at `network_path` are on,
+	/// lowest first; empty if iwd doesn't say.
+	pub fn network_bands(
+		&self,
+		network_path: &OwnedObjectPath,
+	) -> Vec<WifiBand> {
+		let mut bands: Vec<_> = self
+			.bss_for_network(network_path)
+			.filter_map(|(_, bss)| bss.band())
+			.collect();
+		bands.sort_unstable();
+		bands.dedup();
+		bands
+	}
+}
+
+impl Station {
+	/// The network the station is connected to, looked up in `objects`.
+	pub fn connected_network_info<'a>(
+		&self,
+		objects: &'a IwdObjectMap,
+	) -> Option<(&'a OwnedObjectPath, &'a Network)> {
+		let path = self.connected_network()?.path();
+		let (path, all) = objects.0.get_key_value(path)?;
+		Some((path, all.extract()?))
+	}
+}
+
+/// A device and what it's up to, out of an [`IwdObjectMap`].
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+	pub path: OwnedObjectPath,
+	pub device: Device,
+	/// The adapter the device belongs to.
+	pub adapter: Option<Adapter>,
+	/// `None` unless the device is in station mode.
+	pub station: Option<Station>,
+	/// The network the station is connected to.
+	pub connected_network: Option<Network>,
+}
+
+impl DeviceInfo {
+	/// Every device in `objects`, sorted by name.
+	pub fn collect(objects: &IwdObjectMap) -> Vec<DeviceInfo> {
+		let mut devices: Vec<_> = objects
+			.devices()
+			.map(|(path, device)| {
+				let station = objects
+					.find_station_for_device(path)
+					.map(|(_, station)| station);
+				DeviceInfo {
+					path: path.clone(),
+					device: device.clone(),
+					adapter: objects.get_adapter(device.adapter()).cloned(),
+					station: station.cloned(),
+					connected_network: station
+						.and_then(|s| s.connected_network_info(objects))
+						.map(|(_, network)| network.clone()),
+				}
+			})
+			.collect();
+		devices.sort_by(|a, b| a.device.name().cmp(b.device.name()));
+		devices
+	}
+}
+
+impl IntoIterator for IwdObjectMap {
+	type Item = (OwnedObjectPath, All);
+	type IntoIter = std::collections::hash_map::IntoIter<OwnedObjectPath, All>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a IwdObjectMap {
+	type Item = (&'a OwnedObjectPath, &'a All);
+	type IntoIter = std::collections::hash_map::Iter<'a, OwnedObjectPath, All>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
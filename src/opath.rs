@@ -0,0 +1,305 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use zbus::names::BusName;
+use zbus::zvariant::{self, OwnedObjectPath};
+use zbus::Connection;
+
+use crate::client::IwdClient;
+use crate::error::{is_service_unknown, IwdError};
+use crate::proxy::{
+	AccessPointProxy, AdapterProxy, AgentManagerProxy, BasicServiceSetProxy,
+	DeviceProxy, KnownNetworkProxy, NetworkProxy, ObjectManagerProxy,
+	P2pDeviceProxy, P2pDisplayProxy, P2pPeerProxy, StationDiagnosticProxy,
+	StationProxy,
+};
+use crate::types::{NetworkType, Ssid};
+
+/// Whether `path` is a device, or something below one, the way iwd lays them
+/// out: `/net/connman/iwd/<adapter>/<device>[/...]`.
+pub fn is_iwd_managed_path(path: &zvariant::ObjectPath<'_>) -> bool {
+	assert_iwd_path(path).is_ok()
+}
+
+/// Parses the adapter and device index out of a path accepted by
+/// [`is_iwd_managed_path`].
+pub fn assert_iwd_path(
+	path: &zvariant::ObjectPath<'_>,
+) -> Result<(u32, u32), IwdError> {
+	fn index(s: Option<&str>) -> Option<u32> {
+		let s = s.filter(|s| s.bytes().all(|b| b.is_ascii_digit()))?;
+		s.parse().ok()
+	}
+
+	let mut parts = path
+		.as_str()
+		.strip_prefix("/net/connman/iwd/")
+		.unwrap_or_default()
+		.split('/');
+	match (index(parts.next()), index(parts.next())) {
+		(Some(adapter), Some(device)) => Ok((adapter, device)),
+		_ => Err(IwdError::InvalidPath(path.to_owned().into())),
+	}
+}
+
+/// Where iwd puts the known network of `ssid` and `type_`:
+/// `/net/connman/iwd/<SSID in hex>_<type>`.
+pub fn known_network_path(ssid: &Ssid, type_: NetworkType) -> OwnedObjectPath {
+	let path = format!("/net/connman/iwd/{}_{type_}", ssid.to_hex());
+	zvariant::ObjectPath::try_from(path)
+		.expect("hex digits and a type name make a valid path")
+		.into()
+}
+
+pub trait FromObjectPath: Sized + Send {
+	/// The proxy of the object at `path` of `service`, the name iwd is
+	/// under; see [`IwdClient::service`]. Failures are
+	/// [`IwdError::Proxy`], naming the proxy and the path.
+	fn new(
+		conn: &Connection,
+		service: &BusName<'_>,
+		path: OwnedObjectPath,
+	) -> impl Future<Output = Result<Self, IwdError>> + Send;
+
+	/// Like [`new`](FromObjectPath::new), but `None` when nobody provides
+	/// the service.
+	fn try_new(
+		conn: &Connection,
+		service: &BusName<'_>,
+		path: OwnedObjectPath,
+	) -> impl Future<Output = Result<Option<Self>, IwdError>> + Send {
+		let service = service.to_owned();
+		async move {
+			match Self::new(conn, &service, path).await {
+				Ok(proxy) => Ok(Some(proxy)),
+				Err(IwdError::Proxy { source, .. })
+					if is_service_unknown(&source) =>
+				{
+					Ok(None)
+				}
+				Err(err) => Err(err),
+			}
+		}
+	}
+}
+
+macro_rules! from_object_path {
+	($($proxy:ident),+ $(,)?) => {
+		$(
+		impl<'a> FromObjectPath for $proxy<'a> {
+			fn new(
+				conn: &Connection,
+				service: &BusName<'_>,
+				path: OwnedObjectPath,
+			) -> impl Future<Output = Result<Self, IwdError>> + Send {
+				let builder = zbus::proxy::Builder::<Self>::new(conn);
+				let service = service.to_owned();
+				async move {
+					let builder = builder
+						.destination(service)
+						.and_then(|builder| builder.path(path.clone()));
+					let proxy = match builder {
+						Ok(builder) => builder.build().await,
+						Err(err) => Err(err),
+					};
+					proxy.map_err(|source| IwdError::Proxy {
+						proxy: stringify!($proxy),
+						path,
+						source,
+					})
+				}
+			}
+		}
+		)+
+	};
+}
+
+from_object_path!(
+	AccessPointProxy,
+	AdapterProxy,
+	AgentManagerProxy,
+	BasicServiceSetProxy,
+	DeviceProxy,
+	KnownNetworkProxy,
+	NetworkProxy,
+	ObjectManagerProxy,
+	P2pDeviceProxy,
+	P2pDisplayProxy,
+	P2pPeerProxy,
+	StationDiagnosticProxy,
+	StationProxy,
+);
+
+// every proxy can be reached through an `OPath`
+const _: () = {
+	let _ = OPath::<AccessPointProxy>::proxy;
+	let _ = OPath::<AdapterProxy>::proxy;
+	let _ = OPath::<AgentManagerProxy>::proxy;
+	let _ = OPath::<BasicServiceSetProxy>::proxy;
+	let _ = OPath::<DeviceProxy>::proxy;
+	let _ = OPath::<KnownNetworkProxy>::proxy;
+	let _ = OPath::<NetworkProxy>::proxy;
+	let _ = OPath::<ObjectManagerProxy>::proxy;
+	let _ = OPath::<P2pDeviceProxy>::proxy;
+	let _ = OPath::<P2pDisplayProxy>::proxy;
+	let _ = OPath::<P2pPeerProxy>::proxy;
+	let _ = OPath::<StationDiagnosticProxy>::proxy;
+	let _ = OPath::<StationProxy>::proxy;
+};
+
+#[repr(transparent)]
+pub struct OPath<T> {
+	path: OwnedObjectPath,
+	_ty: PhantomData<T>,
+}
+
+// Manual impls, since the derives would needlessly require the same of `T`.
+impl<T> Clone for OPath<T> {
+	fn clone(&self) -> Self {
+		OPath {
+			path: self.path.clone(),
+			_ty: PhantomData,
+		}
+	}
+}
+
+impl<T> PartialEq for OPath<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.path == other.path
+	}
+}
+
+impl<T> Eq for OPath<T> {}
+
+impl<T> Hash for OPath<T> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.path.hash(state)
+	}
+}
+
+impl<T> PartialOrd for OPath<T> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T> Ord for OPath<T> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.path.cmp(&other.path)
+	}
+}
+
+// `Hash`, `Eq` and `Ord` agree with those of the path, so maps keyed by
+// `OPath` can be looked up with a plain path too.
+impl<T> Borrow<OwnedObjectPath> for OPath<T> {
+	fn borrow(&self) -> &OwnedObjectPath {
+		&self.path
+	}
+}
+
+impl<T> PartialEq<OwnedObjectPath> for OPath<T> {
+	fn eq(&self, other: &OwnedObjectPath) -> bool {
+		self.path == *other
+	}
+}
+
+impl<T> PartialEq<OPath<T>> for OwnedObjectPath {
+	fn eq(&self, other: &OPath<T>) -> bool {
+		*self == other.path
+	}
+}
+
+// Like `cast`, any object path is taken at its word.
+impl<'de, T> serde::Deserialize<'de> for OPath<T> {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		Ok(OPath {
+			path: OwnedObjectPath::deserialize(deserializer)?,
+			_ty: PhantomData,
+		})
+	}
+}
+
+impl<T> serde::Serialize for OPath<T> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		self.path.serialize(serializer)
+	}
+}
+
+impl<T> From<OPath<T>> for OwnedObjectPath {
+	fn from(value: OPath<T>) -> Self {
+		value.path
+	}
+}
+
+impl<T: FromObjectPath> From<OwnedObjectPath> for OPath<T> {
+	fn from(path: OwnedObjectPath) -> Self {
+		OPath {
+			path,
+			_ty: PhantomData,
+		}
+	}
+}
+
+impl<T: FromObjectPath> std::str::FromStr for OPath<T> {
+	type Err = zvariant::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let path = zvariant::ObjectPath::try_from(s)?;
+		Ok(OwnedObjectPath::from(path).into())
+	}
+}
+
+impl<'a, T> From<&'a OPath<T>> for zvariant::ObjectPath<'a> {
+	fn from(value: &'a OPath<T>) -> Self {
+		value.path.as_ref()
+	}
+}
+
+impl<T> AsRef<str> for OPath<T> {
+	fn as_ref(&self) -> &str {
+		self.path.as_str()
+	}
+}
+
+impl<T> fmt::Debug for OPath<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.path.as_ref().fmt(f)
+	}
+}
+
+zvar_type!(OwnedObjectPath, impl<T> OPath<T>);
+
+impl<T> OPath<T> {
+	pub fn path(&self) -> &OwnedObjectPath {
+		&self.path
+	}
+
+	/// Reuses the path for another interface, e.g. a station's path as its
+	/// device's.
+	///
+	/// This is safe in the Rust sense, but nothing checks that the object
+	/// actually implements the interface `U` stands for; calls through a
+	/// mistaken cast fail at the D-Bus level with `UnknownInterface`.
+	pub fn cast<U>(self) -> OPath<U> {
+		OPath {
+			path: self.path,
+			_ty: PhantomData,
+		}
+	}
+}
+
+impl<T: FromObjectPath> OPath<T> {
+	pub async fn proxy(self, client: &IwdClient) -> Result<T, IwdError> {
+		T::new(client.connection(), client.service(), self.path).await
+	}
+}
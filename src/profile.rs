@@ -0,0 +1,203 @@
+//! iwd's network profiles, the files in `/var/lib/iwd` that
+//! [`KnownNetwork::profile_path`](crate::types::KnownNetwork::profile_path)
+//! points at, for editing them while iwd isn't looking.
+
+use std::fmt;
+use std::fs::{OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+/// The `[Security]` keys this crate knows about.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Security {
+	/// 64 hex digits, derived from the passphrase by iwd.
+	pub pre_shared_key: Option<String>,
+	pub passphrase: Option<String>,
+	pub eap_method: Option<String>,
+}
+
+/// The `[Settings]` keys this crate knows about.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Settings {
+	pub auto_connect: Option<bool>,
+	pub hidden: Option<bool>,
+}
+
+/// One profile, in iwd's INI-like format.
+///
+/// Comments are dropped, everything else survives a [`parse`] and
+/// [`Display`](fmt::Display) round trip.
+///
+/// [`parse`]: ProfileConfig::parse
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ProfileConfig {
+	pub security: Security,
+	pub settings: Settings,
+	/// Every other key, by section, in file order.
+	pub other: Vec<(String, Vec<(String, String)>)>,
+}
+
+fn invalid(line: usize, msg: impl fmt::Display) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"))
+}
+
+fn parse_bool(line: usize, value: &str) -> io::Result<bool> {
+	match value {
+		"true" => Ok(true),
+		"false" => Ok(false),
+		_ => Err(invalid(line, format_args!("{value:?} is not a boolean"))),
+	}
+}
+
+impl ProfileConfig {
+	pub fn parse(s: &str) -> io::Result<Self> {
+		let mut res = ProfileConfig::default();
+		let mut section = None;
+
+		for (i, line) in s.lines().enumerate() {
+			let line_no = i + 1;
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			if let Some(name) =
+				line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+			{
+				section = Some(name.trim().to_owned());
+				continue;
+			}
+
+			let Some((key, value)) = line.split_once('=') else {
+				return Err(invalid(line_no, "expected key=value"));
+			};
+			let (key, value) = (key.trim(), value.trim());
+			let Some(section) = section.as_deref() else {
+				return Err(invalid(line_no, "key outside of a section"));
+			};
+
+			match (section, key) {
+				("Security", "PreSharedKey") => {
+					res.security.pre_shared_key = Some(value.to_owned())
+				}
+				("Security", "Passphrase") => {
+					res.security.passphrase = Some(value.to_owned())
+				}
+				("Security", "EAP-Method") => {
+					res.security.eap_method = Some(value.to_owned())
+				}
+				("Settings", "AutoConnect") => {
+					res.settings.auto_connect =
+						Some(parse_bool(line_no, value)?)
+				}
+				("Settings", "Hidden") => {
+					res.settings.hidden = Some(parse_bool(line_no, value)?)
+				}
+				_ => res
+					.other_section(section)
+					.push((key.to_owned(), value.to_owned())),
+			}
+		}
+
+		Ok(res)
+	}
+
+	fn other_section(&mut self, name: &str) -> &mut Vec<(String, String)> {
+		let i = match self.other.iter().position(|(n, _)| n == name) {
+			Some(i) => i,
+			None => {
+				self.other.push((name.to_owned(), Vec::new()));
+				self.other.len() - 1
+			}
+		};
+		&mut self.other[i].1
+	}
+
+	pub fn load(path: &Path) -> io::Result<Self> {
+		Self::parse(&std::fs::read_to_string(path)?)
+	}
+
+	/// Writes the profile readable by its owner only, like iwd does, since
+	/// it may hold secrets; that goes for a file already at `path` too.
+	///
+	/// A running iwd only catches up once it notices the change; for
+	/// `AutoConnect`, prefer
+	/// [`KnownNetworkProxy::set_auto_connect`].
+	///
+	/// [`KnownNetworkProxy::set_auto_connect`]:
+	/// crate::proxy::KnownNetworkProxy::set_auto_connect
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let mut file = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.mode(0o600)
+			.open(path)?;
+		file.set_permissions(Permissions::from_mode(0o600))?;
+		file.write_all(self.to_string().as_bytes())
+	}
+}
+
+impl fmt::Display for ProfileConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn section(
+			f: &mut fmt::Formatter<'_>,
+			name: &str,
+			known: &[(&str, Option<String>)],
+			other: &[(String, String)],
+			first: &mut bool,
+		) -> fmt::Result {
+			let mut entries = known
+				.iter()
+				.filter_map(|(k, v)| Some((*k, v.as_deref()?)))
+				.chain(other.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+				.peekable();
+			if entries.peek().is_none() {
+				return Ok(());
+			}
+			if !std::mem::take(first) {
+				writeln!(f)?;
+			}
+			writeln!(f, "[{name}]")?;
+			for (key, value) in entries {
+				writeln!(f, "{key}={value}")?;
+			}
+			Ok(())
+		}
+
+		let Security {
+			pre_shared_key,
+			passphrase,
+			eap_method,
+		} = &self.security;
+		let Settings {
+			auto_connect,
+			hidden,
+		} = &self.settings;
+		let known = |name: &str| match name {
+			"Security" => vec![
+				("PreSharedKey", pre_shared_key.clone()),
+				("Passphrase", passphrase.clone()),
+				("EAP-Method", eap_method.clone()),
+			],
+			"Settings" => vec![
+				("AutoConnect", auto_connect.map(|b| b.to_string())),
+				("Hidden", hidden.map(|b| b.to_string())),
+			],
+			_ => Vec::new(),
+		};
+
+		// the known sections go first, unless they also have other keys and
+		// thus a place in `other`
+		let mut first = true;
+		for name in ["Security", "Settings"] {
+			if !self.other.iter().any(|(n, _)| n == name) {
+				section(f, name, &known(name), &[], &mut first)?;
+			}
+		}
+		for (name, entries) in &self.other {
+			section(f, name, &known(name), entries, &mut first)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use zbus::zvariant::{self, OwnedObjectPath};
+
+use crate::error::IwdError;
+use crate::objects::{All, IwdObjectMap};
+use crate::opath::OPath;
+use crate::types::{HiddenAccessPoint, SignalStrength, StationState};
+
+/// What `GetOrderedNetworks` returns, best first.
+pub type OrderedNetworks =
+	Box<[(OPath<NetworkProxy<'static>>, SignalStrength)]>;
+
+// Each proxy attribute comes twice, so that the blocking variants are only
+// generated with the `blocking` feature. Wrapping them up in `macro_rules!`
+// breaks the hygiene of `self` in the generated code.
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "org.freedesktop.DBus.ObjectManager",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(interface = "org.freedesktop.DBus.ObjectManager")
+)]
+pub trait ObjectManager {
+	fn get_managed_objects(&self) -> zbus::Result<IwdObjectMap>;
+
+	#[zbus(signal)]
+	fn interfaces_added(
+		&self,
+		object_path: OwnedObjectPath,
+		interfaces_and_properties: All,
+	) -> zbus::Result<()>;
+
+	#[zbus(signal)]
+	fn interfaces_removed(
+		&self,
+		object_path: OwnedObjectPath,
+		interfaces: Vec<String>,
+	) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.Station",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.Station",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait Station {
+	fn scan(&self) -> zbus::Result<()>;
+
+	fn disconnect(&self) -> zbus::Result<()>;
+
+	fn get_ordered_networks(&self) -> zbus::Result<OrderedNetworks>;
+
+	/// Not in every iwd build, see [`hidden_access_points`]. Returns the
+	/// access points themselves, not object paths; iwd makes none for them.
+	///
+	/// [`hidden_access_points`]: crate::client::hidden_access_points
+	fn get_hidden_access_points(
+		&self,
+	) -> zbus::Result<Box<[HiddenAccessPoint]>>;
+
+	#[zbus(property)]
+	fn state(&self) -> zbus::Result<StationState>;
+
+	#[zbus(property)]
+	fn scanning(&self) -> zbus::Result<bool>;
+
+	/// Only while disconnected, and only from iwd 2 on.
+	#[zbus(property)]
+	fn disconnected_reason(&self) -> zbus::Result<String>;
+
+	/// When the last scan finished, in seconds since the Unix epoch.
+	#[zbus(property)]
+	fn last_scan(&self) -> zbus::Result<u64>;
+
+	/// `levels` are RSSI thresholds in dBm, in descending order.
+	fn register_signal_level_agent(
+		&self,
+		path: &zvariant::ObjectPath<'_>,
+		levels: &[i16],
+	) -> zbus::Result<()>;
+
+	fn unregister_signal_level_agent(
+		&self,
+		path: &zvariant::ObjectPath<'_>,
+	) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.Device",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.Device",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait Device {
+	#[zbus(property)]
+	fn name(&self) -> zbus::Result<String>;
+
+	#[zbus(property)]
+	fn powered(&self) -> zbus::Result<bool>;
+
+	#[zbus(property)]
+	fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.Adapter",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.Adapter",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait Adapter {
+	#[zbus(property)]
+	fn name(&self) -> zbus::Result<String>;
+
+	#[zbus(property)]
+	fn powered(&self) -> zbus::Result<bool>;
+
+	#[zbus(property)]
+	fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.Network",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.Network",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait Network {
+	fn connect(&self) -> zbus::Result<()>;
+
+	#[zbus(property)]
+	fn name(&self) -> zbus::Result<String>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.BasicServiceSet",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.BasicServiceSet",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait BasicServiceSet {
+	#[zbus(property)]
+	fn address(&self) -> zbus::Result<String>;
+
+	#[zbus(property)]
+	fn frequency(&self) -> zbus::Result<u32>;
+
+	#[zbus(property, name = "RSSI")]
+	fn rssi(&self) -> zbus::Result<i16>;
+
+	#[zbus(property)]
+	fn network(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Device",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Device",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait P2pDevice {
+	/// The peers found so far, with their signal strength.
+	fn get_peers(&self)
+		-> zbus::Result<Vec<(OwnedObjectPath, SignalStrength)>>;
+
+	/// Keeps looking for peers until released, or until this connection
+	/// goes away.
+	fn request_discovery(&self) -> zbus::Result<()>;
+
+	fn release_discovery(&self) -> zbus::Result<()>;
+
+	#[zbus(property)]
+	fn name(&self) -> zbus::Result<String>;
+
+	#[zbus(property)]
+	fn enabled(&self) -> zbus::Result<bool>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Peer",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Peer",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait P2pPeer {
+	/// Provisions with `method`, `pushbutton` or `pin` with `pin`, and
+	/// returns once the peer accepted.
+	fn connect(&self, method: &str, pin: &str) -> zbus::Result<()>;
+
+	fn disconnect(&self) -> zbus::Result<()>;
+
+	#[zbus(property)]
+	fn name(&self) -> zbus::Result<String>;
+
+	#[zbus(property)]
+	fn connected(&self) -> zbus::Result<bool>;
+
+	#[zbus(property)]
+	fn connected_interface(&self) -> zbus::Result<String>;
+
+	#[zbus(property, name = "ConnectedIP")]
+	fn connected_ip(&self) -> zbus::Result<String>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Display",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.p2p.Display",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait P2pDisplay {
+	#[zbus(property)]
+	fn source(&self) -> zbus::Result<bool>;
+
+	#[zbus(property)]
+	fn sink(&self) -> zbus::Result<bool>;
+
+	#[zbus(property)]
+	fn port(&self) -> zbus::Result<u16>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.StationDiagnostic",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.StationDiagnostic",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait StationDiagnostic {
+	fn get_diagnostics(
+		&self,
+	) -> zbus::Result<HashMap<String, zvariant::OwnedValue>>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.KnownNetwork",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.KnownNetwork",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait KnownNetwork {
+	fn forget(&self) -> zbus::Result<()>;
+
+	#[zbus(property)]
+	fn auto_connect(&self) -> zbus::Result<bool>;
+
+	/// Takes effect right away and is persisted by iwd, unlike writing the
+	/// profile with [`ProfileConfig`], which waits for iwd to notice the
+	/// file changed.
+	///
+	/// [`ProfileConfig`]: crate::profile::ProfileConfig
+	#[zbus(property)]
+	fn set_auto_connect(&self, auto_connect: bool) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.AgentManager",
+		default_service = "net.connman.iwd",
+		default_path = "/net/connman/iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.AgentManager",
+		default_service = "net.connman.iwd",
+		default_path = "/net/connman/iwd"
+	)
+)]
+pub trait AgentManager {
+	fn register_agent(
+		&self,
+		path: &zvariant::ObjectPath<'_>,
+	) -> zbus::Result<()>;
+
+	fn unregister_agent(
+		&self,
+		path: &zvariant::ObjectPath<'_>,
+	) -> zbus::Result<()>;
+}
+
+#[cfg_attr(
+	not(feature = "blocking"),
+	zbus::proxy(
+		interface = "net.connman.iwd.AccessPoint",
+		default_service = "net.connman.iwd",
+		gen_blocking = false
+	)
+)]
+#[cfg_attr(
+	feature = "blocking",
+	zbus::proxy(
+		interface = "net.connman.iwd.AccessPoint",
+		default_service = "net.connman.iwd"
+	)
+)]
+pub trait AccessPoint {
+	/// Starts a WPA2 protected access point.
+	fn start(&self, ssid: &str, psk: &str) -> zbus::Result<()>;
+
+	/// Starts an access point without encryption.
+	fn start_open(&self, ssid: &str) -> zbus::Result<()>;
+
+	fn stop(&self) -> zbus::Result<()>;
+
+	#[zbus(property)]
+	fn started(&self) -> zbus::Result<bool>;
+}
+
+/// What [`AccessPointProxy::start_with_config`] starts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AccessPointConfig {
+	Protected { ssid: String, psk: String },
+	Open { ssid: String },
+}
+
+impl AccessPointConfig {
+	/// Checks the passphrase the way WPA2 restricts it: 8 to 63 printable
+	/// ASCII characters.
+	pub fn validate(&self) -> Result<(), IwdError> {
+		let AccessPointConfig::Protected { psk, .. } = self else {
+			return Ok(());
+		};
+		if !(8..=63).contains(&psk.len()) {
+			return Err(IwdError::InvalidArguments(format!(
+				"passphrase must be 8 to 63 characters, not {}",
+				psk.len()
+			)));
+		}
+		if !psk.bytes().all(|b| matches!(b, b' '..=b'~')) {
+			return Err(IwdError::InvalidArguments(
+				"passphrase must be printable ASCII".to_owned(),
+			));
+		}
+		Ok(())
+	}
+}
+
+impl AccessPointProxy<'_> {
+	/// `Start` or `StartOpen`, depending on `config`, which is
+	/// [validated](AccessPointConfig::validate) first.
+	pub async fn start_with_config(
+		&self,
+		config: AccessPointConfig,
+	) -> Result<(), IwdError> {
+		config.validate()?;
+		match &config {
+			AccessPointConfig::Protected { ssid, psk } => {
+				self.start(ssid, psk).await?
+			}
+			AccessPointConfig::Open { ssid } => self.start_open(ssid).await?,
+		}
+		Ok(())
+	}
+}
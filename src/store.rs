@@ -0,0 +1,293 @@
+//! A cache of the objects iwd manages, for callers that read them often and
+//! can live with them being a little out of date.
+
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_lock::RwLock;
+use futures_lite::{Stream, StreamExt};
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::client::{fetch_properties, IwdClient};
+use crate::error::IwdError;
+use crate::objects::{All, IwdObjectMap, IwdRole};
+use crate::proxy::ObjectManagerProxy;
+
+const ROLES: [IwdRole; 8] = [
+	IwdRole::Station,
+	IwdRole::Device,
+	IwdRole::Network,
+	IwdRole::KnownNetwork,
+	IwdRole::Adapter,
+	IwdRole::BasicServiceSet,
+	IwdRole::P2pPeer,
+	IwdRole::P2pDisplay,
+];
+
+#[derive(Default, Debug)]
+struct Cache {
+	objects: Arc<IwdObjectMap>,
+	fetched_at: Option<Instant>,
+	/// Objects whose properties changed or were invalidated since, fetched
+	/// again on the next read.
+	dirty: HashSet<OwnedObjectPath>,
+}
+
+/// [`IwdClient::objects`], kept in memory: fetched again in full once older
+/// than its TTL or on [`refresh`](ObjectStore::refresh), and object by
+/// object once [invalidated](ObjectStore::invalidate).
+///
+/// While its [`Updates`] run, the store follows iwd's signals instead of
+/// expiring. Clones share the cache.
+#[derive(Clone, Debug)]
+pub struct ObjectStore {
+	client: IwdClient,
+	ttl: Duration,
+	cache: Arc<RwLock<Cache>>,
+	/// How many [`Updates::run`] are running.
+	following: Arc<AtomicUsize>,
+}
+
+impl ObjectStore {
+	/// An empty store, filled on the first read.
+	pub fn new(client: IwdClient, ttl: Duration) -> Self {
+		ObjectStore {
+			client,
+			ttl,
+			cache: Arc::default(),
+			following: Arc::default(),
+		}
+	}
+
+	pub fn client(&self) -> &IwdClient {
+		&self.client
+	}
+
+	/// The objects, from memory unless stale; what was read stays as it is
+	/// when the store changes.
+	pub async fn objects(&self) -> Result<Arc<IwdObjectMap>, IwdError> {
+		{
+			let cache = self.cache.read().await;
+			if !self.is_stale(&cache) && cache.dirty.is_empty() {
+				return Ok(cache.objects.clone());
+			}
+		}
+
+		// checked again, as another reader may have fetched them meanwhile
+		let mut cache = self.cache.write().await;
+		if self.is_stale(&cache) {
+			self.fetch(&mut cache).await?;
+		} else {
+			self.fetch_dirty(&mut cache).await?;
+		}
+		Ok(cache.objects.clone())
+	}
+
+	/// Fetches every object again, whether stale or not.
+	pub async fn refresh(&self) -> Result<Arc<IwdObjectMap>, IwdError> {
+		let mut cache = self.cache.write().await;
+		self.fetch(&mut cache).await?;
+		Ok(cache.objects.clone())
+	}
+
+	/// Has the object at `path` fetched again on the next read, alone; gone
+	/// if iwd doesn't have it any more.
+	pub async fn invalidate(&self, path: &OwnedObjectPath) {
+		self.cache.write().await.dirty.insert(path.clone());
+	}
+
+	/// When the objects were last fetched in full, if ever.
+	pub async fn fetched_at(&self) -> Option<Instant> {
+		self.cache.read().await.fetched_at
+	}
+
+	/// How long ago the objects were last fetched in full, if ever.
+	pub async fn age(&self) -> Option<Duration> {
+		Some(self.fetched_at().await?.elapsed())
+	}
+
+	/// Follows iwd's signals from now on, once what they're applied to is
+	/// fetched; they're applied as long as the [`Updates`] run.
+	pub async fn subscribe(&self) -> Result<Updates, IwdError> {
+		let (conn, service) = (self.client.connection(), self.client.service());
+		let manager = ObjectManagerProxy::new(conn, service, "/").await?;
+		let added = manager.receive_interfaces_added().await?.map(|signal| {
+			let args = signal.args()?;
+			let (path, all) =
+				(args.object_path, args.interfaces_and_properties);
+			Ok(Update::Added(path, Box::new(all)))
+		});
+		let removed =
+			manager.receive_interfaces_removed().await?.map(|signal| {
+				let args = signal.args()?;
+				Ok(Update::Removed(args.object_path, args.interfaces))
+			});
+		let rule = zbus::MatchRule::builder()
+			.msg_type(zbus::message::Type::Signal)
+			.sender(service)?
+			.interface("org.freedesktop.DBus.Properties")?
+			.member("PropertiesChanged")?
+			.path_namespace("/net/connman/iwd")?
+			.build();
+		let changed = zbus::MessageStream::for_match_rule(rule, conn, None)
+			.await?
+			.filter_map(|msg| {
+				let path = match msg {
+					Ok(msg) => msg.header().path()?.to_owned(),
+					Err(err) => return Some(Err(err.into())),
+				};
+				Some(Ok(Update::Changed(path.into())))
+			});
+
+		self.refresh().await?;
+		Ok(Updates {
+			stream: added.or(removed).or(changed).boxed(),
+			cache: self.cache.clone(),
+			following: self.following.clone(),
+		})
+	}
+
+	fn is_stale(&self, cache: &Cache) -> bool {
+		let Some(at) = cache.fetched_at else {
+			return true;
+		};
+		self.following.load(Ordering::Relaxed) == 0 && at.elapsed() >= self.ttl
+	}
+
+	async fn fetch(&self, cache: &mut Cache) -> Result<(), IwdError> {
+		cache.objects = Arc::new(self.client.objects().await?);
+		cache.fetched_at = Some(Instant::now());
+		cache.dirty.clear();
+		Ok(())
+	}
+
+	async fn fetch_dirty(&self, cache: &mut Cache) -> Result<(), IwdError> {
+		while let Some(path) = cache.dirty.iter().next().cloned() {
+			let mut all = cache.objects.object(&path).cloned();
+			let roles = all.as_ref().map_or(ROLES.to_vec(), All::roles);
+			let all = all.get_or_insert_with(All::default);
+			for role in roles {
+				fetch_role(&self.client, &path, role, all).await?;
+			}
+
+			let objects = Arc::make_mut(&mut cache.objects);
+			if all.is_empty() {
+				objects.remove(&path);
+			} else {
+				*objects.object_mut(path.clone()) = all.clone();
+			}
+			cache.dirty.remove(&path);
+		}
+		Ok(())
+	}
+}
+
+/// Fetches the interface of `role` at `path` into `all`, or takes it out if
+/// the object doesn't have it any more.
+async fn fetch_role(
+	client: &IwdClient,
+	path: &OwnedObjectPath,
+	role: IwdRole,
+	all: &mut All,
+) -> Result<(), IwdError> {
+	async fn present<T>(
+		client: &IwdClient,
+		path: &OwnedObjectPath,
+	) -> Result<Option<T>, IwdError>
+	where
+		T: zbus::object_server::Interface,
+		T: serde::de::DeserializeOwned + zbus::zvariant::Type,
+	{
+		match fetch_properties(client, path).await {
+			Ok(properties) => Ok(Some(properties)),
+			Err(err) if err.is_unknown_object() => Ok(None),
+			Err(err) => Err(err),
+		}
+	}
+
+	match role {
+		IwdRole::Station => all.station = present(client, path).await?,
+		IwdRole::Device => all.device = present(client, path).await?,
+		IwdRole::Network => all.network = present(client, path).await?,
+		IwdRole::KnownNetwork => {
+			all.known_network = present(client, path).await?;
+		}
+		IwdRole::Adapter => all.adapter = present(client, path).await?,
+		IwdRole::BasicServiceSet => {
+			all.basic_service_set = present(client, path).await?;
+		}
+		IwdRole::P2pPeer => all.p2p_peer = present(client, path).await?,
+		IwdRole::P2pDisplay => {
+			all.p2p_display = present(client, path).await?;
+		}
+		IwdRole::Unknown => {}
+	}
+	Ok(())
+}
+
+enum Update {
+	Added(OwnedObjectPath, Box<All>),
+	Removed(OwnedObjectPath, Vec<String>),
+	Changed(OwnedObjectPath),
+}
+
+/// iwd's signals, applied to an [`ObjectStore`] by [`run`](Updates::run);
+/// unless one of its `Updates` runs, the store expires with its TTL.
+pub struct Updates {
+	stream: Pin<Box<dyn Stream<Item = Result<Update, IwdError>> + Send>>,
+	cache: Arc<RwLock<Cache>>,
+	following: Arc<AtomicUsize>,
+}
+
+/// Counts one running [`Updates::run`] for as long as it lives, which is
+/// also until the future is dropped.
+struct Following(Arc<AtomicUsize>);
+
+impl Following {
+	fn new(count: &Arc<AtomicUsize>) -> Self {
+		count.fetch_add(1, Ordering::Relaxed);
+		Following(count.clone())
+	}
+}
+
+impl Drop for Following {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+impl Updates {
+	/// Applies the signals as they come, until the connection is closed:
+	/// objects and interfaces added or removed at once, and properties
+	/// changed by fetching their object again on the next read.
+	pub async fn run(&mut self) -> Result<(), IwdError> {
+		let _following = Following::new(&self.following);
+		while let Some(update) = self.stream.next().await {
+			let mut cache = self.cache.write().await;
+			match update? {
+				Update::Added(path, all) => {
+					let objects = Arc::make_mut(&mut cache.objects);
+					objects.object_mut(path).merge(*all);
+				}
+				Update::Removed(path, interfaces) => {
+					let objects = Arc::make_mut(&mut cache.objects);
+					let all = objects.object_mut(path.clone());
+					all.remove_interfaces(&interfaces);
+					if all.is_empty() {
+						objects.remove(&path);
+						cache.dirty.remove(&path);
+					}
+				}
+				Update::Changed(path) => {
+					if cache.objects.object(&path).is_some() {
+						cache.dirty.insert(path);
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+}
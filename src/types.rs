@@ -0,0 +1,1062 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use zbus::zvariant;
+
+use crate::opath::OPath;
+use crate::proxy::{
+	AdapterProxy, DeviceProxy, KnownNetworkProxy, NetworkProxy, P2pDeviceProxy,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StationState {
+	Connected,
+	Disconnected,
+	Connecting,
+	Disconnecting,
+	Roaming,
+}
+
+impl StationState {
+	/// The name iwd uses for this state on the wire.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			StationState::Connected => "connected",
+			StationState::Disconnected => "disconnected",
+			StationState::Connecting => "connecting",
+			StationState::Disconnecting => "disconnecting",
+			StationState::Roaming => "roaming",
+		}
+	}
+}
+
+// for `StationProxy::state`
+impl TryFrom<zvariant::OwnedValue> for StationState {
+	type Error = zvariant::Error;
+
+	fn try_from(value: zvariant::OwnedValue) -> Result<Self, Self::Error> {
+		use serde::de::IntoDeserializer;
+
+		let state = String::try_from(value)?;
+		let de: serde::de::value::StrDeserializer<serde::de::value::Error> =
+			state.as_str().into_deserializer();
+		StationState::deserialize(de)
+			.map_err(|err| zvariant::Error::Message(err.to_string()))
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceMode {
+	AdHoc,
+	Station,
+	Ap,
+}
+
+impl DeviceMode {
+	/// The name iwd uses for this mode on the wire.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			DeviceMode::AdHoc => "ad-hoc",
+			DeviceMode::Station => "station",
+			DeviceMode::Ap => "ap",
+		}
+	}
+}
+
+#[derive(
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Deserialize,
+	Serialize,
+	Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkType {
+	Open,
+	Wep,
+	Psk,
+	#[serde(rename = "8021x")]
+	Eap,
+	Hotspot,
+}
+
+impl NetworkType {
+	/// The name iwd uses for this type on the wire.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			NetworkType::Open => "open",
+			NetworkType::Wep => "wep",
+			NetworkType::Psk => "psk",
+			NetworkType::Eap => "8021x",
+			NetworkType::Hotspot => "hotspot",
+		}
+	}
+
+	/// Human-readable name of the security, e.g. `WPA2-Enterprise`.
+	pub fn security_label(&self) -> &'static str {
+		match self {
+			NetworkType::Open => "Open",
+			NetworkType::Wep => "WEP",
+			NetworkType::Psk => "WPA2",
+			NetworkType::Eap => "WPA2-Enterprise",
+			NetworkType::Hotspot => "Hotspot",
+		}
+	}
+
+	/// Whether the network is protected by something stronger than WEP.
+	pub fn is_secure(&self) -> bool {
+		match self {
+			NetworkType::Psk | NetworkType::Eap => true,
+			NetworkType::Open | NetworkType::Wep | NetworkType::Hotspot => {
+				false
+			}
+		}
+	}
+}
+
+zvar_type_str!(StationState, DeviceMode, NetworkType);
+
+/// `Display` as the name iwd uses on the wire, and `FromStr` parsing it in
+/// any case, by the serde renames; the error lists the accepted names.
+macro_rules! wire_name {
+	($($target:ty),+ $(,)?) => {$(
+		impl fmt::Display for $target {
+			fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+				f.write_str(self.as_str())
+			}
+		}
+
+		impl FromStr for $target {
+			type Err = serde::de::value::Error;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				use serde::de::IntoDeserializer;
+
+				Self::deserialize(s.to_ascii_lowercase().into_deserializer())
+			}
+		}
+	)+};
+}
+
+wire_name!(StationState, DeviceMode, NetworkType);
+
+/// Received signal strength.
+///
+/// Stored the way `GetOrderedNetworks` reports it, in units of 1/100 dBm;
+/// plain dBm values (e.g. `StationDiagnostic`'s RSSI) go through
+/// [`SignalStrength::from_dbm`].
+#[derive(
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Deserialize,
+	Serialize,
+	Debug,
+)]
+#[serde(transparent)]
+pub struct SignalStrength(i16);
+
+zvar_type!(i16, [SignalStrength]);
+
+impl SignalStrength {
+	/// [`quality_label`](SignalStrength::quality_label) is `Excellent` above
+	/// this.
+	pub const EXCELLENT: Self = SignalStrength::from_dbm(-50);
+	/// The weakest still `Good`.
+	pub const GOOD: Self = SignalStrength::from_dbm(-60);
+	/// The weakest still `Fair`.
+	pub const FAIR: Self = SignalStrength::from_dbm(-70);
+	/// The weakest still `Poor`, rather than `Very Poor`.
+	pub const POOR: Self = SignalStrength::from_dbm(-80);
+
+	pub const fn from_dbm(dbm: i16) -> Self {
+		SignalStrength(dbm.saturating_mul(100))
+	}
+
+	/// From 1/100 dBm, the unit iwd reports signal strength in.
+	pub const fn from_centi_dbm(centi_dbm: i16) -> Self {
+		SignalStrength(centi_dbm)
+	}
+
+	/// The raw value in 1/100 dBm.
+	pub fn centi_dbm(self) -> i16 {
+		self.0
+	}
+
+	pub fn dbm(self) -> f32 {
+		f32::from(self.0) / 100.0
+	}
+
+	/// Signal quality, linear from 0% at -100 dBm to 100% at -50 dBm and
+	/// clamped outside of that.
+	pub fn percent(self) -> u8 {
+		const FLOOR: i32 = SignalStrength::from_dbm(-100).0 as i32;
+		const CEIL: i32 = SignalStrength::from_dbm(-50).0 as i32;
+
+		let percent = (i32::from(self.0) - FLOOR) * 100 / (CEIL - FLOOR);
+		percent.clamp(0, 100) as u8
+	}
+
+	/// Number of bars out of 4, each covering a quarter of [`percent`].
+	///
+	/// [`percent`]: SignalStrength::percent
+	pub fn bars(self) -> u8 {
+		self.percent().div_ceil(25)
+	}
+
+	/// [`bars`](SignalStrength::bars) drawn as a fixed-width meter.
+	pub fn meter(self) -> &'static str {
+		match self.bars() {
+			0 => "    ",
+			1 => "▂   ",
+			2 => "▂▄  ",
+			3 => "▂▄▆ ",
+			_ => "▂▄▆█",
+		}
+	}
+
+	/// [`bars`](SignalStrength::bars) drawn only as wide as they are, a
+	/// space for none.
+	pub fn bars_unicode(self) -> &'static str {
+		match self.bars() {
+			0 => " ",
+			1 => "▂",
+			2 => "▂▄",
+			3 => "▂▄▆",
+			_ => "▂▄▆█",
+		}
+	}
+
+	/// How good the signal is, in words for users rather than dBm.
+	pub fn quality_label(self) -> &'static str {
+		match self {
+			s if s > Self::EXCELLENT => "Excellent",
+			s if s >= Self::GOOD => "Good",
+			s if s >= Self::FAIR => "Fair",
+			s if s >= Self::POOR => "Poor",
+			_ => "Very Poor",
+		}
+	}
+}
+
+impl fmt::Display for SignalStrength {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} dBm", self.0 / 100)
+	}
+}
+
+/// Network name.
+///
+/// SSIDs are arbitrary bytes, but iwd hands them out as D-Bus strings, so
+/// they are only [`Ssid::Raw`] when they come from somewhere else and aren't
+/// valid UTF-8.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Ssid {
+	Utf8(String),
+	Raw(Vec<u8>),
+}
+
+zvar_type_str!(Ssid);
+
+impl Ssid {
+	pub fn from_bytes(bytes: Vec<u8>) -> Self {
+		match String::from_utf8(bytes) {
+			Ok(s) => Ssid::Utf8(s),
+			Err(err) => Ssid::Raw(err.into_bytes()),
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		match self {
+			Ssid::Utf8(s) => s.as_bytes(),
+			Ssid::Raw(bytes) => bytes,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Ssid::Utf8(s) => Some(s),
+			Ssid::Raw(_) => None,
+		}
+	}
+
+	pub fn to_str_lossy(&self) -> Cow<'_, str> {
+		match self {
+			Ssid::Utf8(s) => Cow::Borrowed(s),
+			Ssid::Raw(bytes) => String::from_utf8_lossy(bytes),
+		}
+	}
+
+	pub fn to_hex(&self) -> String {
+		self.as_bytes().iter().map(|b| format!("{b:02x}")).collect()
+	}
+}
+
+impl fmt::Display for Ssid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.to_str_lossy())
+	}
+}
+
+impl<'de> Deserialize<'de> for Ssid {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		String::deserialize(deserializer).map(Ssid::Utf8)
+	}
+}
+
+impl Serialize for Ssid {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		serializer.serialize_str(&self.to_str_lossy())
+	}
+}
+
+/// A MAC address, e.g. a device's or a BSSID.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MacAddress(pub [u8; 6]);
+
+zvar_type_str!(MacAddress);
+
+/// A MAC address that isn't six colon-separated pairs of hex digits.
+#[derive(Clone, PartialEq, Eq, Debug, thiserror::Error)]
+#[error("invalid MAC address {0:?}")]
+pub struct InvalidMacAddress(String);
+
+impl FromStr for MacAddress {
+	type Err = InvalidMacAddress;
+
+	/// Parses the way iwd formats them, `aa:bb:cc:dd:ee:ff`, in either case.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || InvalidMacAddress(s.to_owned());
+		let mut octets = [0; 6];
+		let mut parts = s.split(':');
+		for octet in &mut octets {
+			let part = parts.next().ok_or_else(invalid)?;
+			if part.len() != 2 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+				return Err(invalid());
+			}
+			*octet = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+		}
+		match parts.next() {
+			Some(_) => Err(invalid()),
+			None => Ok(MacAddress(octets)),
+		}
+	}
+}
+
+impl TryFrom<&str> for MacAddress {
+	type Error = InvalidMacAddress;
+
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+
+impl fmt::Display for MacAddress {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (i, octet) in self.0.iter().enumerate() {
+			if i > 0 {
+				f.write_str(":")?;
+			}
+			write!(f, "{octet:02x}")?;
+		}
+		Ok(())
+	}
+}
+
+impl<'de> Deserialize<'de> for MacAddress {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let s = String::deserialize(deserializer)?;
+		s.parse().map_err(serde::de::Error::custom)
+	}
+}
+
+impl Serialize for MacAddress {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		serializer.collect_str(self)
+	}
+}
+
+/// A WPS PIN: 4 or 8 digits, as iwd takes them for PIN provisioning, with
+/// WPS or Wi-Fi Direct.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WpsPin(String);
+
+/// A PIN that isn't 4 or 8 digits.
+#[derive(Clone, PartialEq, Eq, Debug, thiserror::Error)]
+#[error("invalid WPS PIN {0:?}, expected 4 or 8 digits")]
+pub struct InvalidWpsPin(String);
+
+impl WpsPin {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for WpsPin {
+	type Err = InvalidWpsPin;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let digits = s.bytes().all(|b| b.is_ascii_digit());
+		if digits && matches!(s.len(), 4 | 8) {
+			Ok(WpsPin(s.to_owned()))
+		} else {
+			Err(InvalidWpsPin(s.to_owned()))
+		}
+	}
+}
+
+impl fmt::Display for WpsPin {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// How to prove to a Wi-Fi Direct peer that connecting is wanted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Provisioning {
+	/// The button pressed on both ends.
+	PushButton,
+	/// The PIN the peer shows or was given.
+	Pin(WpsPin),
+}
+
+impl Provisioning {
+	/// The method, as `Connect` takes it.
+	pub fn method(&self) -> &'static str {
+		match self {
+			Provisioning::PushButton => "pushbutton",
+			Provisioning::Pin(_) => "pin",
+		}
+	}
+
+	/// The PIN, as `Connect` takes it; empty for a push button.
+	pub fn pin(&self) -> &str {
+		match self {
+			Provisioning::PushButton => "",
+			Provisioning::Pin(pin) => pin.as_str(),
+		}
+	}
+}
+
+/// An access point that only answers directed probes, so its SSID is
+/// unknown until connecting with it.
+#[derive(
+	Clone, PartialEq, Eq, Debug, Deserialize, Serialize, zvariant::Type,
+)]
+pub struct HiddenAccessPoint {
+	address: String,
+	strength: SignalStrength,
+	#[serde(rename = "type")]
+	type_: NetworkType,
+}
+
+impl HiddenAccessPoint {
+	/// BSSID, as iwd formats it.
+	pub fn address(&self) -> &str {
+		&self.address
+	}
+
+	pub fn strength(&self) -> SignalStrength {
+		self.strength
+	}
+
+	pub fn network_type(&self) -> NetworkType {
+		self.type_
+	}
+}
+
+/// The band a channel is in, by its frequency.
+#[derive(
+	Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize,
+)]
+pub enum WifiBand {
+	#[serde(rename = "2.4")]
+	TwoPointFour,
+	#[serde(rename = "5")]
+	Five,
+	#[serde(rename = "6")]
+	Six,
+}
+
+impl WifiBand {
+	/// The band of the channel at `mhz`, e.g. 2412; `None` outside of them.
+	pub fn from_frequency(mhz: u32) -> Option<Self> {
+		match mhz {
+			2400..=2500 => Some(WifiBand::TwoPointFour),
+			4900..=5900 => Some(WifiBand::Five),
+			5925..=7125 => Some(WifiBand::Six),
+			_ => None,
+		}
+	}
+
+	/// In GHz: `2.4`, `5` or `6`.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			WifiBand::TwoPointFour => "2.4",
+			WifiBand::Five => "5",
+			WifiBand::Six => "6",
+		}
+	}
+
+	/// `bands` joined with `+`, e.g. `2.4+5`; empty if there are none.
+	pub fn joined(bands: &[WifiBand]) -> String {
+		let names: Vec<_> = bands.iter().map(|band| band.as_str()).collect();
+		names.join("+")
+	}
+
+	/// About how far the band reaches indoors, through a wall or two; the
+	/// higher the frequency, the less it gets through.
+	pub fn typical_range_meters(&self) -> u32 {
+		match self {
+			WifiBand::TwoPointFour => 45,
+			WifiBand::Five => 30,
+			WifiBand::Six => 20,
+		}
+	}
+}
+
+impl fmt::Display for WifiBand {
+	/// With the unit, e.g. `2.4 GHz`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} GHz", self.as_str())
+	}
+}
+
+// Property dicts use iwd's PascalCase names on the D-Bus side, but serialize
+// to snake_case keys for JSON output. Object paths serialize as strings.
+
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct Station {
+	state: StationState,
+	connected_network: Option<OPath<NetworkProxy<'static>>>,
+	disconnected_reason: Option<String>,
+	scanning: bool,
+	/// In seconds since the Unix epoch.
+	last_scan: Option<u64>,
+}
+
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct Device {
+	name: String,
+	address: MacAddress,
+	powered: bool,
+	adapter: OPath<AdapterProxy<'static>>,
+	mode: DeviceMode,
+}
+
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct Network {
+	name: Ssid,
+	#[serde(rename = "type")]
+	type_: NetworkType,
+	connected: bool,
+	device: OPath<DeviceProxy<'static>>,
+	known_network: Option<OPath<KnownNetworkProxy<'static>>>,
+	/// In MHz; only iwd 2 and later say.
+	frequency: Option<u32>,
+}
+
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct KnownNetwork {
+	name: Ssid,
+	#[serde(rename = "type")]
+	type_: NetworkType,
+	hidden: bool,
+	last_connected_time: String,
+	auto_connect: bool,
+}
+
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct Adapter {
+	name: String,
+	powered: bool,
+	model: Option<String>,
+	vendor: Option<String>,
+	supported_modes: Box<[DeviceMode]>,
+}
+
+/// One access point of a network, from iwd 2 on; older ones only report
+/// the network as a whole.
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct BasicServiceSet {
+	address: MacAddress,
+	/// In MHz.
+	frequency: Option<u32>,
+	/// In dBm.
+	#[zvariant(rename = "RSSI")]
+	rssi: Option<i16>,
+	network: Option<OPath<NetworkProxy<'static>>>,
+}
+
+/// A Wi-Fi Direct device in range, found by a P2P device's discovery.
+#[derive(
+	Clone,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct P2pPeer {
+	name: String,
+	/// e.g. `display` or `computer`.
+	device_category: Option<String>,
+	device_subcategory: Option<String>,
+	device: Option<OPath<P2pDeviceProxy<'static>>>,
+	connected: bool,
+	/// Interface name of the group, once connected.
+	connected_interface: Option<String>,
+	/// The peer's address in the group, once connected.
+	#[zvariant(rename = "ConnectedIP")]
+	connected_ip: Option<String>,
+}
+
+/// What a Wi-Fi Display (Miracast) capable peer can do; iwd leaves out what
+/// the peer doesn't say.
+#[derive(
+	Clone,
+	Default,
+	PartialEq,
+	Eq,
+	Debug,
+	Serialize,
+	zvariant::DeserializeDict,
+	zvariant::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+pub struct P2pDisplay {
+	/// Sends its screen to others.
+	source: Option<bool>,
+	/// Shows others' screens, e.g. a TV.
+	sink: Option<bool>,
+	/// The RTSP port it takes sessions on.
+	port: Option<u16>,
+}
+
+impl Station {
+	pub fn state(&self) -> StationState {
+		self.state
+	}
+
+	/// Object path of the connected network, if any.
+	pub fn connected_network(&self) -> Option<&OPath<NetworkProxy<'static>>> {
+		self.connected_network.as_ref()
+	}
+
+	/// Why the station was disconnected, e.g. `deauthentication`; only
+	/// while it is, and only from iwd 2 on.
+	pub fn disconnected_reason(&self) -> Option<&str> {
+		self.disconnected_reason.as_deref()
+	}
+
+	pub fn is_scanning(&self) -> bool {
+		self.scanning
+	}
+
+	/// When the last scan finished, if iwd says.
+	pub fn last_scan(&self) -> Option<SystemTime> {
+		let secs = self.last_scan?;
+		SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+	}
+}
+
+impl Device {
+	/// Interface name, e.g. `wlan0`.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn address(&self) -> MacAddress {
+		self.address
+	}
+
+	pub fn is_powered(&self) -> bool {
+		self.powered
+	}
+
+	/// Object path of the adapter the device belongs to.
+	pub fn adapter(&self) -> &OPath<AdapterProxy<'static>> {
+		&self.adapter
+	}
+
+	pub fn mode(&self) -> DeviceMode {
+		self.mode
+	}
+}
+
+impl Network {
+	pub fn name(&self) -> &Ssid {
+		&self.name
+	}
+
+	pub fn network_type(&self) -> NetworkType {
+		self.type_
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.connected
+	}
+
+	/// Object path of the device the network was seen on.
+	pub fn device(&self) -> &OPath<DeviceProxy<'static>> {
+		&self.device
+	}
+
+	/// Object path of the matching known network, if there is a profile.
+	pub fn known_network(&self) -> Option<&OPath<KnownNetworkProxy<'static>>> {
+		self.known_network.as_ref()
+	}
+
+	/// In MHz, e.g. 2412.
+	pub fn frequency(&self) -> Option<u32> {
+		self.frequency
+	}
+
+	pub fn band(&self) -> Option<WifiBand> {
+		WifiBand::from_frequency(self.frequency?)
+	}
+}
+
+impl KnownNetwork {
+	pub fn name(&self) -> &Ssid {
+		&self.name
+	}
+
+	pub fn network_type(&self) -> NetworkType {
+		self.type_
+	}
+
+	pub fn is_hidden(&self) -> bool {
+		self.hidden
+	}
+
+	/// ISO 8601 timestamp, as iwd formats it.
+	pub fn last_connected_time(&self) -> &str {
+		&self.last_connected_time
+	}
+
+	/// [`last_connected_time`](KnownNetwork::last_connected_time), parsed.
+	pub fn last_connected(&self) -> Option<SystemTime> {
+		parse_iwd_time(&self.last_connected_time)
+	}
+
+	/// How many whole days ago the network was last connected to; `None` if
+	/// iwd doesn't say, and 0 if the clock says it's yet to come.
+	pub fn days_since_last_connection(&self) -> Option<u64> {
+		let last = self.last_connected()?;
+		let ago = SystemTime::now().duration_since(last).unwrap_or_default();
+		Some(ago.as_secs() / (24 * 60 * 60))
+	}
+
+	/// Whether the network was last connected to at least `threshold_days`
+	/// days ago; `false` if that's unknown.
+	pub fn is_stale(&self, threshold_days: u64) -> bool {
+		self.days_since_last_connection()
+			.is_some_and(|days| days >= threshold_days)
+	}
+
+	pub fn auto_connect(&self) -> bool {
+		self.auto_connect
+	}
+
+	/// Where iwd keeps the profile, in its default state directory; `None`
+	/// for types it keeps no profile file of.
+	///
+	/// The file is named after the SSID if it only has ASCII alphanumerics,
+	/// spaces, `-` and `_`, and `=` followed by the SSID in hex otherwise.
+	pub fn profile_path(&self) -> Option<PathBuf> {
+		let ext = match self.type_ {
+			NetworkType::Open => "open",
+			NetworkType::Psk => "psk",
+			NetworkType::Eap => "8021x",
+			NetworkType::Wep | NetworkType::Hotspot => return None,
+		};
+		let plain = self.name.as_str().filter(|name| {
+			name.bytes()
+				.all(|b| b.is_ascii_alphanumeric() || b" -_".contains(&b))
+		});
+		let stem = match plain {
+			Some(name) => name.to_owned(),
+			None => format!("={}", self.name.to_hex()),
+		};
+		Some(Path::new("/var/lib/iwd").join(format!("{stem}.{ext}")))
+	}
+}
+
+/// Parses a UTC timestamp the way iwd writes them, e.g.
+/// `2024-03-01T10:00:00Z`, with optional fractional seconds.
+pub fn parse_iwd_time(s: &str) -> Option<SystemTime> {
+	fn number(s: &str) -> Option<u64> {
+		s.bytes()
+			.all(|b| b.is_ascii_digit())
+			.then(|| s.parse().ok())?
+	}
+
+	let (date, time) = s.strip_suffix('Z')?.split_once('T')?;
+	let (time, fraction) = time.split_once('.').unwrap_or((time, "0"));
+	number(fraction)?;
+	let mut date = date.splitn(3, '-').map(number);
+	let mut time = time.splitn(3, ':').map(number);
+	let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+	let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+	let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+	let month_days = match month {
+		2 if leap => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		1..=12 => 31,
+		_ => return None,
+	};
+	if year < 1970
+		|| !(1..=month_days).contains(&day)
+		|| hour > 23
+		|| minute > 59
+		|| second > 60
+	{
+		return None;
+	}
+
+	// days since the epoch of the proleptic Gregorian date, with the year
+	// starting in March so that leap days come last
+	let (y, m) = if month > 2 {
+		(year, month - 3)
+	} else {
+		(year - 1, month + 9)
+	};
+	let days = y * 365 + y / 4 - y / 100 + y / 400 + (153 * m + 2) / 5 + day
+		- 1 - 719_468;
+	let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+	SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+impl Adapter {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn is_powered(&self) -> bool {
+		self.powered
+	}
+
+	pub fn model(&self) -> Option<&str> {
+		self.model.as_deref()
+	}
+
+	pub fn vendor(&self) -> Option<&str> {
+		self.vendor.as_deref()
+	}
+
+	/// [`model`](Adapter::model), or `Unknown`.
+	pub fn model_string(&self) -> &str {
+		self.model().unwrap_or("Unknown")
+	}
+
+	/// [`vendor`](Adapter::vendor), or `Unknown`.
+	pub fn vendor_string(&self) -> &str {
+		self.vendor().unwrap_or("Unknown")
+	}
+
+	/// Vendor and model for display, e.g. `Intel Corporation Wi-Fi 6 AX200`,
+	/// or whichever of the two is known.
+	pub fn description(&self) -> String {
+		match (self.vendor(), self.model()) {
+			(Some(vendor), Some(model)) => format!("{vendor} {model}"),
+			(Some(known), None) | (None, Some(known)) => known.to_owned(),
+			(None, None) => "Unknown".to_owned(),
+		}
+	}
+
+	pub fn supported_modes(&self) -> &[DeviceMode] {
+		&self.supported_modes
+	}
+
+	pub fn supports(&self, mode: DeviceMode) -> bool {
+		self.supported_modes.contains(&mode)
+	}
+
+	/// Whether the adapter supports more than one mode.
+	///
+	/// iwd doesn't say whether they can run at the same time, e.g. an AP
+	/// next to a station, only that each works on its own; so this is a hint
+	/// at best.
+	pub fn is_multimode(&self) -> bool {
+		self.supported_modes
+			.iter()
+			.any(|mode| *mode != self.supported_modes[0])
+	}
+}
+
+impl BasicServiceSet {
+	/// BSSID.
+	pub fn address(&self) -> MacAddress {
+		self.address
+	}
+
+	/// In MHz, e.g. 2412.
+	pub fn frequency(&self) -> Option<u32> {
+		self.frequency
+	}
+
+	pub fn band(&self) -> Option<WifiBand> {
+		WifiBand::from_frequency(self.frequency?)
+	}
+
+	pub fn strength(&self) -> Option<SignalStrength> {
+		self.rssi.map(SignalStrength::from_dbm)
+	}
+
+	/// Object path of the network the access point belongs to.
+	pub fn network(&self) -> Option<&OPath<NetworkProxy<'static>>> {
+		self.network.as_ref()
+	}
+}
+
+impl P2pPeer {
+	/// What the peer calls itself; not unique.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn device_category(&self) -> Option<&str> {
+		self.device_category.as_deref()
+	}
+
+	pub fn device_subcategory(&self) -> Option<&str> {
+		self.device_subcategory.as_deref()
+	}
+
+	/// Object path of the P2P device that found the peer.
+	pub fn device(&self) -> Option<&OPath<P2pDeviceProxy<'static>>> {
+		self.device.as_ref()
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.connected
+	}
+
+	/// e.g. `p2p-wlan0-0`.
+	pub fn connected_interface(&self) -> Option<&str> {
+		self.connected_interface.as_deref()
+	}
+
+	pub fn connected_ip(&self) -> Option<&str> {
+		self.connected_ip.as_deref()
+	}
+}
+
+impl P2pDisplay {
+	pub fn source(&self) -> Option<bool> {
+		self.source
+	}
+
+	pub fn sink(&self) -> Option<bool> {
+		self.sink
+	}
+
+	pub fn port(&self) -> Option<u16> {
+		self.port
+	}
+
+	/// What it can be, `source`, `sink` or `source+sink`; empty if it
+	/// doesn't say.
+	pub fn roles(&self) -> String {
+		let roles = [("source", self.source), ("sink", self.sink)];
+		let names: Vec<_> = roles
+			.iter()
+			.filter_map(|&(name, is)| is?.then_some(name))
+			.collect();
+		names.join("+")
+	}
+}
@@ -0,0 +1,42 @@
+use iwd_playground::proxy::AccessPointConfig;
+use iwd_playground::IwdError;
+
+fn protected(psk: &str) -> AccessPointConfig {
+	AccessPointConfig::Protected {
+		ssid: "hotspot".to_owned(),
+		psk: psk.to_owned(),
+	}
+}
+
+fn assert_rejected(psk: &str) {
+	match protected(psk).validate() {
+		Err(IwdError::InvalidArguments(_)) => {}
+		other => panic!("{psk:?} gave {other:?}"),
+	}
+}
+
+#[test]
+fn passphrase_lengths() {
+	assert_rejected("");
+	assert_rejected(&"a".repeat(7));
+	protected(&"a".repeat(8)).validate().unwrap();
+	protected(&"a".repeat(63)).validate().unwrap();
+	assert_rejected(&"a".repeat(64));
+}
+
+#[test]
+fn passphrase_characters() {
+	protected(" !~ spaces and symbols ").validate().unwrap();
+	assert_rejected("pässwörter");
+	assert_rejected("パスワードです");
+	assert_rejected("tab\tinside");
+	assert_rejected("del\x7f inside");
+}
+
+#[test]
+fn open_needs_no_passphrase() {
+	let config = AccessPointConfig::Open {
+		ssid: "hotspot".to_owned(),
+	};
+	config.validate().unwrap();
+}
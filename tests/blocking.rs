@@ -0,0 +1,85 @@
+//! The blocking client against [`mock::MockIwd`].
+
+#![cfg(feature = "blocking")]
+
+mod mock;
+
+use std::sync::mpsc;
+use std::thread;
+
+use iwd_playground::blocking::IwdClient;
+use iwd_playground::types::{NetworkType, StationState};
+use zbus::zvariant::OwnedObjectPath;
+
+use mock::{block_on, device, MockIwd, ADAPTER};
+
+/// Serves `iwd` from a thread of its own, which keeps the executor the mock
+/// runs on alive, and returns a blocking client talking to it.
+fn serve(iwd: MockIwd) -> IwdClient {
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		block_on(async {
+			let (client, server) = iwd.connect().await.unwrap();
+			tx.send(client).unwrap();
+			futures_lite::future::pending::<()>().await;
+			drop(server);
+		})
+	});
+	let conn = zbus::blocking::Connection::from(rx.recv().unwrap());
+	IwdClient::new(conn).unwrap()
+}
+
+#[test]
+fn lists_networks_in_order() {
+	let client = serve(MockIwd::default());
+	let objects = client.objects().unwrap();
+
+	let rows = client.ordered_networks(&objects, &device()).unwrap();
+	let summary: Vec<_> = rows
+		.iter()
+		.map(|row| {
+			(
+				row.ssid().unwrap().to_string(),
+				row.network_type().unwrap(),
+				row.is_connected(),
+			)
+		})
+		.collect();
+	assert_eq!(
+		summary,
+		[
+			("home".to_owned(), NetworkType::Psk, true),
+			("café".to_owned(), NetworkType::Open, false),
+			("work".to_owned(), NetworkType::Eap, false),
+		]
+	);
+}
+
+#[test]
+fn scans_and_connects() {
+	let iwd = MockIwd::default();
+	let client = serve(iwd.clone());
+
+	client.scan(&device()).unwrap();
+	assert!(!client.station(&device()).unwrap().scanning().unwrap());
+
+	client.connect(&iwd.network("café").path()).unwrap();
+	let objects = client.objects().unwrap();
+	let rows = client.ordered_networks(&objects, &device()).unwrap();
+	let cafe = rows
+		.iter()
+		.find(|row| row.ssid().unwrap().to_string() == "café");
+	assert!(cafe.unwrap().is_connected());
+}
+
+#[test]
+fn reports_status() {
+	let client = serve(MockIwd::default());
+
+	let (station, network) = client.status(&device()).unwrap();
+	assert_eq!(station.state(), StationState::Connected);
+	assert_eq!(network.unwrap().name().to_string(), "home");
+
+	let adapter = OwnedObjectPath::try_from(ADAPTER).unwrap();
+	assert!(client.status(&adapter).is_err());
+}
@@ -0,0 +1,93 @@
+//! The client against a mock that is slow, fails, or changes underneath it.
+
+mod mock;
+
+use std::time::Duration;
+
+use iwd_playground::client::{scan_and_wait, CallTimeouts};
+use iwd_playground::error::IwdErrorKind;
+use iwd_playground::types::StationState;
+use iwd_playground::IwdError;
+
+use mock::fault::{Fault, MockError};
+use mock::{block_on, device, MockIwd};
+
+#[test]
+fn iwd_errors_are_mapped() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let error = MockError::iwd("NotAvailable", "radio is off");
+		fixture.faults().set("Scan", Fault::error(error));
+
+		let client = &fixture.client;
+		let station = client.station(&device()).await.unwrap();
+		let err = scan_and_wait(client, &station).await.unwrap_err();
+		assert!(err.is_iwd(IwdErrorKind::NotAvailable), "{err:?}");
+		assert_eq!(err.to_string(), "iwd: radio is off");
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn slow_replies_time_out() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let delay = Fault::delay(Duration::from_secs(1));
+		fixture.faults().set("GetOrderedNetworks", delay);
+
+		let client = fixture.client.clone().with_timeouts(CallTimeouts {
+			quick: Duration::from_millis(100),
+			..CallTimeouts::DEFAULT
+		});
+		let objects = client.objects().await.unwrap();
+		let err = client
+			.ordered_networks(&objects, &device())
+			.await
+			.unwrap_err();
+		assert!(
+			matches!(
+				err,
+				IwdError::Timeout {
+					method: "GetOrderedNetworks",
+					..
+				}
+			),
+			"{err:?}"
+		);
+		// the timeouts are the client's own
+		let client = &fixture.client;
+		client.ordered_networks(&objects, &device()).await.unwrap();
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn changes_to_the_mock_are_seen() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let work = fixture.iwd.network("work").path();
+
+		fixture.set_connected(Some("work")).await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+		let (_, station) = objects.stations().next().unwrap();
+		assert_eq!(station.connected_network().unwrap(), &work);
+
+		fixture.set_connected(None).await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+		let (_, station) = objects.stations().next().unwrap();
+		assert_eq!(station.state(), StationState::Disconnected);
+		assert!(objects.networks().all(|(_, net)| !net.is_connected()));
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn closes_during_a_scan() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let station = fixture.client.station(&device()).await.unwrap();
+		station.scan().await.unwrap();
+		assert!(station.scanning().await.unwrap());
+		fixture.close().await.unwrap();
+	});
+}
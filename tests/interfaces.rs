@@ -0,0 +1,491 @@
+//! The interface impls of the property structs, served and read back.
+
+mod mock;
+
+use std::collections::HashMap;
+
+use iwd_playground::bus::DEFAULT_SERVICE;
+use iwd_playground::objects::{All, IwdInterface, IwdObjectMap, IwdRole};
+use iwd_playground::types::{
+	Adapter, BasicServiceSet, Device, KnownNetwork, Network, NetworkType,
+	P2pDisplay, P2pPeer, Station, StationState,
+};
+use serde::de::DeserializeOwned;
+use zbus::fdo::{ObjectManagerProxy, PropertiesProxy};
+use zbus::object_server::Interface;
+use zbus::zvariant::serialized::Context;
+use zbus::zvariant::{self, OwnedObjectPath, Type};
+
+use mock::{block_on, MockDisplay, MockIwd, MockPeer};
+
+/// Serves `value` at `path`, and deserializes what `GetAll` returns for it
+/// the way `GetManagedObjects` results are.
+async fn round_trip<T>(path: &OwnedObjectPath, value: &T) -> T
+where
+	T: Interface + Clone + DeserializeOwned + Type,
+{
+	let (client, server) = mock::pair().await.unwrap();
+	server
+		.object_server()
+		.at(path, value.clone())
+		.await
+		.unwrap();
+
+	let properties = PropertiesProxy::builder(&client)
+		.destination(DEFAULT_SERVICE)
+		.unwrap()
+		.path(path)
+		.unwrap()
+		.build()
+		.await
+		.unwrap();
+	let all = properties.get_all(Some(T::name()).into()).await.unwrap();
+	let data =
+		zvariant::to_bytes(Context::new_dbus(zvariant::LE, 0), &all).unwrap();
+	data.deserialize().unwrap().0
+}
+
+#[test]
+fn properties_round_trip() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+
+		for (path, station) in objects.stations() {
+			assert_eq!(&round_trip(path, station).await, station);
+		}
+		for (path, device) in objects.devices() {
+			assert_eq!(&round_trip(path, device).await, device);
+		}
+		for (path, network) in objects.networks() {
+			assert_eq!(&round_trip(path, network).await, network);
+		}
+		for (path, known) in objects.known_networks() {
+			assert_eq!(&round_trip(path, known).await, known);
+		}
+		for (path, adapter) in objects.adapters() {
+			assert_eq!(&round_trip(path, adapter).await, adapter);
+		}
+		for (path, bss) in objects.basic_service_sets() {
+			assert_eq!(&round_trip(path, bss).await, bss);
+		}
+		assert_eq!(objects.basic_service_sets().count(), 3);
+		// some with and some without their optional properties
+		assert!(objects.networks().any(|(_, n)| n.known_network().is_none()));
+		assert!(objects.networks().any(|(_, n)| n.known_network().is_some()));
+		assert!(objects.networks().any(|(_, n)| n.frequency().is_none()));
+		assert!(objects.networks().any(|(_, n)| n.frequency().is_some()));
+	});
+}
+
+/// A `Station` dict as it comes off the bus: `State`, `ConnectedNetwork`
+/// and `Scanning`, keys sorted, little endian.
+const STATION_DUMP: &str = "\
+	760000000000000010000000436f6e6e65637465644e6574776f726b00016f0021000000\
+	2f6e65742f636f6e6e6d616e2f6977642f302f342f36383666366436355f70736b0000\
+	00080000005363616e6e696e6700016200000000000000000005000000537461746500\
+	01730000000009000000636f6e6e656374656400";
+
+/// The `Network` it's connected to, including `ExtendedServiceSet`, which
+/// isn't read.
+const NETWORK_DUMP: &str = "\
+	040100000000000009000000436f6e6e656374656400016200000000010000000600\
+	000044657669636500016f000000140000002f6e65742f636f6e6e6d616e2f697764\
+	2f302f34000000000000000012000000457874656e64656453657276696365536574\
+	0002616f0000330000002e0000002f6e65742f636f6e6e6d616e2f6977642f302f34\
+	2f36383666366436355f70736b2f3061316232633364346535660000000000000c00\
+	00004b6e6f776e4e6574776f726b00016f001d0000002f6e65742f636f6e6e6d616e\
+	2f6977642f36383666366436355f70736b000000040000004e616d65000173000400\
+	0000686f6d65000000000400000054797065000173000300000070736b00";
+
+fn from_dump<T: DeserializeOwned + Type>(hex: &str) -> T {
+	let hex: Vec<_> = hex.bytes().filter(u8::is_ascii_hexdigit).collect();
+	let bytes: Vec<u8> = hex
+		.chunks(2)
+		.map(|b| u8::from_str_radix(std::str::from_utf8(b).unwrap(), 16))
+		.collect::<Result<_, _>>()
+		.unwrap();
+	let ctxt = Context::new_dbus(zvariant::LE, 0);
+	zvariant::serialized::Data::new(bytes, ctxt)
+		.deserialize()
+		.unwrap()
+		.0
+}
+
+#[test]
+fn object_paths_from_captured_dicts() {
+	let station: Station = from_dump(STATION_DUMP);
+	assert_eq!(station.state(), StationState::Connected);
+	let connected = station.connected_network().unwrap();
+	assert_eq!(
+		connected.path().as_str(),
+		"/net/connman/iwd/0/4/686f6d65_psk"
+	);
+
+	let network: Network = from_dump(NETWORK_DUMP);
+	assert_eq!(network.name().as_str(), Some("home"));
+	assert_eq!(network.network_type(), NetworkType::Psk);
+	assert!(network.is_connected());
+	assert_eq!(network.device().path().as_str(), "/net/connman/iwd/0/4");
+	assert_eq!(
+		network.known_network().unwrap().path().as_str(),
+		"/net/connman/iwd/686f6d65_psk"
+	);
+	assert!(connected
+		.path()
+		.as_str()
+		.starts_with(network.device().as_ref()));
+	assert_eq!(network.frequency(), None);
+}
+
+/// `T`'s D-Bus name is the one it's served under, and it's extracted from
+/// the objects that have it, which [`IwdObjectMap::iter_typed`] lists.
+fn assert_extracts<T>(objects: &IwdObjectMap)
+where
+	T: IwdInterface + Interface + Clone + PartialEq + std::fmt::Debug,
+{
+	assert_eq!(T::interface_name(), T::name().as_str());
+
+	let mut paths = Vec::new();
+	for (path, all) in objects {
+		let mut all = all.clone();
+		let Some(value) = all.extract::<T>().cloned() else {
+			continue;
+		};
+		assert_eq!(T::extract(&all), Some(&value));
+		assert_eq!(all.extract_mut::<T>(), Some(&mut value.clone()));
+		paths.push(path.clone());
+	}
+	let mut typed: Vec<OwnedObjectPath> =
+		objects.iter_typed::<T>().map(Into::into).collect();
+	paths.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+	typed.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+	assert!(!paths.is_empty(), "{}", T::interface_name());
+	assert_eq!(paths, typed);
+}
+
+#[test]
+fn interfaces_are_extracted_by_type() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+
+		assert_extracts::<Station>(&objects);
+		assert_extracts::<Device>(&objects);
+		assert_extracts::<Network>(&objects);
+		assert_extracts::<KnownNetwork>(&objects);
+		assert_extracts::<Adapter>(&objects);
+		assert_extracts::<BasicServiceSet>(&objects);
+	});
+}
+
+#[test]
+fn peers_round_trip_with_their_display() {
+	let sink = MockDisplay {
+		source: Some(false),
+		sink: Some(true),
+		port: Some(7236),
+	};
+	let iwd = MockIwd {
+		p2p: Some(vec![
+			MockPeer::new("tv", "02:00:00:00:05:01", "display")
+				.display(sink)
+				.connected(),
+			MockPeer::new("phone", "02:00:00:00:05:02", "telephone")
+				.display(MockDisplay::default())
+				.connected(),
+			MockPeer::new("laptop", "02:00:00:00:05:03", "computer")
+				.connected(),
+		]),
+		..MockIwd::default()
+	};
+	block_on(async {
+		let fixture = iwd.fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+
+		for (path, peer) in objects.p2p_peers() {
+			assert_eq!(&round_trip(path, peer).await, peer);
+		}
+		for (path, display) in objects.p2p_displays() {
+			assert_eq!(&round_trip(path, display).await, display);
+		}
+		assert_extracts::<P2pPeer>(&objects);
+		assert_extracts::<P2pDisplay>(&objects);
+
+		let mut roles: Vec<_> = objects
+			.p2p_peers()
+			.map(|(path, peer)| {
+				let all = objects.object(path).unwrap();
+				let display = all.extract::<P2pDisplay>();
+				(peer.name(), display.map(|d| (d.roles(), d.port())))
+			})
+			.collect();
+		roles.sort();
+		assert_eq!(
+			roles,
+			[
+				("laptop", None),
+				("phone", Some((String::new(), None))),
+				("tv", Some(("sink".to_owned(), Some(7236)))),
+			]
+		);
+	});
+}
+
+#[test]
+fn meta_interfaces_are_skipped() {
+	use std::collections::HashSet;
+
+	let interfaces: HashMap<_, _> = [
+		"org.freedesktop.DBus.Properties",
+		"org.freedesktop.DBus.Introspectable",
+		"org.freedesktop.DBus.Peer",
+		"net.connman.iwd.Future",
+	]
+	.into_iter()
+	.map(|name| (name, HashMap::<String, zvariant::Value>::new()))
+	.collect();
+	let data =
+		zvariant::to_bytes(Context::new_dbus(zvariant::LE, 0), &interfaces)
+			.unwrap();
+	let rest = || {
+		let all: All = data.deserialize().unwrap().0;
+		let mut names: Vec<_> =
+			all.rest_interfaces().map(|name| name.to_string()).collect();
+		names.sort();
+		names
+	};
+
+	assert_eq!(rest(), ["net.connman.iwd.Future"]);
+	{
+		let _guard = All::skipping(HashSet::new());
+		assert_eq!(
+			rest(),
+			[
+				"net.connman.iwd.Future",
+				"org.freedesktop.DBus.Introspectable",
+				"org.freedesktop.DBus.Peer",
+				"org.freedesktop.DBus.Properties",
+			]
+		);
+	}
+	assert_eq!(rest(), ["net.connman.iwd.Future"]);
+}
+
+#[test]
+fn clients_skip_their_own_list() {
+	use std::collections::HashSet;
+
+	use zbus::names::{InterfaceName, OwnedInterfaceName};
+
+	const DIAGNOSTIC: &str = "net.connman.iwd.StationDiagnostic";
+
+	fn assert_send<T: Send>(_: &T) {}
+
+	fn rest(objects: &IwdObjectMap) -> Vec<String> {
+		let mut rest: Vec<_> = objects
+			.into_iter()
+			.flat_map(|(_, all)| all.rest_interfaces())
+			.map(|name| name.to_string())
+			.collect();
+		rest.dedup();
+		rest
+	}
+
+	block_on(async {
+		let mock = MockIwd {
+			rssi: Some(-50),
+			..MockIwd::default()
+		};
+		let fixture = mock.fixture().await.unwrap();
+		let objects = fixture.client.objects();
+		assert_send(&objects);
+		assert_eq!(rest(&objects.await.unwrap()), [DIAGNOSTIC]);
+
+		let diagnostic: OwnedInterfaceName =
+			InterfaceName::from_static_str_unchecked(DIAGNOSTIC).into();
+		let client = fixture
+			.client
+			.clone()
+			.with_skip_list(HashSet::from([diagnostic]));
+		assert!(rest(&client.objects().await.unwrap()).is_empty());
+	});
+}
+
+#[test]
+fn clones_equal_the_original() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+		for (path, all) in objects {
+			assert_eq!(all.clone(), all, "{path}");
+			assert_eq!(all.try_clone().unwrap(), all, "{path}");
+		}
+	});
+
+	let props = HashMap::from([("Answer", zvariant::Value::U32(42))]);
+	let interfaces = HashMap::from([("net.connman.iwd.Future", props)]);
+	let data =
+		zvariant::to_bytes(Context::new_dbus(zvariant::LE, 0), &interfaces)
+			.unwrap();
+	let all: All = data.deserialize().unwrap().0;
+	assert_eq!(all.rest_interfaces().count(), 1);
+	assert_eq!(all.clone(), all);
+	assert_eq!(all.try_clone().unwrap(), all);
+	assert_ne!(all, All::default());
+}
+
+/// Every interface the mock serves, each with the properties of the first
+/// object that has it.
+struct Interfaces {
+	objects: IwdObjectMap,
+	/// The path of that object, by interface.
+	paths: HashMap<String, OwnedObjectPath>,
+	properties: HashMap<String, HashMap<String, zvariant::OwnedValue>>,
+}
+
+impl Interfaces {
+	async fn fetch() -> Self {
+		let iwd = MockIwd {
+			p2p: Some(vec![MockPeer::new(
+				"tv",
+				"02:00:00:00:05:01",
+				"display",
+			)
+			.display(MockDisplay::default())
+			.connected()]),
+			..MockIwd::default()
+		};
+		let fixture = iwd.fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+		let manager = ObjectManagerProxy::builder(fixture.client.connection())
+			.destination(DEFAULT_SERVICE)
+			.unwrap()
+			.path("/")
+			.unwrap()
+			.build()
+			.await
+			.unwrap();
+
+		let mut paths = HashMap::new();
+		let mut properties = HashMap::new();
+		for (path, object) in manager.get_managed_objects().await.unwrap() {
+			for (name, props) in object {
+				paths.entry(name.to_string()).or_insert(path.clone());
+				properties.entry(name.to_string()).or_insert(props);
+			}
+		}
+		Interfaces {
+			objects,
+			paths,
+			properties,
+		}
+	}
+
+	/// `field` of `json`, a serialized `All`, is the `T` of the object it
+	/// was taken from.
+	fn assert_serialized<T>(&self, json: &serde_json::Value, field: &str)
+	where
+		T: IwdInterface + serde::Serialize,
+	{
+		let path = &self.paths[T::interface_name()];
+		let all = self.objects.object(path).unwrap();
+		let typed = serde_json::to_value(all.extract::<T>().unwrap()).unwrap();
+		assert!(!json[field].is_null(), "{field}");
+		assert_eq!(json[field], typed, "{field}");
+	}
+}
+
+/// An `All` of `interfaces`, by their names and properties.
+fn all_of<V: serde::Serialize + Type>(interfaces: &HashMap<&str, V>) -> All {
+	let data =
+		zvariant::to_bytes(Context::new_dbus(zvariant::LE, 0), interfaces)
+			.unwrap();
+	data.deserialize().unwrap().0
+}
+
+#[test]
+fn every_interface_serializes() {
+	block_on(async {
+		let mut interfaces = Interfaces::fetch().await;
+
+		// one object with every interface there is, which iwd never has
+		for name in ["net.connman.iwd.Future", "com.example.Extra"] {
+			let answer = zvariant::OwnedValue::from(42u32);
+			interfaces.properties.insert(
+				name.to_owned(),
+				[("Answer".to_owned(), answer)].into(),
+			);
+		}
+		let all = all_of(
+			&interfaces
+				.properties
+				.iter()
+				.map(|(name, props)| (name.as_str(), props))
+				.collect(),
+		);
+
+		let json = serde_json::to_value(&all).unwrap();
+		assert_eq!(json.as_object().unwrap().len(), 9);
+		interfaces.assert_serialized::<Station>(&json, "station");
+		interfaces.assert_serialized::<Device>(&json, "device");
+		interfaces.assert_serialized::<Network>(&json, "network");
+		interfaces.assert_serialized::<KnownNetwork>(&json, "known_network");
+		interfaces.assert_serialized::<Adapter>(&json, "adapter");
+		interfaces
+			.assert_serialized::<BasicServiceSet>(&json, "basic_service_set");
+		interfaces.assert_serialized::<P2pPeer>(&json, "p2p_peer");
+		interfaces.assert_serialized::<P2pDisplay>(&json, "p2p_display");
+		assert_eq!(
+			json["rest_interfaces"],
+			serde_json::json!([
+				"com.example.Extra",
+				"net.connman.iwd.Future",
+				"net.connman.iwd.p2p.Device",
+			])
+		);
+	});
+}
+
+#[test]
+fn dominant_role_of_every_combination() {
+	// most specific first
+	let roles = [
+		(Station::interface_name(), IwdRole::Station),
+		(Device::interface_name(), IwdRole::Device),
+		(Network::interface_name(), IwdRole::Network),
+		(KnownNetwork::interface_name(), IwdRole::KnownNetwork),
+		(Adapter::interface_name(), IwdRole::Adapter),
+		(BasicServiceSet::interface_name(), IwdRole::BasicServiceSet),
+		(P2pPeer::interface_name(), IwdRole::P2pPeer),
+		(P2pDisplay::interface_name(), IwdRole::P2pDisplay),
+	];
+
+	block_on(async {
+		let interfaces = Interfaces::fetch().await;
+		let future = HashMap::<String, zvariant::OwnedValue>::new();
+
+		for set in 0..1u32 << roles.len() {
+			let present: Vec<_> = (0..roles.len())
+				.filter(|i| set & 1 << i != 0)
+				.map(|i| roles[i])
+				.collect();
+			let mut object: HashMap<_, _> = present
+				.iter()
+				.map(|(name, _)| (*name, &interfaces.properties[*name]))
+				.collect();
+			// which never counts
+			object.insert("net.connman.iwd.Future", &future);
+
+			let all = all_of(&object);
+			let expected = present.first().map_or(IwdRole::Unknown, |r| r.1);
+			let names: Vec<_> = present.iter().map(|(name, _)| name).collect();
+			assert_eq!(all.dominant_role(), expected, "{names:?}");
+			let roles: Vec<_> = present.iter().map(|(_, role)| *role).collect();
+			assert_eq!(all.roles(), roles, "{names:?}");
+		}
+	});
+
+	assert_eq!(All::default().dominant_role(), IwdRole::Unknown);
+	assert_eq!(All::default().roles(), []);
+}
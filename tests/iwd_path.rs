@@ -0,0 +1,53 @@
+use iwd_playground::opath::{assert_iwd_path, is_iwd_managed_path};
+use iwd_playground::IwdError;
+use zbus::zvariant::ObjectPath;
+
+fn indices(path: &str) -> Option<(u32, u32)> {
+	let path = ObjectPath::try_from(path).unwrap();
+	match assert_iwd_path(&path) {
+		Ok(indices) => {
+			assert!(is_iwd_managed_path(&path), "{path}");
+			Some(indices)
+		}
+		Err(IwdError::InvalidPath(invalid)) => {
+			assert_eq!(invalid.as_str(), path.as_str());
+			assert!(!is_iwd_managed_path(&path), "{path}");
+			None
+		}
+		Err(err) => panic!("{path}: {err}"),
+	}
+}
+
+#[test]
+fn devices_and_below() {
+	assert_eq!(indices("/net/connman/iwd/0/4"), Some((0, 4)));
+	assert_eq!(indices("/net/connman/iwd/12/345"), Some((12, 345)));
+	assert_eq!(indices("/net/connman/iwd/0/4/686f6d65_psk"), Some((0, 4)));
+	assert_eq!(
+		indices("/net/connman/iwd/0/4/686f6d65_psk/0a1b2c3d4e5f"),
+		Some((0, 4))
+	);
+}
+
+#[test]
+fn rejects_missing_segments() {
+	assert_eq!(indices("/"), None);
+	assert_eq!(indices("/net/connman/iwd"), None);
+	assert_eq!(indices("/net/connman/iwd/0"), None);
+}
+
+#[test]
+fn rejects_non_numeric_segments() {
+	assert_eq!(indices("/net/connman/iwd/686f6d65_psk"), None);
+	assert_eq!(indices("/net/connman/iwd/p2p/4"), None);
+	assert_eq!(indices("/net/connman/iwd/0/4a"), None);
+	assert_eq!(indices("/net/connman/iwd/0/4_5"), None);
+	assert_eq!(indices("/net/connman/iwd/0/99999999999"), None);
+}
+
+#[test]
+fn rejects_other_prefixes() {
+	assert_eq!(indices("/net/connman/iwdx/0/4"), None);
+	assert_eq!(indices("/net/connman/0/4"), None);
+	assert_eq!(indices("/org/freedesktop/0/4"), None);
+}
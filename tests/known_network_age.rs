@@ -0,0 +1,68 @@
+//! How long ago known networks were connected to, by the timestamps iwd
+//! keeps.
+
+mod mock;
+
+use std::time::SystemTime;
+
+use iwd_playground::types::parse_iwd_time;
+
+use mock::{block_on, MockIwd};
+
+fn epoch_secs(s: &str) -> Option<u64> {
+	let time = parse_iwd_time(s)?;
+	Some(
+		time.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap()
+			.as_secs(),
+	)
+}
+
+#[test]
+fn parses_timestamps() {
+	assert_eq!(epoch_secs("1970-01-01T00:00:00Z"), Some(0));
+	assert_eq!(epoch_secs("2024-03-01T10:00:00Z"), Some(1_709_287_200));
+	assert_eq!(epoch_secs("2024-03-01T10:00:00.250Z"), Some(1_709_287_200));
+	assert_eq!(epoch_secs("2000-02-29T23:59:59Z"), Some(951_868_799));
+	assert_eq!(epoch_secs("2038-01-19T03:14:08Z"), Some(1 << 31));
+}
+
+#[test]
+fn rejects_malformed_timestamps() {
+	for s in [
+		"",
+		"2024-03-01",
+		"2024-03-01T10:00:00",
+		"2024-03-01T10:00:00+01:00",
+		"2024-03-01 10:00:00Z",
+		"2024-3-1T10:00:00.Z",
+		"2023-02-29T10:00:00Z",
+		"2024-04-31T10:00:00Z",
+		"2024-13-01T10:00:00Z",
+		"2024-03-01T24:00:00Z",
+		"1969-12-31T23:59:59Z",
+		"+2024-03-01T10:00:00Z",
+	] {
+		assert_eq!(epoch_secs(s), None, "{s:?}");
+	}
+}
+
+#[test]
+fn known_networks_age() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+
+		let last = parse_iwd_time("2024-03-01T10:00:00Z").unwrap();
+		let ago = SystemTime::now().duration_since(last).unwrap();
+		let days = ago.as_secs() / 86_400;
+		for (_, known) in objects.known_networks() {
+			assert_eq!(known.last_connected(), Some(last));
+			let since = known.days_since_last_connection().unwrap();
+			// or one more, if another day went by meanwhile
+			assert!(since == days || since == days + 1, "{since} days");
+			assert!(known.is_stale(days));
+			assert!(!known.is_stale(days + 2));
+		}
+	});
+}
@@ -0,0 +1,25 @@
+use iwd_playground::types::MacAddress;
+
+#[test]
+fn parses_and_formats() {
+	let mac: MacAddress = "02:00:AB:cd:0e:ff".parse().unwrap();
+	assert_eq!(mac, MacAddress([0x02, 0x00, 0xab, 0xcd, 0x0e, 0xff]));
+	assert_eq!(mac.to_string(), "02:00:ab:cd:0e:ff");
+	assert_eq!(MacAddress::try_from("02:00:ab:cd:0e:ff"), Ok(mac));
+}
+
+#[test]
+fn rejects_malformed() {
+	for s in [
+		"",
+		"02:00:ab:cd:0e",
+		"02:00:ab:cd:0e:ff:01",
+		"02:00:ab:cd:0e:f",
+		"02:00:ab:cd:0e:+f",
+		"02-00-ab-cd-0e-ff",
+		"02:00:ab:cd:0e:fg",
+		"02:00:ab:cd:0e:ff:",
+	] {
+		assert!(s.parse::<MacAddress>().is_err(), "{s:?}");
+	}
+}
@@ -0,0 +1,108 @@
+//! Making the mock's methods slow or fail, to test the client's timeouts
+//! and error handling.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use zbus::message::{Builder, Header, Message};
+use zbus::names::{ErrorName, OwnedErrorName};
+use zbus::{fdo, DBusError};
+
+/// An error reply of the mock's methods, by name.
+#[derive(Clone, Debug)]
+pub struct MockError {
+	name: OwnedErrorName,
+	message: String,
+}
+
+impl MockError {
+	/// iwd's error called `net.connman.iwd.<kind>`.
+	pub fn iwd(kind: &str, message: &str) -> Self {
+		MockError {
+			name: ErrorName::try_from(format!("net.connman.iwd.{kind}"))
+				.unwrap()
+				.into(),
+			message: message.to_owned(),
+		}
+	}
+}
+
+impl DBusError for MockError {
+	fn create_reply(&self, call: &Header<'_>) -> zbus::Result<Message> {
+		// `Message::method_error` wants the whole call, which isn't at hand
+		#[allow(deprecated)]
+		Builder::error(call, &self.name)?.build(&(&self.message,))
+	}
+
+	fn name(&self) -> ErrorName<'_> {
+		self.name.as_ref()
+	}
+
+	fn description(&self) -> Option<&str> {
+		Some(&self.message)
+	}
+}
+
+impl From<fdo::Error> for MockError {
+	fn from(err: fdo::Error) -> Self {
+		MockError {
+			name: err.name().into(),
+			message: err.description().unwrap_or_default().to_owned(),
+		}
+	}
+}
+
+impl From<zbus::Error> for MockError {
+	fn from(err: zbus::Error) -> Self {
+		fdo::Error::from(err).into()
+	}
+}
+
+/// What a method does before what it is called for.
+#[derive(Clone, Default, Debug)]
+pub struct Fault {
+	/// How long it takes to answer.
+	pub delay: Duration,
+	/// What it fails with instead.
+	pub error: Option<MockError>,
+}
+
+impl Fault {
+	pub fn delay(delay: Duration) -> Self {
+		Fault { delay, error: None }
+	}
+
+	pub fn error(error: MockError) -> Self {
+		Fault {
+			error: Some(error),
+			..Fault::default()
+		}
+	}
+}
+
+/// The faults of the mock's methods, by method name; shared by all its
+/// objects, and changed from the test while they are served.
+#[derive(Clone, Default, Debug)]
+pub struct Faults(Arc<Mutex<HashMap<&'static str, Fault>>>);
+
+impl Faults {
+	pub fn set(&self, method: &'static str, fault: Fault) {
+		self.0.lock().unwrap().insert(method, fault);
+	}
+
+	pub fn clear(&self) {
+		self.0.lock().unwrap().clear();
+	}
+
+	/// Waits and fails the way the fault of `method` says, if any.
+	pub(super) async fn apply(&self, method: &str) -> Result<(), MockError> {
+		let Some(fault) = self.0.lock().unwrap().get(method).cloned() else {
+			return Ok(());
+		};
+		if !fault.delay.is_zero() {
+			async_io::Timer::after(fault.delay).await;
+		}
+		fault.error.map_or(Ok(()), Err)
+	}
+}
@@ -0,0 +1,1219 @@
+//! A mock iwd for the integration tests: one adapter with a station, a few
+//! networks and their known network profiles, served to a client over a
+//! peer-to-peer connection.
+
+#![allow(dead_code)]
+
+pub mod fault;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::os::unix::net::UnixListener;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_lock::RwLock;
+use iwd_playground::{IwdClient, IwdError};
+use zbus::message::Header;
+use zbus::object_server::SignalContext;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{fdo, Connection, ObjectServer};
+
+use fault::{Faults, MockError};
+
+pub const ADAPTER: &str = "/net/connman/iwd/0";
+pub const DEVICE: &str = "/net/connman/iwd/0/4";
+pub const P2P_DEVICE: &str = "/net/connman/iwd/0/5";
+/// Another device of the adapter, only there if a network is seen by it,
+/// see [`MockNetwork::on`], or it's a station, see
+/// [`MockIwd::second_station`].
+pub const SECOND_DEVICE: &str = "/net/connman/iwd/0/6";
+
+/// Runs `future` on the executor zbus was built for.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+	#[cfg(feature = "tokio")]
+	return tokio::runtime::Builder::new_multi_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(future);
+	#[cfg(not(feature = "tokio"))]
+	async_io::block_on(future)
+}
+
+fn object_path(path: String) -> OwnedObjectPath {
+	ObjectPath::try_from(path).unwrap().into()
+}
+
+/// [`DEVICE`], the station most tests talk to.
+pub fn device() -> OwnedObjectPath {
+	object_path(DEVICE.to_owned())
+}
+
+/// The way iwd names its objects: the SSID in hex, and the type.
+fn path_id(name: &str, type_: &str) -> String {
+	let hex: String = name.bytes().map(|b| format!("{b:02x}")).collect();
+	format!("{hex}_{type_}")
+}
+
+/// A network in range.
+#[derive(Clone, Debug)]
+pub struct MockNetwork {
+	pub name: String,
+	pub type_: &'static str,
+	/// In 1/100 dBm.
+	pub strength: i16,
+	/// Path of the device that sees it, [`DEVICE`] unless set.
+	pub device: &'static str,
+	/// Whether there's a known network profile for it.
+	pub known: bool,
+	/// Its access points, which only iwd 2 and later report.
+	pub bss: Vec<MockBss>,
+	/// In MHz, which only iwd 2 and later report.
+	pub frequency: Option<u32>,
+}
+
+/// An access point of a [`MockNetwork`].
+#[derive(Clone, Debug)]
+pub struct MockBss {
+	pub address: &'static str,
+	/// In MHz.
+	pub frequency: u32,
+	/// In dBm.
+	pub rssi: i16,
+}
+
+/// A Wi-Fi Direct peer, found once discovery is requested.
+#[derive(Clone, Debug)]
+pub struct MockPeer {
+	pub name: String,
+	pub address: &'static str,
+	pub category: &'static str,
+	/// In 1/100 dBm.
+	pub strength: i16,
+	/// What it takes to connect, the push button if `None`.
+	pub pin: Option<&'static str>,
+	pub connected: bool,
+	/// Its Wi-Fi Display capabilities, if it has any.
+	pub display: Option<MockDisplay>,
+}
+
+/// What a [`MockPeer`] says about Wi-Fi Display; iwd leaves out what the
+/// peer doesn't say.
+#[derive(Clone, Default, Debug)]
+pub struct MockDisplay {
+	pub source: Option<bool>,
+	pub sink: Option<bool>,
+	pub port: Option<u16>,
+}
+
+impl MockPeer {
+	pub fn new(
+		name: &str,
+		address: &'static str,
+		category: &'static str,
+	) -> Self {
+		MockPeer {
+			name: name.to_owned(),
+			address,
+			category,
+			strength: -6000,
+			pin: None,
+			connected: false,
+			display: None,
+		}
+	}
+
+	pub fn strength(mut self, strength: i16) -> Self {
+		self.strength = strength;
+		self
+	}
+
+	pub fn pin(mut self, pin: &'static str) -> Self {
+		self.pin = Some(pin);
+		self
+	}
+
+	pub fn connected(mut self) -> Self {
+		self.connected = true;
+		self
+	}
+
+	pub fn display(mut self, display: MockDisplay) -> Self {
+		self.display = Some(display);
+		self
+	}
+
+	pub fn path(&self) -> OwnedObjectPath {
+		let hex = self.address.replace(':', "");
+		object_path(format!("{P2P_DEVICE}/p2p_peers/{hex}"))
+	}
+}
+
+impl MockNetwork {
+	pub fn new(name: &str, type_: &'static str, strength: i16) -> Self {
+		MockNetwork {
+			name: name.to_owned(),
+			type_,
+			strength,
+			device: DEVICE,
+			known: false,
+			bss: Vec::new(),
+			frequency: None,
+		}
+	}
+
+	pub fn frequency(mut self, mhz: u32) -> Self {
+		self.frequency = Some(mhz);
+		self
+	}
+
+	pub fn bss(
+		mut self,
+		address: &'static str,
+		frequency: u32,
+		rssi: i16,
+	) -> Self {
+		self.bss.push(MockBss {
+			address,
+			frequency,
+			rssi,
+		});
+		self
+	}
+
+	pub fn bss_path(&self, address: &str) -> OwnedObjectPath {
+		let hex = address.replace(':', "");
+		object_path(format!("{}/{hex}", self.path()))
+	}
+
+	pub fn known(mut self) -> Self {
+		self.known = true;
+		self
+	}
+
+	/// Has it seen by the device at `device` instead, which is left out of
+	/// [`DEVICE`]'s `GetOrderedNetworks`.
+	pub fn on(mut self, device: &'static str) -> Self {
+		self.device = device;
+		self
+	}
+
+	pub fn path(&self) -> OwnedObjectPath {
+		let id = path_id(&self.name, self.type_);
+		object_path(format!("{}/{id}", self.device))
+	}
+
+	pub fn known_path(&self) -> OwnedObjectPath {
+		object_path(format!(
+			"/net/connman/iwd/{}",
+			path_id(&self.name, self.type_)
+		))
+	}
+}
+
+/// What the mock starts out with.
+#[derive(Clone, Debug)]
+pub struct MockIwd {
+	/// Best first, the way `GetOrderedNetworks` returns them.
+	pub networks: Vec<MockNetwork>,
+	/// Name of the network the station is connected to.
+	pub connected: Option<String>,
+	/// The adapter's; iwd leaves them out when the driver doesn't say.
+	pub model: Option<String>,
+	pub vendor: Option<String>,
+	/// The peers of the adapter's P2P device; no such device if `None`.
+	pub p2p: Option<Vec<MockPeer>>,
+	/// How long a scan takes; with zero, it's over before `Scan` returns.
+	pub scan_time: Duration,
+	/// How long a scan of [`SECOND_DEVICE`] takes, if it's a station too.
+	pub second_station: Option<Duration>,
+	/// The RSSI `GetDiagnostics` reports while connected, in dBm; without
+	/// it, there's no `StationDiagnostic`, which not every iwd offers.
+	pub rssi: Option<i16>,
+	/// Shared with the objects once served.
+	pub faults: Faults,
+}
+
+impl Default for MockIwd {
+	fn default() -> Self {
+		MockIwd {
+			networks: vec![
+				MockNetwork::new("home", "psk", -5400)
+					.known()
+					.bss("02:00:00:00:01:01", 5180, -54)
+					.bss("02:00:00:00:01:02", 2412, -61),
+				MockNetwork::new("café", "open", -7200).frequency(2437),
+				MockNetwork::new("work", "8021x", -8100).known().bss(
+					"02:00:00:00:02:01",
+					5955,
+					-81,
+				),
+			],
+			connected: Some("home".to_owned()),
+			model: Some("Mock Wireless".to_owned()),
+			vendor: Some("Mock Inc.".to_owned()),
+			p2p: None,
+			scan_time: Duration::from_millis(50),
+			second_station: None,
+			rssi: None,
+			faults: Faults::default(),
+		}
+	}
+}
+
+impl MockIwd {
+	pub fn network(&self, name: &str) -> &MockNetwork {
+		self.networks.iter().find(|net| net.name == name).unwrap()
+	}
+
+	/// The station of the device at `device` as it is when iwd starts,
+	/// without a signal level agent.
+	fn station(&self, device: &str) -> Station {
+		let connected = self
+			.connected
+			.as_deref()
+			.map(|name| self.network(name))
+			.filter(|net| net.device == device)
+			.map(MockNetwork::path);
+		Station {
+			state: match connected {
+				Some(_) => "connected",
+				None => "disconnected",
+			},
+			connected_network: connected,
+			disconnected_reason: None,
+			scanning: false,
+			last_scan: None,
+			finish_scan: None,
+			signal_agent: None,
+			scan_time: match device {
+				SECOND_DEVICE => self.second_station.unwrap_or_default(),
+				_ => self.scan_time,
+			},
+			ordered: self
+				.networks
+				.iter()
+				.filter(|net| net.device == device)
+				.map(|net| (net.path(), net.strength))
+				.collect(),
+			faults: self.faults.clone(),
+		}
+	}
+
+	/// Serves the objects on `conn`, with an object manager at `/` unless
+	/// there is one already.
+	pub async fn serve(&self, conn: &Connection) -> zbus::Result<()> {
+		let server = conn.object_server();
+		server.at("/", fdo::ObjectManager).await?;
+		let adapter = Adapter {
+			model: self.model.clone(),
+			vendor: self.vendor.clone(),
+		};
+		server.at(ADAPTER, adapter).await?;
+		let device = Device {
+			name: "wlan0",
+			address: "02:00:00:00:00:01",
+		};
+		server.at(DEVICE, device).await?;
+		if self.second_station.is_some()
+			|| self.networks.iter().any(|net| net.device == SECOND_DEVICE)
+		{
+			let device = Device {
+				name: "wlan1",
+				address: "02:00:00:00:00:02",
+			};
+			server.at(SECOND_DEVICE, device).await?;
+		}
+
+		let connected = self
+			.connected
+			.as_deref()
+			.map(|name| self.network(name).path());
+		server.at(DEVICE, self.station(DEVICE)).await?;
+		if self.second_station.is_some() {
+			server
+				.at(SECOND_DEVICE, self.station(SECOND_DEVICE))
+				.await?;
+		}
+		if let Some(rssi) = self.rssi {
+			for device in [DEVICE, SECOND_DEVICE] {
+				let diagnostic = StationDiagnostic {
+					rssi,
+					faults: self.faults.clone(),
+				};
+				server.at(device, diagnostic).await?;
+			}
+		}
+		if let Some(peers) = &self.p2p {
+			let device = P2pDevice {
+				peers: peers.clone(),
+				found: false,
+				discovering: false,
+				faults: self.faults.clone(),
+			};
+			server.at(P2P_DEVICE, device).await?;
+			// the others are only found by discovering
+			for peer in peers.iter().filter(|peer| peer.connected) {
+				serve_peer(&server, peer, &self.faults).await?;
+			}
+		}
+
+		for net in &self.networks {
+			let known_network = net.known.then(|| net.known_path());
+			let network = Network {
+				name: net.name.clone(),
+				type_: net.type_,
+				connected: connected.as_ref() == Some(&net.path()),
+				device: object_path(net.device.to_owned()),
+				known_network,
+				frequency: net.frequency,
+				faults: self.faults.clone(),
+			};
+			server.at(net.path(), network).await?;
+			for bss in &net.bss {
+				let path = net.bss_path(bss.address);
+				let bss = BasicServiceSet {
+					bss: bss.clone(),
+					network: net.path(),
+				};
+				server.at(path, bss).await?;
+			}
+			if net.known {
+				let known = KnownNetwork {
+					name: net.name.clone(),
+					type_: net.type_,
+					network: net.path(),
+					auto_connect: true,
+					auto_connect_writes: Vec::new(),
+					faults: self.faults.clone(),
+				};
+				server.at(net.known_path(), known).await?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Serves the mock on one end of a socket pair, and returns the
+	/// connections of both ends: the client's, then the mock's.
+	pub async fn connect(&self) -> zbus::Result<(Connection, Connection)> {
+		let (client, server) = pair().await?;
+		self.serve(&server).await?;
+		Ok((client, server))
+	}
+
+	/// Serves the mock to an [`IwdClient`], with handles on the mock.
+	pub async fn fixture(&self) -> Result<Fixture, IwdError> {
+		let (client, server) = self.connect().await?;
+		Ok(Fixture {
+			client: IwdClient::new(client).await?,
+			server,
+			iwd: self.clone(),
+		})
+	}
+
+	/// Waits on `listener` for one client that takes it for a bus, like the
+	/// binary does with `--bus=unix:path=...`, and serves the mock to it,
+	/// returning the mock's connection.
+	pub async fn listen(
+		&self,
+		listener: UnixListener,
+	) -> zbus::Result<Connection> {
+		#[cfg(not(feature = "tokio"))]
+		let stream = {
+			let listener = async_io::Async::new(listener)?;
+			listener.accept().await?.0.into_inner()?
+		};
+		#[cfg(feature = "tokio")]
+		let stream = {
+			listener.set_nonblocking(true)?;
+			let listener = tokio::net::UnixListener::from_std(listener)?;
+			listener.accept().await?.0
+		};
+
+		let served = Arc::new(RwLock::new(()));
+		let serving = served.write().await;
+		let bus = FakeBus {
+			served: served.clone(),
+		};
+		let server = zbus::connection::Builder::unix_stream(stream)
+			.server(zbus::Guid::generate())?
+			.p2p()
+			.serve_at("/org/freedesktop/DBus", bus)?
+			.build()
+			.await?;
+		// or the client drops the signals, as not from the name's owner;
+		// only now, as the object server would take calls to this name only
+		server.set_unique_name(IWD_UNIQUE_NAME)?;
+		self.serve(&server).await?;
+		drop(serving);
+		Ok(server)
+	}
+}
+
+/// A client and the mock it talks to.
+pub struct Fixture {
+	pub client: IwdClient,
+	/// The mock's end of the connection.
+	pub server: Connection,
+	/// What the mock started out with, and its faults.
+	pub iwd: MockIwd,
+}
+
+impl Fixture {
+	pub fn faults(&self) -> &Faults {
+		&self.iwd.faults
+	}
+
+	/// Whether discovery was requested on the P2P device and not released.
+	pub async fn is_discovering(&self) -> zbus::Result<bool> {
+		let device = self
+			.server
+			.object_server()
+			.interface::<_, P2pDevice>(P2P_DEVICE)
+			.await?;
+		let discovering = device.get().await.discovering;
+		Ok(discovering)
+	}
+
+	/// Connects the station to the network called `name`, or disconnects
+	/// it, the way iwd would on its own, e.g. roaming.
+	pub async fn set_connected(&self, name: Option<&str>) -> fdo::Result<()> {
+		let server = self.server.object_server();
+		let path = name.map(|name| self.iwd.network(name).path());
+		let station = server.interface::<_, Station>(DEVICE).await?;
+		station
+			.get_mut()
+			.await
+			.set_connected(&server, station.signal_context(), path.clone())
+			.await?;
+		if let Some(path) = path {
+			let network = server.interface::<_, Network>(path).await?;
+			network.get_mut().await.connected = true;
+			let guard = network.get().await;
+			guard.connected_changed(network.signal_context()).await?;
+		}
+		Ok(())
+	}
+
+	/// What `AutoConnect` of the known network called `name` was set to
+	/// through `Properties.Set`, in order.
+	pub async fn auto_connect_writes(
+		&self,
+		name: &str,
+	) -> zbus::Result<Vec<bool>> {
+		let path = self.iwd.network(name).known_path();
+		let known = self
+			.server
+			.object_server()
+			.interface::<_, KnownNetwork>(path)
+			.await?;
+		let writes = known.get().await.auto_connect_writes.clone();
+		Ok(writes)
+	}
+
+	/// The thresholds the signal level agent registered with, if there is
+	/// one.
+	pub async fn signal_levels(&self) -> zbus::Result<Option<Vec<i16>>> {
+		let station = self
+			.server
+			.object_server()
+			.interface::<_, Station>(DEVICE)
+			.await?;
+		let agent = station.get().await.signal_agent.clone();
+		Ok(agent.map(|(_, levels)| levels))
+	}
+
+	/// Tells the signal level agent the RSSI is now in the range at `level`,
+	/// the way iwd does.
+	pub async fn signal_level_changed(&self, level: u8) -> zbus::Result<()> {
+		let station = self
+			.server
+			.object_server()
+			.interface::<_, Station>(DEVICE)
+			.await?;
+		let agent = station.get().await.signal_agent.clone();
+		let Some((path, _)) = agent else {
+			return Err(zbus::Error::Failure("no agent".to_owned()));
+		};
+		let device = ObjectPath::from_static_str_unchecked(DEVICE);
+		self.server
+			.call_method(
+				None::<zbus::names::BusName>,
+				path,
+				Some("net.connman.iwd.SignalLevelAgent"),
+				"Changed",
+				&(device, level),
+			)
+			.await?;
+		Ok(())
+	}
+
+	/// Replaces the station with a fresh one, which has forgotten the
+	/// signal level agent, the way it is after iwd restarted.
+	pub async fn restart(&self) -> zbus::Result<()> {
+		let server = self.server.object_server();
+		server.remove::<Station, _>(DEVICE).await?;
+		server.at(DEVICE, self.iwd.station(DEVICE)).await?;
+		Ok(())
+	}
+
+	/// Closes both ends, stopping a scan that's still running first; with
+	/// it gone, nothing holds on to the mock.
+	pub async fn close(self) -> zbus::Result<()> {
+		let station = self
+			.server
+			.object_server()
+			.interface::<_, Station>(DEVICE)
+			.await?;
+		drop(station.get_mut().await.finish_scan.take());
+		self.client.connection().clone().close().await?;
+		self.server.close().await
+	}
+}
+
+/// The connections of both ends of a socket pair, the client's, then the
+/// server's, with nothing served yet but an object manager at `/`.
+pub async fn pair() -> zbus::Result<(Connection, Connection)> {
+	#[cfg(not(feature = "tokio"))]
+	let (client, server) = std::os::unix::net::UnixStream::pair()?;
+	#[cfg(feature = "tokio")]
+	let (client, server) = tokio::net::UnixStream::pair()?;
+
+	// serving something already has the object server take calls as soon
+	// as the connection is up, rather than drop the first ones
+	let server = zbus::connection::Builder::unix_stream(server)
+		.server(zbus::Guid::generate())?
+		.p2p()
+		.serve_at("/", fdo::ObjectManager)?
+		.build();
+	let client = zbus::connection::Builder::unix_stream(client).p2p().build();
+	let (server, client) =
+		futures_lite::future::try_zip(server, client).await?;
+	Ok((client, server))
+}
+
+const IWD_UNIQUE_NAME: &str = ":1.0";
+
+/// Just enough of the bus for a client connecting to one: iwd is the only
+/// other peer, and always there.
+struct FakeBus {
+	/// Held for writing until the mock is served.
+	served: Arc<RwLock<()>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.DBus")]
+impl FakeBus {
+	/// Only answers once the mock is served, so the client can't call it
+	/// any sooner.
+	async fn hello(&self) -> &str {
+		let _served = self.served.read().await;
+		":1.1"
+	}
+
+	fn add_match(&self, _rule: &str) {}
+
+	fn remove_match(&self, _rule: &str) {}
+
+	fn name_has_owner(&self, _name: &str) -> bool {
+		true
+	}
+
+	fn get_name_owner(&self, _name: &str) -> &str {
+		IWD_UNIQUE_NAME
+	}
+}
+
+struct Adapter {
+	model: Option<String>,
+	vendor: Option<String>,
+}
+
+#[zbus::interface(name = "net.connman.iwd.Adapter")]
+impl Adapter {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		"phy0"
+	}
+
+	#[zbus(property)]
+	fn powered(&self) -> bool {
+		true
+	}
+
+	#[zbus(property)]
+	fn model(&self) -> fdo::Result<String> {
+		self.model.clone().ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn vendor(&self) -> fdo::Result<String> {
+		self.vendor.clone().ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn supported_modes(&self) -> Vec<String> {
+		vec!["station".to_owned(), "ap".to_owned()]
+	}
+}
+
+struct Device {
+	name: &'static str,
+	address: &'static str,
+}
+
+#[zbus::interface(name = "net.connman.iwd.Device")]
+impl Device {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		self.name
+	}
+
+	#[zbus(property)]
+	fn address(&self) -> &str {
+		self.address
+	}
+
+	#[zbus(property)]
+	fn powered(&self) -> bool {
+		true
+	}
+
+	#[zbus(property)]
+	fn adapter(&self) -> OwnedObjectPath {
+		object_path(ADAPTER.to_owned())
+	}
+
+	#[zbus(property)]
+	fn mode(&self) -> &str {
+		"station"
+	}
+}
+
+/// iwd leaves out the properties it has no value for, which the getters
+/// do by failing.
+fn absent() -> fdo::Error {
+	fdo::Error::UnknownProperty("not set".to_owned())
+}
+
+struct Station {
+	state: &'static str,
+	connected_network: Option<OwnedObjectPath>,
+	/// Why it was disconnected, while it is: `local` once told to.
+	disconnected_reason: Option<&'static str>,
+	scanning: bool,
+	/// When the last scan finished, in seconds since the Unix epoch.
+	last_scan: Option<u64>,
+	/// Ends the scan in progress, if any; cancelled when dropped.
+	finish_scan: Option<zbus::Task<zbus::Result<()>>>,
+	scan_time: Duration,
+	ordered: Vec<(OwnedObjectPath, i16)>,
+	/// The path of the registered signal level agent, and its thresholds.
+	signal_agent: Option<(OwnedObjectPath, Vec<i16>)>,
+	faults: Faults,
+}
+
+impl Station {
+	async fn scan_finished(
+		&mut self,
+		ctxt: &SignalContext<'_>,
+	) -> zbus::Result<()> {
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+		self.last_scan = Some(now.unwrap().as_secs());
+		self.scanning = false;
+		self.last_scan_changed(ctxt).await?;
+		self.scanning_changed(ctxt).await
+	}
+
+	/// Points the station at `network`, or nowhere, and marks the network
+	/// it was connected to before as disconnected. Marking `network` is up
+	/// to the caller, which may be the network itself.
+	async fn set_connected(
+		&mut self,
+		server: &ObjectServer,
+		ctxt: &SignalContext<'_>,
+		network: Option<OwnedObjectPath>,
+	) -> fdo::Result<()> {
+		let previous = self.connected_network.take();
+		if let Some(path) = previous.filter(|p| Some(p) != network.as_ref()) {
+			let iface = server.interface::<_, Network>(&path).await?;
+			iface.get_mut().await.connected = false;
+			let guard = iface.get().await;
+			guard.connected_changed(iface.signal_context()).await?;
+		}
+
+		self.state = match network {
+			Some(_) => "connected",
+			None => "disconnected",
+		};
+		if network.is_some() && self.disconnected_reason.take().is_some() {
+			self.disconnected_reason_invalidate(ctxt).await?;
+		}
+		self.connected_network = network;
+		self.state_changed(ctxt).await?;
+		match self.connected_network {
+			Some(_) => self.connected_network_changed(ctxt).await?,
+			None => self.connected_network_invalidate(ctxt).await?,
+		}
+		Ok(())
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.Station")]
+impl Station {
+	#[zbus(property)]
+	fn state(&self) -> &str {
+		self.state
+	}
+
+	#[zbus(property)]
+	fn connected_network(&self) -> fdo::Result<OwnedObjectPath> {
+		self.connected_network.clone().ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn disconnected_reason(&self) -> fdo::Result<&str> {
+		self.disconnected_reason.ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn scanning(&self) -> bool {
+		self.scanning
+	}
+
+	#[zbus(property)]
+	fn last_scan(&self) -> fdo::Result<u64> {
+		self.last_scan.ok_or_else(absent)
+	}
+
+	/// Finishes shortly after, finding nothing new.
+	async fn scan(
+		&mut self,
+		#[zbus(connection)] conn: &Connection,
+		#[zbus(signal_context)] ctxt: SignalContext<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Scan").await?;
+		if self.scanning {
+			return Err(MockError::iwd("Busy", "already scanning"));
+		}
+		self.scanning = true;
+		self.scanning_changed(&ctxt).await?;
+		if self.scan_time.is_zero() {
+			return Ok(self.scan_finished(&ctxt).await?);
+		}
+
+		// apart from the first signal, or the client only sees the result
+		let server = conn.clone();
+		let scan_time = self.scan_time;
+		let path = ctxt.path().to_owned();
+		let finish = async move {
+			async_io::Timer::after(scan_time).await;
+			let station =
+				server.object_server().interface::<_, Station>(path).await?;
+			let mut guard = station.get_mut().await;
+			guard.scan_finished(station.signal_context()).await
+		};
+		self.finish_scan = Some(conn.executor().spawn(finish, "scan"));
+		Ok(())
+	}
+
+	async fn get_ordered_networks(
+		&self,
+	) -> Result<Vec<(OwnedObjectPath, i16)>, MockError> {
+		self.faults.apply("GetOrderedNetworks").await?;
+		Ok(self.ordered.clone())
+	}
+
+	async fn get_hidden_access_points(
+		&self,
+	) -> Result<Vec<(String, i16, String)>, MockError> {
+		self.faults.apply("GetHiddenAccessPoints").await?;
+		Ok(Vec::new())
+	}
+
+	async fn disconnect(
+		&mut self,
+		#[zbus(object_server)] server: &ObjectServer,
+		#[zbus(signal_context)] ctxt: SignalContext<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Disconnect").await?;
+		if self.connected_network.is_none() {
+			return Err(MockError::iwd("NotConnected", "not connected"));
+		}
+		self.disconnected_reason = Some("local");
+		self.disconnected_reason_changed(&ctxt).await?;
+		Ok(self.set_connected(server, &ctxt, None).await?)
+	}
+
+	fn register_signal_level_agent(
+		&mut self,
+		path: OwnedObjectPath,
+		levels: Vec<i16>,
+	) -> Result<(), MockError> {
+		if self.signal_agent.is_some() {
+			return Err(MockError::iwd("AlreadyExists", "agent registered"));
+		}
+		if levels.is_empty() || !levels.windows(2).all(|w| w[0] > w[1]) {
+			return Err(MockError::iwd("InvalidArguments", "bad levels"));
+		}
+		self.signal_agent = Some((path, levels));
+		Ok(())
+	}
+
+	fn unregister_signal_level_agent(
+		&mut self,
+		path: OwnedObjectPath,
+	) -> Result<(), MockError> {
+		match self.signal_agent.take_if(|(agent, _)| *agent == path) {
+			Some(_) => Ok(()),
+			None => Err(MockError::iwd("NotFound", "no such agent")),
+		}
+	}
+}
+
+/// The `StationDiagnostic` of a station, which only answers while it's
+/// connected.
+struct StationDiagnostic {
+	/// In dBm.
+	rssi: i16,
+	faults: Faults,
+}
+
+#[zbus::interface(name = "net.connman.iwd.StationDiagnostic")]
+impl StationDiagnostic {
+	async fn get_diagnostics(
+		&self,
+		#[zbus(object_server)] server: &ObjectServer,
+		#[zbus(header)] header: Header<'_>,
+	) -> Result<HashMap<String, OwnedValue>, MockError> {
+		self.faults.apply("GetDiagnostics").await?;
+		let path = header.path().unwrap();
+		let station = server.interface::<_, Station>(path).await?;
+		if station.get().await.connected_network.is_none() {
+			return Err(MockError::iwd("NotConnected", "not connected"));
+		}
+		Ok(HashMap::from([("RSSI".to_owned(), self.rssi.into())]))
+	}
+}
+
+struct Network {
+	name: String,
+	type_: &'static str,
+	connected: bool,
+	device: OwnedObjectPath,
+	known_network: Option<OwnedObjectPath>,
+	frequency: Option<u32>,
+	faults: Faults,
+}
+
+#[zbus::interface(name = "net.connman.iwd.Network")]
+impl Network {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	#[zbus(property, name = "Type")]
+	fn type_(&self) -> &str {
+		self.type_
+	}
+
+	#[zbus(property)]
+	fn connected(&self) -> bool {
+		self.connected
+	}
+
+	#[zbus(property)]
+	fn device(&self) -> OwnedObjectPath {
+		self.device.clone()
+	}
+
+	#[zbus(property)]
+	fn known_network(&self) -> fdo::Result<OwnedObjectPath> {
+		self.known_network.clone().ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn frequency(&self) -> fdo::Result<u32> {
+		self.frequency.ok_or_else(absent)
+	}
+
+	/// Connects without asking an agent for anything.
+	async fn connect(
+		&mut self,
+		#[zbus(object_server)] server: &ObjectServer,
+		#[zbus(signal_context)] ctxt: SignalContext<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Connect").await?;
+		let path = ctxt.path().to_owned().into();
+		let station = server.interface::<_, Station>(DEVICE).await?;
+		station
+			.get_mut()
+			.await
+			.set_connected(server, station.signal_context(), Some(path))
+			.await?;
+		self.connected = true;
+		self.connected_changed(&ctxt).await?;
+		Ok(())
+	}
+}
+
+struct BasicServiceSet {
+	bss: MockBss,
+	network: OwnedObjectPath,
+}
+
+#[zbus::interface(name = "net.connman.iwd.BasicServiceSet")]
+impl BasicServiceSet {
+	#[zbus(property)]
+	fn address(&self) -> &str {
+		self.bss.address
+	}
+
+	#[zbus(property)]
+	fn frequency(&self) -> u32 {
+		self.bss.frequency
+	}
+
+	#[zbus(property, name = "RSSI")]
+	fn rssi(&self) -> i16 {
+		self.bss.rssi
+	}
+
+	#[zbus(property)]
+	fn network(&self) -> OwnedObjectPath {
+		self.network.clone()
+	}
+}
+
+struct KnownNetwork {
+	name: String,
+	type_: &'static str,
+	/// The network this is the profile of.
+	network: OwnedObjectPath,
+	auto_connect: bool,
+	/// Every value `AutoConnect` was set to through `Properties.Set`.
+	auto_connect_writes: Vec<bool>,
+	faults: Faults,
+}
+
+#[zbus::interface(name = "net.connman.iwd.KnownNetwork")]
+impl KnownNetwork {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	#[zbus(property, name = "Type")]
+	fn type_(&self) -> &str {
+		self.type_
+	}
+
+	#[zbus(property)]
+	fn hidden(&self) -> bool {
+		false
+	}
+
+	#[zbus(property)]
+	fn last_connected_time(&self) -> &str {
+		"2024-03-01T10:00:00Z"
+	}
+
+	#[zbus(property)]
+	fn auto_connect(&self) -> bool {
+		self.auto_connect
+	}
+
+	#[zbus(property)]
+	fn set_auto_connect(&mut self, auto_connect: bool) {
+		self.auto_connect_writes.push(auto_connect);
+		self.auto_connect = auto_connect;
+	}
+
+	/// Removes the profile, and with it the network's link to it.
+	async fn forget(
+		&self,
+		#[zbus(object_server)] server: &ObjectServer,
+		#[zbus(header)] header: Header<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Forget").await?;
+		let network = server.interface::<_, Network>(&self.network).await?;
+		network.get_mut().await.known_network = None;
+		let guard = network.get().await;
+		guard
+			.known_network_invalidate(network.signal_context())
+			.await?;
+		drop(guard);
+		server
+			.remove::<KnownNetwork, _>(header.path().unwrap())
+			.await?;
+		Ok(())
+	}
+}
+
+struct P2pDevice {
+	/// Served once discovery is requested, and kept after.
+	peers: Vec<MockPeer>,
+	found: bool,
+	discovering: bool,
+	faults: Faults,
+}
+
+#[zbus::interface(name = "net.connman.iwd.p2p.Device")]
+impl P2pDevice {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		"mock-p2p"
+	}
+
+	#[zbus(property)]
+	fn enabled(&self) -> bool {
+		true
+	}
+
+	fn get_peers(&self) -> Vec<(OwnedObjectPath, i16)> {
+		if !self.found {
+			return Vec::new();
+		}
+		let peers = self.peers.iter();
+		peers.map(|peer| (peer.path(), peer.strength)).collect()
+	}
+
+	/// Serves the peers, as if they were all found at once.
+	async fn request_discovery(
+		&mut self,
+		#[zbus(object_server)] server: &ObjectServer,
+	) -> Result<(), MockError> {
+		self.faults.apply("RequestDiscovery").await?;
+		self.discovering = true;
+		self.found = true;
+		for peer in &self.peers {
+			serve_peer(server, peer, &self.faults).await?;
+		}
+		Ok(())
+	}
+
+	async fn release_discovery(&mut self) -> Result<(), MockError> {
+		self.faults.apply("ReleaseDiscovery").await?;
+		self.discovering = false;
+		Ok(())
+	}
+}
+
+/// Serves `peer`, and its display capabilities if it has any.
+async fn serve_peer(
+	server: &ObjectServer,
+	peer: &MockPeer,
+	faults: &Faults,
+) -> zbus::Result<()> {
+	server.at(peer.path(), P2pPeer::new(peer, faults)).await?;
+	if let Some(display) = &peer.display {
+		server.at(peer.path(), P2pDisplay(display.clone())).await?;
+	}
+	Ok(())
+}
+
+struct P2pPeer {
+	peer: MockPeer,
+	device: OwnedObjectPath,
+	faults: Faults,
+}
+
+impl P2pPeer {
+	fn new(peer: &MockPeer, faults: &Faults) -> Self {
+		P2pPeer {
+			peer: peer.clone(),
+			device: object_path(P2P_DEVICE.to_owned()),
+			faults: faults.clone(),
+		}
+	}
+}
+
+#[zbus::interface(name = "net.connman.iwd.p2p.Peer")]
+impl P2pPeer {
+	#[zbus(property)]
+	fn name(&self) -> &str {
+		&self.peer.name
+	}
+
+	#[zbus(property)]
+	fn device_category(&self) -> &str {
+		self.peer.category
+	}
+
+	#[zbus(property)]
+	fn device(&self) -> OwnedObjectPath {
+		self.device.clone()
+	}
+
+	#[zbus(property)]
+	fn connected(&self) -> bool {
+		self.peer.connected
+	}
+
+	#[zbus(property)]
+	fn connected_interface(&self) -> fdo::Result<&str> {
+		self.peer
+			.connected
+			.then_some("p2p-wlan0-0")
+			.ok_or_else(absent)
+	}
+
+	#[zbus(property, name = "ConnectedIP")]
+	fn connected_ip(&self) -> fdo::Result<&str> {
+		self.peer
+			.connected
+			.then_some("192.168.49.2")
+			.ok_or_else(absent)
+	}
+
+	/// Connects if `pin` is the peer's PIN, or it takes the push button.
+	async fn connect(
+		&mut self,
+		method: &str,
+		pin: &str,
+		#[zbus(signal_context)] ctxt: SignalContext<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Connect").await?;
+		let accepted = match self.peer.pin {
+			Some(expected) => method == "pin" && pin == expected,
+			None => method == "pushbutton",
+		};
+		if !accepted {
+			return Err(MockError::iwd("Failed", "Operation failed"));
+		}
+		self.peer.connected = true;
+		self.connected_changed(&ctxt).await?;
+		Ok(())
+	}
+
+	async fn disconnect(
+		&mut self,
+		#[zbus(signal_context)] ctxt: SignalContext<'_>,
+	) -> Result<(), MockError> {
+		self.faults.apply("Disconnect").await?;
+		self.peer.connected = false;
+		self.connected_changed(&ctxt).await?;
+		Ok(())
+	}
+}
+
+struct P2pDisplay(MockDisplay);
+
+#[zbus::interface(name = "net.connman.iwd.p2p.Display")]
+impl P2pDisplay {
+	#[zbus(property)]
+	fn source(&self) -> fdo::Result<bool> {
+		self.0.source.ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn sink(&self) -> fdo::Result<bool> {
+		self.0.sink.ok_or_else(absent)
+	}
+
+	#[zbus(property)]
+	fn port(&self) -> fdo::Result<u16> {
+		self.0.port.ok_or_else(absent)
+	}
+}
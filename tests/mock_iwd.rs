@@ -0,0 +1,244 @@
+//! Runs the binary against the mock, which it takes for iwd on a bus of its
+//! own.
+
+mod mock;
+
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use mock::fault::Fault;
+use mock::{
+	block_on, MockDisplay, MockIwd, MockNetwork, MockPeer, SECOND_DEVICE,
+};
+
+fn socket_path(name: &str) -> PathBuf {
+	let pid = std::process::id();
+	std::env::temp_dir().join(format!("iwd-playground-{pid}-{name}.sock"))
+}
+
+/// Runs the binary with `args` against `iwd`, until it exits.
+fn run(iwd: &MockIwd, name: &str, args: &[&str]) -> Output {
+	let path = socket_path(name);
+	let _ = std::fs::remove_file(&path);
+	let listener = UnixListener::bind(&path).unwrap();
+
+	block_on(async {
+		let server = iwd.listen(listener);
+		let (tx, rx) = async_channel::bounded(1);
+		let bus = format!("--bus=unix:path={}", path.display());
+		let args: Vec<String> = args.iter().map(|&arg| arg.into()).collect();
+		std::thread::spawn(move || {
+			let output = Command::new(env!("CARGO_BIN_EXE_iwd-get-networks"))
+				.args(&args)
+				.arg(bus)
+				.stdin(Stdio::null())
+				.output();
+			let _ = tx.send_blocking(output);
+		});
+
+		let _server = server.await.unwrap();
+		let output = rx.recv().await.unwrap().unwrap();
+		let _ = std::fs::remove_file(&path);
+		output
+	})
+}
+
+#[test]
+fn lists_the_ssids() {
+	let output = run(&MockIwd::default(), "list", &["list", "--porcelain"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let ssids: Vec<_> = stdout
+		.lines()
+		.map(|line| line.split('\t').next().unwrap())
+		.collect();
+	assert_eq!(ssids, ["home", "café", "work"]);
+}
+
+/// Both stations scan at once, and are listed by name however their scans
+/// finish.
+#[test]
+fn lists_every_station_in_order() {
+	const DELAY: Duration = Duration::from_millis(600);
+
+	let iwd = MockIwd {
+		networks: vec![
+			MockNetwork::new("home", "psk", -5400),
+			MockNetwork::new("upstairs", "psk", -6000).on(SECOND_DEVICE),
+		],
+		// so that wlan1 is done first
+		scan_time: DELAY / 2,
+		second_station: Some(Duration::ZERO),
+		..MockIwd::default()
+	};
+	iwd.faults.set("Scan", Fault::delay(DELAY));
+
+	let start = Instant::now();
+	let output = run(&iwd, "list-stations", &["list", "--porcelain"]);
+	let elapsed = start.elapsed();
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let rows: Vec<Vec<_>> = stdout
+		.lines()
+		.map(|line| line.split('\t').collect())
+		.collect();
+	let rows: Vec<_> = rows.iter().map(|row| (row[0], row[5])).collect();
+	assert_eq!(rows, [("home", "wlan0"), ("upstairs", "wlan1")]);
+	assert!(elapsed < 2 * DELAY, "took {elapsed:?}");
+}
+
+/// The networks and the diagnostics are asked for at once, and the
+/// connected network gets the diagnostics' RSSI.
+#[test]
+fn bar_asks_for_diagnostics_alongside() {
+	const DELAY: Duration = Duration::from_millis(600);
+
+	let iwd = MockIwd {
+		rssi: Some(-50),
+		..MockIwd::default()
+	};
+	iwd.faults.set("GetOrderedNetworks", Fault::delay(DELAY));
+	iwd.faults.set("GetDiagnostics", Fault::delay(DELAY));
+
+	let start = Instant::now();
+	let output = run(&iwd, "bar-diagnostics", &["bar"]);
+	let elapsed = start.elapsed();
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert_eq!(stdout, "home (-50 dBm)\n");
+	assert!(elapsed >= DELAY);
+	assert!(elapsed < 2 * DELAY, "took {elapsed:?}");
+}
+
+#[test]
+fn forgets_by_ssid() {
+	let iwd = MockIwd::default();
+	let output = run(&iwd, "forget", &["forget", "work"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+
+	let output = run(&iwd, "forget-unknown", &["forget", "café"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("no known network called"), "{stderr}");
+}
+
+#[test]
+fn lists_access_points() {
+	let mut iwd = MockIwd::default();
+	let output = run(&iwd, "bss", &["bss", "home", "--porcelain"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let fields: Vec<Vec<_>> = stdout
+		.lines()
+		.map(|line| line.split('\t').take(5).collect())
+		.collect();
+	assert_eq!(
+		fields,
+		[
+			["02:00:00:00:01:01", "psk", "5", "5180", "-54"],
+			["02:00:00:00:01:02", "psk", "2.4", "2412", "-61"],
+		]
+	);
+
+	for net in &mut iwd.networks {
+		net.bss.clear();
+	}
+	let output = run(&iwd, "bss-old-iwd", &["bss", "home"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(!output.status.success());
+	assert!(stderr.contains("before iwd 2"), "{stderr}");
+}
+
+#[test]
+fn discovers_peers() {
+	let sink = MockDisplay {
+		sink: Some(true),
+		..MockDisplay::default()
+	};
+	let mut iwd = MockIwd {
+		p2p: Some(vec![
+			MockPeer::new("living room", "02:00:00:00:05:01", "display")
+				.display(sink),
+			MockPeer::new("laptop", "02:00:00:00:05:02", "computer"),
+		]),
+		..MockIwd::default()
+	};
+	let args = ["peers", "--discover=0.2", "--porcelain"];
+	let output = run(&iwd, "peers", &args);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert_eq!(
+		stdout,
+		"laptop\tcomputer\tfalse\t\t\
+		 /net/connman/iwd/0/5/p2p_peers/020000000502\n\
+		 living room\tdisplay\tfalse\tsink\t\
+		 /net/connman/iwd/0/5/p2p_peers/020000000501\n"
+	);
+
+	let output = run(&iwd, "peers-table", &["peers", "--discover=0.2"]);
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert!(stdout.contains("WFD sink"), "{stdout}");
+
+	iwd.p2p = None;
+	let output = run(&iwd, "peers-unsupported", &["peers"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(!output.status.success());
+	assert!(stderr.contains("not supported by this adapter"), "{stderr}");
+}
+
+#[test]
+fn connects_to_the_strongest_peer_of_a_name() {
+	let weak = MockPeer::new("tv", "02:00:00:00:05:01", "display");
+	let strong = MockPeer::new("tv", "02:00:00:00:05:02", "display")
+		.strength(-4000)
+		.pin("12345670");
+	let iwd = MockIwd {
+		p2p: Some(vec![weak.strength(-7000), strong.clone()]),
+		..MockIwd::default()
+	};
+	let args = ["peer-connect", "tv", "--pin=12345670", "--porcelain"];
+	let output = run(&iwd, "peer-connect", &args);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let path = strong.path();
+	assert_eq!(stdout, format!("tv\tp2p-wlan0-0\t192.168.49.2\t{path}\n"));
+
+	let args = ["peer-connect", "tv", "--pin=87654321"];
+	let output = run(&iwd, "peer-connect-wrong-pin", &args);
+	assert_eq!(output.status.code(), Some(5));
+
+	let args = ["peer-connect", "radio", "--discover=0.2"];
+	let output = run(&iwd, "peer-connect-unknown", &args);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert_eq!(output.status.code(), Some(4));
+	assert!(stderr.contains("no peer called \"radio\""), "{stderr}");
+}
+
+#[test]
+fn disconnects_from_connected_peers() {
+	let iwd = MockIwd {
+		p2p: Some(vec![
+			MockPeer::new("tv", "02:00:00:00:05:01", "display").connected(),
+			MockPeer::new("laptop", "02:00:00:00:05:02", "computer"),
+		]),
+		..MockIwd::default()
+	};
+	let output = run(&iwd, "peer-disconnect", &["peer-disconnect", "tv"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "{stderr}");
+
+	let args = ["peer-disconnect", "laptop"];
+	let output = run(&iwd, "peer-disconnect-unconnected", &args);
+	assert_eq!(output.status.code(), Some(4));
+}
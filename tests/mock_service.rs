@@ -0,0 +1,513 @@
+//! The client end to end against [`mock::MockIwd`].
+
+mod mock;
+
+use std::time::{Duration, Instant};
+
+use futures_lite::StreamExt;
+use iwd_playground::client::{
+	best_known, enumerate_devices, find_known_network, join_all,
+	needs_fresh_scan, scan_and_collect, scan_and_wait, scan_results_age,
+	visible_networks,
+};
+use iwd_playground::objects::IwdObjectMap;
+use iwd_playground::opath::OPath;
+use iwd_playground::proxy::{KnownNetworkProxy, StationProxy};
+use iwd_playground::types::{
+	KnownNetwork, NetworkType, Ssid, Station, StationState, WifiBand,
+};
+use iwd_playground::{IwdClient, IwdError};
+use zbus::zvariant::OwnedObjectPath;
+
+use mock::fault::{Fault, MockError};
+use mock::{block_on, device, MockIwd, MockNetwork, DEVICE, SECOND_DEVICE};
+
+#[test]
+fn lists_networks_in_order() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+		let station = client.station(&device()).await.unwrap();
+
+		let rows = visible_networks(client, &objects, &device(), &station)
+			.await
+			.unwrap();
+		let summary: Vec<_> = rows
+			.iter()
+			.map(|row| {
+				(
+					row.ssid().unwrap().to_string(),
+					row.network_type().unwrap(),
+					row.is_connected(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("home".to_owned(), NetworkType::Psk, true),
+				("café".to_owned(), NetworkType::Open, false),
+				("work".to_owned(), NetworkType::Eap, false),
+			]
+		);
+		assert!(
+			rows[0].is_known() && !rows[1].is_known() && rows[2].is_known()
+		);
+	});
+}
+
+#[test]
+fn networks_are_split_by_device() {
+	let mut iwd = MockIwd::default();
+	iwd.networks.extend([
+		MockNetwork::new("upstairs", "psk", -6000).on(SECOND_DEVICE),
+		MockNetwork::new("café", "open", -6500).on(SECOND_DEVICE),
+	]);
+	block_on(async {
+		let fixture = iwd.fixture().await.unwrap();
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+
+		let names = |device: &str| {
+			let device = OwnedObjectPath::try_from(device).unwrap();
+			let mut names: Vec<_> = objects
+				.networks_for_device(&device)
+				.map(|(path, net)| {
+					assert!(path.starts_with(device.as_str()), "{path}");
+					net.name().to_string()
+				})
+				.collect();
+			names.sort();
+			names
+		};
+		assert_eq!(names(DEVICE), ["café", "home", "work"]);
+		assert_eq!(names(SECOND_DEVICE), ["café", "upstairs"]);
+		assert!(names("/net/connman/iwd/0/7").is_empty());
+	});
+}
+
+#[test]
+fn signal_level_agent_maps_levels_to_thresholds() {
+	use std::sync::{Arc, Mutex};
+
+	use iwd_playground::agent::SignalLevelAgent;
+	use zbus::zvariant::ObjectPath;
+
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let station = fixture.client.station(&device()).await.unwrap();
+		let seen = Arc::new(Mutex::new(Vec::new()));
+		let agent = SignalLevelAgent::new(vec![-70, -50, -60, -60], {
+			let seen = seen.clone();
+			move |path, threshold| {
+				seen.lock().unwrap().push((path, threshold));
+			}
+		});
+		let path = ObjectPath::from_static_str_unchecked("/test/agent");
+		agent
+			.register(&fixture.client, &station, path)
+			.await
+			.unwrap();
+
+		// in descending order, without duplicates
+		let levels = fixture.signal_levels().await.unwrap();
+		assert_eq!(levels.as_deref(), Some(&[-50, -60, -70][..]));
+
+		for level in [0, 1, 2, 3, 200] {
+			fixture.signal_level_changed(level).await.unwrap();
+		}
+		let seen = seen.lock().unwrap();
+		assert!(seen.iter().all(|(path, _)| *path == device()));
+		let thresholds: Vec<_> = seen.iter().map(|(_, t)| *t).collect();
+		assert_eq!(thresholds, [-50, -60, -70, i16::MIN, i16::MIN]);
+	});
+}
+
+#[test]
+fn signal_level_agent_is_served_again_after_a_restart() {
+	use std::sync::{Arc, Mutex};
+
+	use iwd_playground::agent::SignalLevelAgent;
+	use zbus::zvariant::ObjectPath;
+
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let station = client.station(&device()).await.unwrap();
+		let seen = Arc::new(Mutex::new(Vec::new()));
+		let agent = |name: &'static str| {
+			let seen = seen.clone();
+			SignalLevelAgent::new(vec![-60], move |_, threshold| {
+				seen.lock().unwrap().push((name, threshold));
+			})
+		};
+		let path = ObjectPath::from_static_str_unchecked("/test/agent");
+		agent("first")
+			.register(client, &station, path.clone())
+			.await
+			.unwrap();
+
+		fixture.restart().await.unwrap();
+		assert_eq!(fixture.signal_levels().await.unwrap(), None);
+		// the first one is still served
+		let err = agent("second")
+			.register(client, &station, path.clone())
+			.await
+			.unwrap_err();
+		assert!(matches!(err, IwdError::PathInUse(_)), "{err:?}");
+
+		// which the restarted iwd doesn't know of
+		SignalLevelAgent::unregister(client, &station, path.clone())
+			.await
+			.unwrap_err();
+		agent("second")
+			.register(client, &station, path)
+			.await
+			.unwrap();
+		fixture.signal_level_changed(0).await.unwrap();
+		fixture.signal_level_changed(1).await.unwrap();
+		let seen = seen.lock().unwrap();
+		assert_eq!(*seen, [("second", -60), ("second", i16::MIN)]);
+	});
+}
+
+#[test]
+fn bands_fall_back_to_the_network_frequency() {
+	use WifiBand::*;
+
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+
+		let rows = client.ordered_networks(&objects, &device()).await.unwrap();
+		let bands: Vec<_> = rows.iter().map(|row| row.bands.clone()).collect();
+		// café has no access points, only its own frequency
+		assert_eq!(
+			bands,
+			[vec![TwoPointFour, Five], vec![TwoPointFour], vec![Six]]
+		);
+	});
+}
+
+#[test]
+fn ordered_networks_keep_those_missing_from_the_snapshot() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+
+		let rows = client.ordered_networks(&objects, &device()).await.unwrap();
+		assert!(rows.iter().all(|row| row.network.is_some()));
+
+		let empty = IwdObjectMap::default();
+		let unresolved = client.ordered_networks(&empty, &device()).await;
+		let unresolved = unresolved.unwrap();
+		assert_eq!(unresolved.len(), rows.len());
+		for (entry, row) in unresolved.iter().zip(&rows) {
+			assert_eq!(entry.path, row.path);
+			assert_eq!(entry.strength, row.strength);
+			assert_eq!(entry.device, row.device);
+			assert!(entry.network.is_none());
+			assert!(!entry.is_known() && !entry.is_connected());
+		}
+	});
+}
+
+#[test]
+fn scan_finishes() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let station = client.station(&device()).await.unwrap();
+		scan_and_wait(client, &station).await.unwrap();
+		assert!(!station.scanning().await.unwrap());
+	});
+}
+
+#[test]
+fn scan_over_before_the_reply_finishes() {
+	block_on(async {
+		let iwd = MockIwd {
+			scan_time: Duration::ZERO,
+			..MockIwd::default()
+		};
+		let fixture = iwd.fixture().await.unwrap();
+		let client = &fixture.client;
+		let station = client.station(&device()).await.unwrap();
+		scan_and_wait(client, &station).await.unwrap();
+		assert!(!station.scanning().await.unwrap());
+	});
+}
+
+#[test]
+fn fresh_scan_results_are_used() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let station = client.station(&device()).await.unwrap();
+
+		let objects = client.objects().await.unwrap();
+		let (_, s) = objects.stations().next().unwrap();
+		assert_eq!(scan_results_age(s), None);
+		assert!(needs_fresh_scan(s, Duration::from_secs(60)));
+
+		scan_and_wait(client, &station).await.unwrap();
+		let objects = client.objects().await.unwrap();
+		let (_, s) = objects.stations().next().unwrap();
+		assert!(scan_results_age(s).unwrap() < Duration::from_secs(5));
+		assert!(!needs_fresh_scan(s, Duration::from_secs(60)));
+
+		// which it would fail, if it scanned
+		let error = MockError::iwd("NotAvailable", "radio is off");
+		fixture.faults().set("Scan", Fault::error(error));
+		let res = scan_and_collect(client, &station, &objects, false)
+			.await
+			.unwrap();
+		assert_eq!(res.networks.len(), 3);
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn connect_moves_the_station() {
+	block_on(async {
+		let mock = MockIwd::default();
+		let fixture = mock.fixture().await.unwrap();
+		let client = &fixture.client;
+
+		let work = mock.network("work").path();
+		client.connect(&work).await.unwrap();
+
+		let objects = client.objects().await.unwrap();
+		let (_, station) = objects.stations().next().unwrap();
+		assert_eq!(station.state(), StationState::Connected);
+		assert_eq!(station.connected_network().unwrap(), &work);
+		let connected: Vec<_> = objects
+			.networks()
+			.filter(|(_, net)| net.is_connected())
+			.map(|(path, _)| path.clone())
+			.collect();
+		assert_eq!(connected, [work]);
+	});
+}
+
+#[test]
+fn best_known_networks_come_strongest_first() {
+	block_on(async {
+		// not in iwd's order, which `best_known` doesn't rely on
+		let mock = MockIwd {
+			networks: vec![
+				MockNetwork::new("weak", "psk", -8000).known(),
+				MockNetwork::new("open", "open", -4000),
+				MockNetwork::new("strong", "psk", -5000).known(),
+				MockNetwork::new("middle", "8021x", -6500).known(),
+			],
+			connected: None,
+			..MockIwd::default()
+		};
+		let fixture = mock.fixture().await.unwrap();
+		let client = &fixture.client;
+
+		let objects = client.objects().await.unwrap();
+		let rows = client.ordered_networks(&objects, &device()).await.unwrap();
+		let ssids: Vec<_> = best_known(rows)
+			.into_iter()
+			.map(|net| net.ssid().unwrap().to_string())
+			.collect();
+		assert_eq!(ssids, ["strong", "middle", "weak"]);
+	});
+}
+
+#[test]
+fn forget_removes_the_known_network() {
+	block_on(async {
+		let mock = MockIwd::default();
+		let fixture = mock.fixture().await.unwrap();
+		let client = &fixture.client;
+
+		let home = mock.network("home");
+		let known: KnownNetworkProxy =
+			OPath::from(home.known_path()).proxy(client).await.unwrap();
+		known.forget().await.unwrap();
+
+		let objects = client.objects().await.unwrap();
+		assert!(objects
+			.known_networks()
+			.all(|(_, k)| k.name().as_str() != Some("home")));
+		let (_, network) = objects
+			.networks()
+			.find(|(p, _)| **p == home.path())
+			.unwrap();
+		assert!(network.known_network().is_none());
+	});
+}
+
+#[test]
+fn set_auto_connect_writes_the_property() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let home = fixture.iwd.network("home");
+		let known: KnownNetworkProxy = OPath::from(home.known_path())
+			.proxy(&fixture.client)
+			.await
+			.unwrap();
+		assert!(known.auto_connect().await.unwrap());
+
+		known.set_auto_connect(false).await.unwrap();
+		let writes = fixture.auto_connect_writes("home").await.unwrap();
+		assert_eq!(writes, [false]);
+	});
+}
+
+#[test]
+fn finds_known_networks_by_ssid() {
+	block_on(async {
+		let mock = MockIwd::default();
+		let fixture = mock.fixture().await.unwrap();
+		let client = &fixture.client;
+
+		let ssid = |name: &str| Ssid::Utf8(name.to_owned());
+		let (path, known) = find_known_network(client, &ssid("work"))
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(path, mock.network("work").known_path());
+		assert_eq!(known.network_type(), NetworkType::Eap);
+		let unknown = find_known_network(client, &ssid("café")).await.unwrap();
+		assert!(unknown.is_none());
+	});
+}
+
+#[test]
+fn proxies_talk_to_the_clients_service() {
+	block_on(async {
+		let (conn, _server) = MockIwd::default().connect().await.unwrap();
+		let client = IwdClient::with_service(conn, "org.example.iwd")
+			.await
+			.unwrap();
+		assert_eq!(client.service().as_str(), "org.example.iwd");
+		let station = client.station(&device()).await.unwrap();
+		assert_eq!(station.inner().destination(), client.service());
+		// the mock answers whatever the destination
+		assert!(client.objects().await.unwrap().stations().next().is_some());
+	});
+}
+
+#[test]
+fn state_changes_are_signalled() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let station: StationProxy =
+			OPath::from(device()).proxy(client).await.unwrap();
+		// only once the properties are cached, or that counts as a change
+		let state = station.state().await.unwrap();
+		assert_eq!(state, StationState::Connected);
+		let mut changes = station.receive_state_changed().await;
+
+		station.disconnect().await.unwrap();
+		let state = changes.next().await.unwrap().get().await.unwrap();
+		assert_eq!(state, StationState::Disconnected);
+	});
+}
+
+#[test]
+fn disconnecting_gives_a_reason() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+		let (_, station) = objects.stations().next().unwrap();
+		assert_eq!(station.disconnected_reason(), None);
+
+		client
+			.station(&device())
+			.await
+			.unwrap()
+			.disconnect()
+			.await
+			.unwrap();
+		let objects = client.objects().await.unwrap();
+		let (_, station) = objects.stations().next().unwrap();
+		assert_eq!(station.disconnected_reason(), Some("local"));
+	});
+}
+
+#[test]
+fn enumerates_devices() {
+	block_on(async {
+		let (conn, _server) = MockIwd::default().connect().await.unwrap();
+		let devices = enumerate_devices(&conn).await.unwrap();
+
+		assert_eq!(devices.len(), 1);
+		let info = &devices[0];
+		assert_eq!(info.path, device());
+		assert_eq!(info.device.name(), "wlan0");
+		assert_eq!(info.adapter.as_ref().unwrap().name(), "phy0");
+		let station = info.station.as_ref().unwrap();
+		assert_eq!(station.state(), StationState::Connected);
+		let network = info.connected_network.as_ref().unwrap();
+		assert_eq!(network.name().as_str(), Some("home"));
+	});
+}
+
+#[test]
+fn iterates_objects_by_type() {
+	block_on(async {
+		let mock = MockIwd::default();
+		let fixture = mock.fixture().await.unwrap();
+		let objects = fixture.client.objects().await.unwrap();
+
+		let stations: Vec<OPath<StationProxy>> =
+			objects.iter_typed::<Station>().collect();
+		assert_eq!(stations, [device()]);
+		let mut known: Vec<_> = objects.iter_typed::<KnownNetwork>().collect();
+		known.sort();
+		let mut expected: Vec<_> = mock
+			.networks
+			.iter()
+			.filter(|network| network.known)
+			.map(|network| {
+				OPath::<KnownNetworkProxy>::from(network.known_path())
+			})
+			.collect();
+		expected.sort();
+		assert_eq!(known, expected);
+	});
+}
+
+#[test]
+fn calls_run_at_the_same_time() {
+	const DELAY: Duration = Duration::from_millis(300);
+
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		fixture
+			.faults()
+			.set("GetOrderedNetworks", Fault::delay(DELAY));
+		let client = &fixture.client;
+		let objects = client.objects().await.unwrap();
+		let path = device();
+		let station = client.station(&path).await.unwrap();
+
+		let start = Instant::now();
+		let calls =
+			(0..3).map(|_| visible_networks(client, &objects, &path, &station));
+		let results = join_all(calls).await;
+		let elapsed = start.elapsed();
+
+		assert!(elapsed >= DELAY);
+		assert!(elapsed < 2 * DELAY, "took {elapsed:?}");
+		for rows in results {
+			let ssids: Vec<_> = rows
+				.unwrap()
+				.iter()
+				.map(|row| row.ssid().unwrap().to_string())
+				.collect();
+			assert_eq!(ssids, ["home", "café", "work"]);
+		}
+		fixture.close().await.unwrap();
+	});
+}
@@ -0,0 +1,232 @@
+//! [`ObjectStore`] against [`mock::MockIwd`]: when it fetches the objects
+//! again, and what it picks up in between.
+
+mod mock;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use iwd_playground::client::discover_peers;
+use iwd_playground::error::IwdErrorKind;
+use iwd_playground::objects::IwdObjectMap;
+use iwd_playground::opath::OPath;
+use iwd_playground::proxy::KnownNetworkProxy;
+use iwd_playground::store::ObjectStore;
+
+use mock::fault::{Fault, MockError};
+use mock::{block_on, device, MockIwd, MockPeer};
+
+const LONG: Duration = Duration::from_secs(3600);
+
+fn is_connected(objects: &IwdObjectMap) -> bool {
+	let station = objects.get_station(&OPath::from(device())).unwrap();
+	station.connected_network().is_some()
+}
+
+/// Reads `store` until `done` holds of what it reads, or a second passes.
+async fn eventually(
+	store: &ObjectStore,
+	done: impl Fn(&IwdObjectMap) -> bool,
+) -> Arc<IwdObjectMap> {
+	for _ in 0..100 {
+		let objects = store.objects().await.unwrap();
+		if done(&objects) {
+			return objects;
+		}
+		async_io::Timer::after(Duration::from_millis(10)).await;
+	}
+	panic!("the store never caught up");
+}
+
+#[test]
+fn expires_after_its_ttl() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let ttl = Duration::from_millis(200);
+		let store = ObjectStore::new(fixture.client.clone(), ttl);
+		assert!(store.age().await.is_none());
+
+		let first = store.objects().await.unwrap();
+		assert!(is_connected(&first));
+		let fetched_at = store.fetched_at().await.unwrap();
+
+		fixture.set_connected(None).await.unwrap();
+		let cached = store.objects().await.unwrap();
+		assert!(Arc::ptr_eq(&first, &cached));
+		assert!(is_connected(&cached));
+
+		async_io::Timer::after(ttl).await;
+		let fetched = store.objects().await.unwrap();
+		assert!(!is_connected(&fetched));
+		assert!(store.fetched_at().await.unwrap() > fetched_at);
+		// what was read before stays as it was
+		assert!(is_connected(&first));
+
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn follows_signals_while_subscribed() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let store = ObjectStore::new(fixture.client.clone(), LONG);
+		let mut updates = store.subscribe().await.unwrap();
+		let fetched_at = store.fetched_at().await.unwrap();
+
+		let body = async {
+			let home = fixture.iwd.network("home");
+			let known: KnownNetworkProxy = OPath::from(home.known_path())
+				.proxy(&fixture.client)
+				.await
+				.unwrap();
+			known.forget().await.unwrap();
+			let objects = eventually(&store, |objects| {
+				objects.object(&home.known_path()).is_none()
+			})
+			.await;
+			let network = objects.get_network(&OPath::from(home.path()));
+			assert!(network.unwrap().known_network().is_none());
+
+			fixture.set_connected(None).await.unwrap();
+			eventually(&store, |objects| !is_connected(objects)).await;
+			assert_eq!(store.fetched_at().await, Some(fetched_at));
+		};
+		let run = async {
+			updates.run().await.unwrap();
+			panic!("the updates stopped");
+		};
+		futures_lite::future::or(body, run).await;
+		drop(updates);
+
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn follows_while_any_updates_run() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let ttl = Duration::from_millis(200);
+		let store = ObjectStore::new(fixture.client.clone(), ttl);
+		let mut running = store.subscribe().await.unwrap();
+		let idle = store.subscribe().await.unwrap();
+		let fetched_at = store.fetched_at().await.unwrap();
+
+		let body = async {
+			drop(idle);
+			async_io::Timer::after(ttl).await;
+			store.objects().await.unwrap();
+			assert_eq!(store.fetched_at().await, Some(fetched_at));
+		};
+		let run = async {
+			running.run().await.unwrap();
+			panic!("the updates stopped");
+		};
+		futures_lite::future::or(body, run).await;
+
+		// with none running, it expires again
+		store.objects().await.unwrap();
+		assert!(store.fetched_at().await.unwrap() > fetched_at);
+		drop(running);
+
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn refetches_invalidated_objects_alone() {
+	block_on(async {
+		let fixture = MockIwd::default().fixture().await.unwrap();
+		let store = ObjectStore::new(fixture.client.clone(), LONG);
+		store.objects().await.unwrap();
+		let fetched_at = store.fetched_at().await.unwrap();
+
+		let home = fixture.iwd.network("home");
+		let known: KnownNetworkProxy = OPath::from(home.known_path())
+			.proxy(&fixture.client)
+			.await
+			.unwrap();
+		known.forget().await.unwrap();
+		let cached = store.objects().await.unwrap();
+		assert!(cached.object(&home.known_path()).is_some());
+
+		store.invalidate(&home.known_path()).await;
+		store.invalidate(&home.path()).await;
+		let objects = store.objects().await.unwrap();
+		assert!(objects.object(&home.known_path()).is_none());
+		let network = objects.get_network(&OPath::from(home.path())).unwrap();
+		assert!(network.known_network().is_none());
+		assert!(is_connected(&objects));
+		assert_eq!(store.fetched_at().await, Some(fetched_at));
+
+		fixture.close().await.unwrap();
+	});
+}
+
+fn with_peers() -> MockIwd {
+	MockIwd {
+		p2p: Some(vec![
+			MockPeer::new("living room", "02:00:00:00:05:01", "display"),
+			MockPeer::new("laptop", "02:00:00:00:05:02", "computer"),
+		]),
+		..MockIwd::default()
+	}
+}
+
+#[test]
+fn discovers_peers_until_told_to_stop() {
+	block_on(async {
+		let fixture = with_peers().fixture().await.unwrap();
+		let store = ObjectStore::new(fixture.client.clone(), LONG);
+		let before = store.objects().await.unwrap();
+		let device = before.p2p_devices().next().unwrap();
+		assert_eq!(before.peers_for_device(&device).count(), 0);
+		let proxy = device.clone().proxy(&fixture.client).await.unwrap();
+
+		let found = |objects: &IwdObjectMap| {
+			objects.peers_for_device(&device).count() == 2
+		};
+		let objects = discover_peers(&store, &proxy, async {
+			eventually(&store, found).await;
+		})
+		.await
+		.unwrap();
+		let mut names: Vec<_> = objects
+			.peers_for_device(&device)
+			.map(|(_, peer)| (peer.name(), peer.device_category()))
+			.collect();
+		names.sort();
+		assert_eq!(
+			names,
+			[
+				("laptop", Some("computer")),
+				("living room", Some("display")),
+			]
+		);
+		assert!(!fixture.is_discovering().await.unwrap());
+
+		fixture.close().await.unwrap();
+	});
+}
+
+#[test]
+fn discovery_fails_without_support() {
+	block_on(async {
+		let fixture = with_peers().fixture().await.unwrap();
+		let error = MockError::iwd("NotSupported", "Operation not supported");
+		fixture
+			.faults()
+			.set("RequestDiscovery", Fault::error(error));
+		let store = ObjectStore::new(fixture.client.clone(), LONG);
+		let objects = store.objects().await.unwrap();
+		let device = objects.p2p_devices().next().unwrap();
+		let proxy = device.proxy(&fixture.client).await.unwrap();
+
+		let err = discover_peers(&store, &proxy, async {}).await.unwrap_err();
+		assert!(err.is_iwd(IwdErrorKind::NotSupported), "{err}");
+		assert!(!fixture.is_discovering().await.unwrap());
+
+		fixture.close().await.unwrap();
+	});
+}
@@ -0,0 +1,143 @@
+//! Known network profiles, the files iwd keeps them in.
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use iwd_playground::profile::ProfileConfig;
+use iwd_playground::types::KnownNetwork;
+use zbus::zvariant::serialized::Context;
+use zbus::zvariant::{self, Value};
+
+/// A known network called `name`, the way iwd reports it.
+fn known(name: &str, type_: &str) -> KnownNetwork {
+	let properties: HashMap<_, _> = [
+		("Name", Value::from(name)),
+		("Type", Value::from(type_)),
+		("Hidden", Value::from(false)),
+		("LastConnectedTime", Value::from("2024-03-01T10:00:00Z")),
+		("AutoConnect", Value::from(true)),
+	]
+	.into();
+	let data =
+		zvariant::to_bytes(Context::new_dbus(zvariant::LE, 0), &properties)
+			.unwrap();
+	data.deserialize().unwrap().0
+}
+
+#[test]
+fn plain_ssids_name_the_file() {
+	let cases = [
+		("home", "psk", "home.psk"),
+		("My Home-5G_2", "open", "My Home-5G_2.open"),
+		("eduroam", "8021x", "eduroam.8021x"),
+	];
+	for (name, type_, file) in cases {
+		let path = Path::new("/var/lib/iwd").join(file);
+		assert_eq!(known(name, type_).profile_path(), Some(path), "{name:?}");
+	}
+}
+
+#[test]
+fn other_ssids_are_hex_encoded() {
+	// iwd doesn't escape single characters: one outside of the plain set
+	// makes it name the file `=` and the whole SSID in hex
+	let cases = [
+		("a=b", "psk", "=613d62.psk"),
+		("x/y", "open", "=782f79.open"),
+		("../etc", "psk", "=2e2e2f657463.psk"),
+		("café", "8021x", "=636166c3a9.8021x"),
+		("home.", "psk", "=686f6d652e.psk"),
+	];
+	for (name, type_, file) in cases {
+		let path = Path::new("/var/lib/iwd").join(file);
+		assert_eq!(known(name, type_).profile_path(), Some(path), "{name:?}");
+	}
+}
+
+#[test]
+fn no_profile_for_wep_and_hotspots() {
+	assert_eq!(known("old", "wep").profile_path(), None);
+	assert_eq!(known("hotspot", "hotspot").profile_path(), None);
+}
+
+/// A directory of its own for `name`, empty.
+fn temp_dir(name: &str) -> PathBuf {
+	let pid = std::process::id();
+	let dir = std::env::temp_dir().join(format!("iwd-playground-{pid}-{name}"));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+const PROFILE: &str = "\
+# written by hand
+[Security]
+Passphrase = correct horse
+
+[IPv4]
+Address=192.168.1.10
+Gateway=192.168.1.1
+
+[Settings]
+AutoConnect=false
+Hidden=true
+[Security]
+PreSharedKey=0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef
+";
+
+/// [`PROFILE`], as it's written back.
+const WRITTEN: &str = "\
+[Security]
+PreSharedKey=0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef
+Passphrase=correct horse
+
+[Settings]
+AutoConnect=false
+Hidden=true
+
+[IPv4]
+Address=192.168.1.10
+Gateway=192.168.1.1
+";
+
+#[test]
+fn profiles_round_trip() {
+	let profile = ProfileConfig::parse(PROFILE).unwrap();
+	assert_eq!(
+		profile.security.passphrase.as_deref(),
+		Some("correct horse")
+	);
+	assert_eq!(profile.settings.auto_connect, Some(false));
+	assert_eq!(profile.settings.hidden, Some(true));
+
+	let written = profile.to_string();
+	assert_eq!(written, WRITTEN);
+	assert_eq!(ProfileConfig::parse(&written).unwrap(), profile);
+
+	let dir = temp_dir("round-trip");
+	let path = dir.join("home.psk");
+	profile.save(&path).unwrap();
+	assert_eq!(fs::read_to_string(&path).unwrap(), written);
+	assert_eq!(ProfileConfig::load(&path).unwrap(), profile);
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saved_profiles_are_private() {
+	let mode = |path: &Path| fs::metadata(path).unwrap().mode() & 0o777;
+	let profile = ProfileConfig::parse(PROFILE).unwrap();
+	let dir = temp_dir("private");
+
+	let path = dir.join("home.psk");
+	profile.save(&path).unwrap();
+	assert_eq!(mode(&path), 0o600);
+
+	let path = dir.join("work.psk");
+	fs::write(&path, "[Settings]\nAutoConnect=true\n").unwrap();
+	fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+	profile.save(&path).unwrap();
+	assert_eq!(mode(&path), 0o600);
+	fs::remove_dir_all(dir).unwrap();
+}
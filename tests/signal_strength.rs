@@ -0,0 +1,71 @@
+use iwd_playground::types::SignalStrength;
+
+#[test]
+fn dbm_and_centi_dbm() {
+	let strength = SignalStrength::from_centi_dbm(-5400);
+	assert_eq!(strength, SignalStrength::from_dbm(-54));
+	assert_eq!(strength.centi_dbm(), -5400);
+	assert_eq!(SignalStrength::from_centi_dbm(-5450).to_string(), "-54 dBm");
+	assert_eq!(SignalStrength::from_dbm(i16::MIN).centi_dbm(), i16::MIN);
+}
+
+#[test]
+fn quality_label_boundaries() {
+	let cases = [
+		(0, "Excellent"),
+		(-49, "Excellent"),
+		(-50, "Good"),
+		(-60, "Good"),
+		(-61, "Fair"),
+		(-70, "Fair"),
+		(-71, "Poor"),
+		(-80, "Poor"),
+		(-81, "Very Poor"),
+		(i16::MIN, "Very Poor"),
+	];
+	for (dbm, label) in cases {
+		let strength = SignalStrength::from_dbm(dbm);
+		assert_eq!(strength.quality_label(), label, "{dbm} dBm");
+	}
+}
+
+#[test]
+fn percent_boundaries() {
+	let cases = [
+		(i16::MIN, 0),
+		(-150, 0),
+		(-101, 0),
+		(-100, 0),
+		(-99, 2),
+		(-75, 50),
+		(-51, 98),
+		(-50, 100),
+		(-49, 100),
+		(0, 100),
+		(i16::MAX, 100),
+	];
+	for (dbm, percent) in cases {
+		let strength = SignalStrength::from_dbm(dbm);
+		assert_eq!(strength.percent(), percent, "{dbm} dBm");
+	}
+}
+
+#[test]
+fn bars_unicode_boundaries() {
+	// a quarter of the 50 dBm between -100 and -50 dBm per bar
+	let cases = [
+		(-100, " "),
+		(-99, "▂"),
+		(-88, "▂"),
+		(-87, "▂▄"),
+		(-75, "▂▄"),
+		(-74, "▂▄▆"),
+		(-63, "▂▄▆"),
+		(-62, "▂▄▆█"),
+		(-30, "▂▄▆█"),
+	];
+	for (dbm, bars) in cases {
+		let strength = SignalStrength::from_dbm(dbm);
+		assert_eq!(strength.bars_unicode(), bars, "{dbm} dBm");
+	}
+}
@@ -0,0 +1,67 @@
+use iwd_playground::types::WifiBand;
+
+#[test]
+fn bands_by_frequency() {
+	use WifiBand::*;
+
+	for (mhz, band) in [
+		// channel 1, 13, and Japan's 14
+		(2400, Some(TwoPointFour)),
+		(2412, Some(TwoPointFour)),
+		(2472, Some(TwoPointFour)),
+		(2484, Some(TwoPointFour)),
+		(2500, Some(TwoPointFour)),
+		// 4.9 GHz public safety, channels 36 and 165, and 177
+		(4900, Some(Five)),
+		(4915, Some(Five)),
+		(4940, Some(Five)),
+		(5180, Some(Five)),
+		(5825, Some(Five)),
+		(5885, Some(Five)),
+		(5900, Some(Five)),
+		// 6 GHz channels 2 (the lowest), 1 and 233
+		(5925, Some(Six)),
+		(5935, Some(Six)),
+		(5955, Some(Six)),
+		(7115, Some(Six)),
+		(7125, Some(Six)),
+		// between and outside of them
+		(2399, None),
+		(2501, None),
+		(4899, None),
+		(5910, None),
+		(5924, None),
+		(7126, None),
+		(0, None),
+	] {
+		assert_eq!(WifiBand::from_frequency(mhz), band, "{mhz} MHz");
+	}
+}
+
+#[test]
+fn joins_bands() {
+	use WifiBand::*;
+
+	assert_eq!(WifiBand::joined(&[]), "");
+	assert_eq!(WifiBand::joined(&[Five]), "5");
+	assert_eq!(WifiBand::joined(&[TwoPointFour, Five, Six]), "2.4+5+6");
+}
+
+#[test]
+fn displays_bands_in_ghz() {
+	use WifiBand::*;
+
+	let shown = [TwoPointFour, Five, Six].map(|band| band.to_string());
+	assert_eq!(shown, ["2.4 GHz", "5 GHz", "6 GHz"]);
+}
+
+#[test]
+fn higher_bands_reach_less_far() {
+	use WifiBand::*;
+
+	let ranges = [TwoPointFour, Five, Six].map(|b| b.typical_range_meters());
+	assert!(
+		ranges.windows(2).all(|pair| pair[0] > pair[1]),
+		"{ranges:?}"
+	);
+}
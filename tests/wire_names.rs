@@ -0,0 +1,95 @@
+//! The enums are spelled the way iwd spells them: by serde, both ways, on
+//! D-Bus and in JSON, and by `Display` and `FromStr`.
+
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+use iwd_playground::types::{DeviceMode, NetworkType, StationState};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zbus::zvariant::serialized::Context;
+use zbus::zvariant::{self, Type};
+
+// Each match lists the variants again, so that adding one fails to compile
+// until it's in the list too.
+
+fn states() -> Vec<StationState> {
+	use StationState::*;
+
+	let all = vec![Connected, Disconnected, Connecting, Disconnecting, Roaming];
+	for state in &all {
+		match state {
+			Connected | Disconnected | Connecting | Disconnecting | Roaming => {
+			}
+		}
+	}
+	all
+}
+
+fn modes() -> Vec<DeviceMode> {
+	use DeviceMode::*;
+
+	let all = vec![AdHoc, Station, Ap];
+	for mode in &all {
+		match mode {
+			AdHoc | Station | Ap => {}
+		}
+	}
+	all
+}
+
+fn types() -> Vec<NetworkType> {
+	use NetworkType::*;
+
+	let all = vec![Open, Wep, Psk, Eap, Hotspot];
+	for type_ in &all {
+		match type_ {
+			Open | Wep | Psk | Eap | Hotspot => {}
+		}
+	}
+	all
+}
+
+fn assert_agrees<T>(variants: &[T])
+where
+	T: Display + FromStr + Serialize + DeserializeOwned + Type,
+	T: PartialEq + Debug,
+{
+	let ctxt = Context::new_dbus(zvariant::LE, 0);
+	assert_eq!(T::signature(), "s");
+
+	for variant in variants {
+		let name = variant.to_string();
+
+		let wire = serde_json::to_value(variant).unwrap();
+		assert_eq!(wire, name.as_str(), "{variant:?}");
+		assert_eq!(&serde_json::from_value::<T>(wire).unwrap(), variant);
+
+		let data = zvariant::to_bytes(ctxt, variant).unwrap();
+		let string = zvariant::to_bytes(ctxt, &name).unwrap();
+		assert_eq!(data.bytes(), string.bytes(), "{variant:?}");
+		assert_eq!(&data.deserialize::<T>().unwrap().0, variant);
+
+		assert_eq!(name.parse::<T>().ok().as_ref(), Some(variant));
+		assert_eq!(
+			name.to_uppercase().parse::<T>().ok().as_ref(),
+			Some(variant)
+		);
+	}
+}
+
+#[test]
+fn station_state() {
+	assert_agrees(&states());
+}
+
+#[test]
+fn device_mode() {
+	assert_agrees(&modes());
+}
+
+#[test]
+fn network_type() {
+	assert_agrees(&types());
+	assert!("eap".parse::<NetworkType>().is_err());
+}
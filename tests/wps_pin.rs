@@ -0,0 +1,30 @@
+use iwd_playground::types::{Provisioning, WpsPin};
+
+#[test]
+fn pins_are_4_or_8_digits() {
+	for pin in ["1234", "0000", "12345670"] {
+		assert_eq!(pin.parse::<WpsPin>().unwrap().as_str(), pin);
+	}
+	for pin in [
+		"",
+		"123",
+		"12345",
+		"1234567",
+		"123456789",
+		"1234567a",
+		"+123",
+	] {
+		assert!(pin.parse::<WpsPin>().is_err(), "{pin:?}");
+	}
+	// digits, but not ASCII ones
+	assert!("١٢٣٤".parse::<WpsPin>().is_err());
+}
+
+#[test]
+fn provisioning_as_connect_takes_it() {
+	let pin: WpsPin = "12345670".parse().unwrap();
+	let by_pin = Provisioning::Pin(pin);
+	assert_eq!((by_pin.method(), by_pin.pin()), ("pin", "12345670"));
+	let by_button = Provisioning::PushButton;
+	assert_eq!((by_button.method(), by_button.pin()), ("pushbutton", ""));
+}